@@ -0,0 +1,118 @@
+use std::rc::Rc;
+
+use drowsed_math::TransformQuaternion3D;
+
+use crate::{motor::scene_manager::SceneManager, components::entity::Entity, ecs::sparse_set::SparseSet};
+
+use super::clip::AnimationClip;
+
+/// what happens when an animation gets to the end of its clip.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LoopMode {
+    /// stops on the last frame.
+    #[default]
+    Once,
+    /// starts over from the beginning.
+    Loop,
+    /// plays backwards to the beginning, then forwards again.
+    PingPong,
+}
+
+/// # Animation
+/// a clip playing on an object. Clips are shared, so many objects can play
+/// the same one at different times.
+#[derive(Clone)]
+pub struct Animation {
+    pub clip: Rc<AnimationClip>,
+    pub mode: LoopMode,
+    /// multiplies the delta time, negative plays backwards.
+    pub speed: f32,
+    pub playing: bool,
+    /// seconds played, not wrapped around the duration of the clip.
+    elapsed: f32,
+    /// the transform the clip is applied on top of, taken when the animation starts.
+    base: Option<TransformQuaternion3D>,
+}
+
+impl Animation {
+    pub fn new(clip: Rc<AnimationClip>, mode: LoopMode) -> Self {
+        Self { clip, mode, speed: 1.0, playing: true, elapsed: 0.0, base: None }
+    }
+    /// the time in the clip the animation is at.
+    pub fn time(&self) -> f32 {
+        let duration = self.clip.duration();
+        if duration <= 0.0 {
+            return 0.0;
+        }
+        match self.mode {
+            LoopMode::Once => self.elapsed.clamp(0.0, duration),
+            LoopMode::Loop => self.elapsed.rem_euclid(duration),
+            LoopMode::PingPong => {
+                let time = self.elapsed.rem_euclid(duration * 2.0);
+                if time > duration { duration * 2.0 - time } else { time }
+            }
+        }
+    }
+    pub fn seek(&mut self, time: f32) {
+        self.elapsed = time;
+    }
+    /// whether a [`LoopMode::Once`] animation got to the end, looping ones never finish.
+    pub fn is_finished(&self) -> bool {
+        self.mode == LoopMode::Once && (self.elapsed >= self.clip.duration() || (self.speed < 0.0 && self.elapsed <= 0.0))
+    }
+    pub fn advance(&mut self, deltatime: f32) {
+        if self.playing && !self.is_finished() {
+            self.elapsed += deltatime * self.speed;
+        }
+    }
+}
+
+/// # AnimationSystem
+/// Plays animations on objects, writing their local transforms. Runs before
+/// propagation so children follow their animated parents in the same frame.
+/// Finished animations stop writing and are left stopped on the last frame.
+pub struct AnimationSystem {
+    animations: SparseSet<Animation>,
+}
+
+impl AnimationSystem {
+    pub fn new() -> Self {
+        Self { animations: SparseSet::new() }
+    }
+    /// starts playing `animation` on the object, replacing the one it was playing.
+    pub fn push(&mut self, id: Entity, animation: Animation) {
+        self.animations.insert(id, animation);
+    }
+    pub fn remove(&mut self, id: Entity) -> Option<Animation> {
+        self.animations.remove(id)
+    }
+    pub fn get(&self, id: Entity) -> Option<&Animation> {
+        self.animations.get(id)
+    }
+    pub fn get_mut(&mut self, id: Entity) -> Option<&mut Animation> {
+        self.animations.get_mut(id)
+    }
+    pub fn render(&mut self, deltatime: f32, scenemanager: &mut SceneManager<TransformQuaternion3D>) {
+        for id in self.animations.entities().to_vec() {
+            let (Some(object), Some(animation)) = (scenemanager.get_object_by_id_mut(id), self.animations.get_mut(id)) else {
+                continue;
+            };
+            if !animation.playing || animation.is_finished() {
+                continue;
+            }
+            let base = *animation.base.get_or_insert(object.transform());
+            animation.advance(deltatime);
+            *object.transform_mut() = animation.clip.sample(animation.time(), &base);
+            // the last frame was written, from now on the transform belongs to whatever else moves it
+            if animation.is_finished() {
+                animation.playing = false;
+            }
+        }
+    }
+}
+
+impl Default for AnimationSystem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
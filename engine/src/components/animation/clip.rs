@@ -0,0 +1,249 @@
+use drowsed_math::{FVec3, TransformQuaternion3D, complex::quaternion::Quaternion, Vector, EuclideanGeometry};
+
+use super::easing::Easing;
+
+/// how values between two keyframes are found.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Interpolation {
+    /// holds the value of the previous keyframe until the next one.
+    Step,
+    #[default]
+    Linear,
+    /// smooth curve through the keyframes, with Catmull-Rom tangents.
+    /// Rotations are always slerped, so this is the same as `Linear` for them.
+    CubicHermite,
+}
+
+/// # Keyframe
+/// `value` at `time` seconds into the clip, `easing` shapes the way to the next keyframe.
+#[derive(Debug, Clone, Copy)]
+pub struct Keyframe<V> {
+    pub time: f32,
+    pub value: V,
+    pub easing: Easing,
+}
+
+impl<V> Keyframe<V> {
+    pub fn new(time: f32, value: V) -> Self {
+        Self { time, value, easing: Easing::Linear }
+    }
+    pub fn eased(time: f32, value: V, easing: Easing) -> Self {
+        Self { time, value, easing }
+    }
+}
+
+/// # Track
+/// keyframes of a single property, kept sorted by time.
+#[derive(Debug, Clone)]
+pub struct Track<V> {
+    keyframes: Vec<Keyframe<V>>,
+    pub interpolation: Interpolation,
+}
+
+/// where a time falls in a track: between keyframe `index` and the next one, `t` of the way there.
+struct Position {
+    index: usize,
+    t: f32,
+}
+
+impl<V: Copy> Track<V> {
+    pub fn new(interpolation: Interpolation) -> Self {
+        Self { keyframes: vec![], interpolation }
+    }
+    pub fn with_keyframes(interpolation: Interpolation, keyframes: impl IntoIterator<Item = Keyframe<V>>) -> Self {
+        let mut track = Self::new(interpolation);
+        for keyframe in keyframes {
+            track.insert(keyframe);
+        }
+        track
+    }
+    /// adds the keyframe, replacing one at the same time.
+    pub fn insert(&mut self, keyframe: Keyframe<V>) {
+        match self.keyframes.binary_search_by(|other| other.time.total_cmp(&keyframe.time)) {
+            Ok(index) => self.keyframes[index] = keyframe,
+            Err(index) => self.keyframes.insert(index, keyframe),
+        }
+    }
+    pub fn keyframes(&self) -> &[Keyframe<V>] {
+        &self.keyframes
+    }
+    /// time of the last keyframe.
+    pub fn duration(&self) -> f32 {
+        self.keyframes.last().map_or(0.0, |keyframe| keyframe.time)
+    }
+    /// `None` when there are no keyframes, the first or last value outside of them.
+    fn position(&self, time: f32) -> Option<Position> {
+        let first = self.keyframes.first()?;
+        if time <= first.time || self.keyframes.len() == 1 {
+            return Some(Position { index: 0, t: 0.0 });
+        }
+        let next = self.keyframes.partition_point(|keyframe| keyframe.time <= time);
+        if next >= self.keyframes.len() {
+            return Some(Position { index: self.keyframes.len() - 1, t: 0.0 });
+        }
+        let (from, to) = (&self.keyframes[next - 1], &self.keyframes[next]);
+        let t = (time - from.time) / (to.time - from.time);
+        let t = match self.interpolation {
+            Interpolation::Step => 0.0,
+            _ => from.easing.apply(t),
+        };
+        Some(Position { index: next - 1, t })
+    }
+}
+
+impl Track<FVec3> {
+    /// the tangent of the curve at keyframe `index`, in change per second.
+    fn tangent(&self, index: usize) -> FVec3 {
+        let previous = &self.keyframes[index.saturating_sub(1)];
+        let next = &self.keyframes[(index + 1).min(self.keyframes.len() - 1)];
+        if next.time <= previous.time {
+            return FVec3::from(0.0);
+        }
+        (next.value - previous.value) * (1.0 / (next.time - previous.time))
+    }
+    pub fn sample(&self, time: f32) -> Option<FVec3> {
+        let Position { index, t } = self.position(time)?;
+        let from = &self.keyframes[index];
+        if t == 0.0 {
+            return Some(from.value);
+        }
+        let to = &self.keyframes[index + 1];
+        Some(match self.interpolation {
+            Interpolation::Step => from.value,
+            Interpolation::Linear => from.value + (to.value - from.value) * t,
+            Interpolation::CubicHermite => {
+                let span = to.time - from.time;
+                let (t2, t3) = (t * t, t * t * t);
+                from.value * (2.0 * t3 - 3.0 * t2 + 1.0)
+                    + self.tangent(index) * ((t3 - 2.0 * t2 + t) * span)
+                    + to.value * (-2.0 * t3 + 3.0 * t2)
+                    + self.tangent(index + 1) * ((t3 - t2) * span)
+            }
+        })
+    }
+}
+
+impl Track<Quaternion<f32>> {
+    pub fn sample(&self, time: f32) -> Option<Quaternion<f32>> {
+        let Position { index, t } = self.position(time)?;
+        let from = &self.keyframes[index];
+        if t == 0.0 || self.interpolation == Interpolation::Step {
+            return Some(from.value);
+        }
+        Some(slerp(from.value, self.keyframes[index + 1].value, t))
+    }
+}
+
+/// # slerp
+/// rotates from `a` towards `b` at a constant speed, along the shortest way.
+/// Goes through the rotation between the two as an angle and an axis, which
+/// always comes out as the shortest way since the angle is at most half a turn.
+pub fn slerp(a: Quaternion<f32>, b: Quaternion<f32>, t: f32) -> Quaternion<f32> {
+    let difference = a.conjugate() * b;
+    // columns of the rotation matrix of `difference`
+    let x = difference * FVec3::new(1.0, 0.0, 0.0);
+    let y = difference * FVec3::new(0.0, 1.0, 0.0);
+    let z = difference * FVec3::new(0.0, 0.0, 1.0);
+    let angle = ((x.x + y.y + z.z - 1.0) / 2.0).clamp(-1.0, 1.0).acos();
+    if angle < 1e-5 {
+        return a;
+    }
+    let skew = FVec3::new(y.z - z.y, z.x - x.z, x.y - y.x);
+    let axis = if skew.length() > 1e-4 {
+        skew.normalize()
+    } else {
+        // half a turn, the matrix is symmetric so the axis comes from its diagonal
+        let axis = FVec3::new(
+            ((x.x + 1.0) / 2.0).max(0.0).sqrt(),
+            ((y.y + 1.0) / 2.0).max(0.0).sqrt(),
+            ((z.z + 1.0) / 2.0).max(0.0).sqrt(),
+        );
+        if axis.x >= axis.y && axis.x >= axis.z {
+            FVec3::new(axis.x, axis.y.copysign(x.y), axis.z.copysign(x.z))
+        } else if axis.y >= axis.z {
+            FVec3::new(axis.x.copysign(x.y), axis.y, axis.z.copysign(y.z))
+        } else {
+            FVec3::new(axis.x.copysign(x.z), axis.y.copysign(y.z), axis.z)
+        }
+    };
+    a * Quaternion::<f32>::angle_axis(angle * t, axis)
+}
+
+/// # AnimationClip
+/// Tracks for the translation, rotation and scale of an object. Properties
+/// without a track are left the way they are.
+#[derive(Clone, Default)]
+pub struct AnimationClip {
+    pub translation: Option<Track<FVec3>>,
+    pub rotation: Option<Track<Quaternion<f32>>>,
+    pub scale: Option<Track<FVec3>>,
+}
+
+impl AnimationClip {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn with_translation(mut self, track: Track<FVec3>) -> Self {
+        self.translation = Some(track);
+        self
+    }
+    pub fn with_rotation(mut self, track: Track<Quaternion<f32>>) -> Self {
+        self.rotation = Some(track);
+        self
+    }
+    pub fn with_scale(mut self, track: Track<FVec3>) -> Self {
+        self.scale = Some(track);
+        self
+    }
+    /// the time of the last keyframe of any track.
+    pub fn duration(&self) -> f32 {
+        let translation = self.translation.as_ref().map_or(0.0, Track::duration);
+        let rotation = self.rotation.as_ref().map_or(0.0, Track::duration);
+        let scale = self.scale.as_ref().map_or(0.0, Track::duration);
+        translation.max(rotation).max(scale)
+    }
+    /// `base` with every animated property set to its value at `time`.
+    pub fn sample(&self, time: f32, base: &TransformQuaternion3D) -> TransformQuaternion3D {
+        let mut transform = *base;
+        if let Some(translation) = self.translation.as_ref().and_then(|track| track.sample(time)) {
+            transform.translation = translation;
+        }
+        if let Some(rotation) = self.rotation.as_ref().and_then(|track| track.sample(time)) {
+            transform.rotation = rotation;
+        }
+        if let Some(scale) = self.scale.as_ref().and_then(|track| track.sample(time)) {
+            transform.scale = scale;
+        }
+        transform
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// quaternions and their negation are the same rotation, so compare what they do to the axes.
+    fn assert_same_rotation(a: Quaternion<f32>, b: Quaternion<f32>) {
+        for axis in [FVec3::new(1.0, 0.0, 0.0), FVec3::new(0.0, 1.0, 0.0), FVec3::new(0.0, 0.0, 1.0)] {
+            let difference = (a * axis - b * axis).length();
+            assert!(difference < 1e-4, "rotations differ by {difference}");
+        }
+    }
+
+    #[test]
+    fn slerp_starts_at_a_and_ends_at_b() {
+        let a = Quaternion::<f32>::angle_axis(0.3, FVec3::new(0.0, 1.0, 0.0));
+        let b = Quaternion::<f32>::angle_axis(1.2, FVec3::new(0.0, 0.0, 1.0));
+        assert_same_rotation(slerp(a, b, 0.0), a);
+        assert_same_rotation(slerp(a, b, 1.0), b);
+    }
+
+    #[test]
+    fn slerp_turns_at_a_constant_speed() {
+        let axis = FVec3::new(0.0, 1.0, 0.0);
+        let a = Quaternion::<f32>::angle_axis(0.2, axis);
+        let b = Quaternion::<f32>::angle_axis(1.0, axis);
+        assert_same_rotation(slerp(a, b, 0.5), Quaternion::<f32>::angle_axis(0.6, axis));
+        assert_same_rotation(slerp(a, b, 0.25), Quaternion::<f32>::angle_axis(0.4, axis));
+    }
+}
@@ -0,0 +1,49 @@
+use std::f32::consts::PI;
+
+/// # Easing
+/// Reshapes the progress between two keyframes, `In` curves start slow,
+/// `Out` curves end slow and `InOut` curves do both.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Easing {
+    #[default]
+    Linear,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    SineIn,
+    SineOut,
+    SineInOut,
+    /// overshoots a little before settling, `s` is how much (1.70158 is the usual amount).
+    BackOut { s: f32 },
+}
+
+impl Easing {
+    /// maps `t` in 0..=1 to the eased progress, also 0 at 0 and 1 at 1.
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match *self {
+            Self::Linear => t,
+            Self::QuadIn => t * t,
+            Self::QuadOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Self::QuadInOut => if t < 0.5 {
+                2.0 * t * t
+            } else {
+                1.0 - (-2.0 * t + 2.0).powi(2) / 2.0
+            },
+            Self::CubicIn => t * t * t,
+            Self::CubicOut => 1.0 - (1.0 - t).powi(3),
+            Self::CubicInOut => if t < 0.5 {
+                4.0 * t * t * t
+            } else {
+                1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+            },
+            Self::SineIn => 1.0 - (t * PI / 2.0).cos(),
+            Self::SineOut => (t * PI / 2.0).sin(),
+            Self::SineInOut => -((t * PI).cos() - 1.0) / 2.0,
+            Self::BackOut { s } => 1.0 + (s + 1.0) * (t - 1.0).powi(3) + s * (t - 1.0).powi(2),
+        }
+    }
+}
@@ -0,0 +1,3 @@
+pub mod easing;
+pub mod clip;
+pub mod animation_system;
@@ -0,0 +1,153 @@
+use std::{cell::Ref, collections::HashSet};
+
+use drowsed_math::{TransformMatrix, Vector};
+use mofongo::{bodies::RigidBody, collider::CollisionInfo};
+use winit::event::VirtualKeyCode;
+use yum_mocha::vk_obj::rendering::mesh::{Vertex, VulkanIndexable};
+
+use crate::{motor::{system_manager::SystemManager, events::EventReader}, ecs::sparse_set::SparseSet};
+
+use super::{entity::Entity, object::BasicObject, collisions::collision_system::CollisionEvent};
+
+/// # BehaviourContext
+/// what a [`Behaviour`] gets to work with, the object it is attached to and
+/// the whole [`SystemManager`] for the scene, physics, input and events.
+pub struct BehaviourContext<'a, V: Vertex, E: Vector, I: VulkanIndexable, T: TransformMatrix<f32>, R: RigidBody<Transformation = T>> {
+    pub id: Entity,
+    pub systems: &'a mut SystemManager<V, E, I, T, R>,
+}
+
+impl<'a, V: Vertex + 'static, E: Vector + 'static, I: VulkanIndexable + 'static, T: TransformMatrix<f32> + 'static, R: RigidBody<Transformation = T> + 'static> BehaviourContext<'a, V, E, I, T, R> {
+    pub fn object(&self) -> Option<Ref<BasicObject<T>>> {
+        self.systems.get_object(&self.id)
+    }
+    pub fn object_mut(&mut self) -> Option<&mut BasicObject<T>> {
+        self.systems.get_object_mut(&self.id)
+    }
+    pub fn rigidbody_mut(&mut self) -> Option<&mut R> {
+        self.systems.get_rigidbody_mut(&self.id)
+    }
+    /// false when running without input, like in a headless motor.
+    pub fn is_pressed(&self, key: VirtualKeyCode) -> bool {
+        self.systems.input.as_ref().map_or(false, |input| input.lock().unwrap().is_pressed(key))
+    }
+}
+
+/// # Behaviour
+/// Logic attached to an object, driven by the [`BehaviourSystem`] once a frame.
+/// `on_start` runs the first frame the behaviour is around, before its first `on_update`.
+pub trait Behaviour<V: Vertex, E: Vector, I: VulkanIndexable, T: TransformMatrix<f32>, R: RigidBody<Transformation = T>> {
+    fn on_start(&mut self, _context: &mut BehaviourContext<V, E, I, T, R>) {}
+    fn on_update(&mut self, _context: &mut BehaviourContext<V, E, I, T, R>, _deltatime: R::DeltaTime) {}
+    /// called before `on_update` for every object this one collided with in the last frame,
+    /// once per object even if physics stepped several times, with the latest contact.
+    fn on_collision(&mut self, _context: &mut BehaviourContext<V, E, I, T, R>, _other: Entity, _info: &CollisionInfo<E>) {}
+    /// called when the object is despawned, or the behaviour is removed from it.
+    fn on_destroy(&mut self, _context: &mut BehaviourContext<V, E, I, T, R>) {}
+}
+
+struct AttachedBehaviour<V: Vertex, E: Vector, I: VulkanIndexable, T: TransformMatrix<f32>, R: RigidBody<Transformation = T>> {
+    behaviour: Box<dyn Behaviour<V, E, I, T, R>>,
+    started: bool,
+}
+
+/// # BehaviourSystem
+/// the behaviours of every object. While they run they are moved out of the
+/// [`SystemManager`], so anything attached or despawned in the meantime gets
+/// queued up and handled once they are done.
+pub struct BehaviourSystem<V: Vertex, E: Vector, I: VulkanIndexable, T: TransformMatrix<f32>, R: RigidBody<Transformation = T>> {
+    behaviours: SparseSet<Vec<AttachedBehaviour<V, E, I, T, R>>>,
+    collisions: EventReader<CollisionEvent<E>>,
+    running: bool,
+    despawned: Vec<Entity>,
+}
+
+impl<V: Vertex, E: Vector, I: VulkanIndexable, T: TransformMatrix<f32>, R: RigidBody<Transformation = T>> BehaviourSystem<V, E, I, T, R> {
+    pub fn new() -> Self {
+        Self { behaviours: SparseSet::new(), collisions: EventReader::default(), running: false, despawned: vec![] }
+    }
+    pub fn push(&mut self, id: Entity, behaviour: Box<dyn Behaviour<V, E, I, T, R>>) {
+        let attached = AttachedBehaviour { behaviour, started: false };
+        match self.behaviours.get_mut(id) {
+            Some(list) => list.push(attached),
+            None => {
+                self.behaviours.insert(id, vec![attached]);
+            }
+        }
+    }
+    pub fn has(&self, id: Entity) -> bool {
+        self.behaviours.contains(id)
+    }
+}
+
+impl<V: Vertex, E: Vector, I: VulkanIndexable, T: TransformMatrix<f32>, R: RigidBody<Transformation = T>> Default for BehaviourSystem<V, E, I, T, R> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V: Vertex, E: Vector, I: VulkanIndexable, T: TransformMatrix<f32>, R: RigidBody<Transformation = T>> SystemManager<V, E, I, T, R> {
+    /// # attach
+    /// attaches `behaviour` to the object, it starts running the next time behaviours run.
+    pub fn attach(&mut self, id: Entity, behaviour: impl Behaviour<V, E, I, T, R> + 'static) {
+        self.behaviours.push(id, Box::new(behaviour));
+    }
+    /// # detach_behaviours
+    /// removes every behaviour of the object, calling their `on_destroy`.
+    pub fn detach_behaviours(&mut self, id: Entity) {
+        if self.behaviours.running {
+            self.behaviours.despawned.push(id);
+        }
+        let Some(attached) = self.behaviours.behaviours.remove(id) else {
+            return;
+        };
+        for mut attached in attached {
+            attached.behaviour.on_destroy(&mut BehaviourContext { id, systems: self });
+        }
+    }
+}
+
+impl<V: Vertex + 'static, E: Vector + 'static, I: VulkanIndexable + 'static, T: TransformMatrix<f32> + 'static, R: RigidBody<Transformation = T> + 'static> SystemManager<V, E, I, T, R> {
+    /// # run_behaviours
+    /// runs every behaviour of the objects in the selected scene, objects in
+    /// other scenes have their behaviours paused.
+    pub fn run_behaviours(&mut self, deltatime: R::DeltaTime) {
+        let mut system = std::mem::replace(&mut self.behaviours, BehaviourSystem { running: true, ..BehaviourSystem::new() });
+        let mut collisions: Vec<CollisionEvent<E>> = self.events.read(&mut system.collisions).copied().collect();
+        // every fixed physics step sends its own events, only the latest of every pair is kept
+        let mut seen = HashSet::new();
+        collisions.reverse();
+        collisions.retain(|collision| seen.insert((collision.entity, collision.other)));
+        collisions.reverse();
+        let ids: Vec<Entity> = system.behaviours.entities().to_vec();
+        for id in ids {
+            if self.get_object(&id).is_none() {
+                continue;
+            }
+            let Some(attached) = system.behaviours.get_mut(id) else {
+                continue;
+            };
+            for attached in attached.iter_mut() {
+                let mut context = BehaviourContext { id, systems: self };
+                if !attached.started {
+                    attached.behaviour.on_start(&mut context);
+                    attached.started = true;
+                }
+                for collision in collisions.iter().filter(|collision| collision.entity == id) {
+                    attached.behaviour.on_collision(&mut context, collision.other, &collision.info);
+                }
+                attached.behaviour.on_update(&mut context, deltatime);
+            }
+        }
+
+        let queued = std::mem::replace(&mut self.behaviours, system);
+        for (id, attached) in queued.behaviours.entities.into_iter().zip(queued.behaviours.values) {
+            for attached in attached {
+                self.behaviours.push(id, attached.behaviour);
+            }
+        }
+        for id in queued.despawned {
+            self.detach_behaviours(id);
+        }
+    }
+}
@@ -1,39 +1,64 @@
-use std::{collections::HashMap, sync::Arc, cell::{RefCell, Cell}, rc::Rc};
+use std::{cell::{RefCell, Cell}, rc::Rc, marker::PhantomData};
 
 use drowsed_math::{TransformQuaternion3D, FVec3, Transform, Vector, TransformMatrix};
 use mofongo::collider::{Collider, CollisionInfo};
-use yum_mocha::vk_obj::{self, device::ReplacingDevice};
 
-use crate::{components::scene::Scene, motor::scene_manager::SceneManager};
+use crate::{components::{entity::Entity, object::BasicObject}, motor::scene_manager::SceneManager};
 
+/// # CollisionEvent
+/// sent every frame for every pair of colliders touching each other, once
+/// from the point of view of each of the two.
+#[derive(Debug, Clone, Copy)]
+pub struct CollisionEvent<V: Vector> {
+    pub entity: Entity,
+    pub other: Entity,
+    pub info: CollisionInfo<V>,
+}
+
+/// # AttachedCollider
+/// the collider of an entity, kept as a component in the world of the
+/// [`SceneManager`], and the last collision it found.
+pub struct AttachedCollider<T: TransformMatrix<f32>, V: Vector> {
+    pub collider: Rc<RefCell<dyn Collider<TransformComponent = T, ColliderLayoutVertex = V>>>,
+    pub info: Cell<Option<CollisionInfo<V>>>,
+}
+
+impl<T: TransformMatrix<f32>, V: Vector> AttachedCollider<T, V> {
+    pub fn new(collider: Rc<RefCell<dyn Collider<TransformComponent = T, ColliderLayoutVertex = V>>>) -> Self {
+        Self { collider, info: Cell::new(None) }
+    }
+}
+
+/// # CollisionSystem
+/// checks the [`AttachedCollider`]s of the selected scene against each other.
 pub struct CollisionSystem<T: TransformMatrix<f32>, V: Vector> {
-    colliders: HashMap<i128, (Rc<RefCell<(dyn Collider<TransformComponent = T, ColliderLayoutVertex = V>)>>, Cell<Option<CollisionInfo<V>>>)>
+    phantom: PhantomData<(T, V)>,
 }
 
-impl<T: TransformMatrix<f32>, V: Vector> CollisionSystem<T, V> {
+impl<T: TransformMatrix<f32> + 'static, V: Vector + 'static> CollisionSystem<T, V> {
     pub fn new() -> Self {
-        Self { colliders: HashMap::new() }
-    }
-    pub fn push(&mut self, id: i128, collider: Rc<RefCell<dyn Collider<TransformComponent = T, ColliderLayoutVertex = V>>>) {
-        self.colliders.insert(id, (collider, Cell::new(None)));
+        Self { phantom: PhantomData }
     }
-    pub fn get_collider_by_id(&self, id: i128) -> Option<&(Rc<RefCell<dyn Collider<TransformComponent = T, ColliderLayoutVertex = V>>>, Cell<Option<CollisionInfo<V>>>)> {
-        self.colliders.get(&id)
-    }
-    pub fn render(&mut self, device: Arc<ReplacingDevice>, scenemanager: &SceneManager<T>) {
+    /// checks every collider against each other, returning the collisions found.
+    pub fn render(&mut self, scenemanager: &SceneManager<T>) -> Vec<CollisionEvent<V>> {
         let scene = scenemanager.get_selected_scene();
-        for (id, (collider, info)) in &self.colliders {
-            let object = scene.get_object_by_id(*id).unwrap();
-            let cell = collider.borrow();
-            for (j_id, j_collider) in self.colliders.iter() {
-                if *id == *j_id {
+        let mut query = scenemanager.world.query::<(Entity, &BasicObject<T>, &AttachedCollider<T, V>)>();
+        let colliders: Vec<_> = query.iter().filter(|(id, _, _)| scene.contains(*id)).collect();
+        let mut events = vec![];
+        for (id, object, attached) in &colliders {
+            let cell = attached.collider.borrow();
+            for (j_id, object2, j_attached) in &colliders {
+                if id == j_id {
                     continue;
                 }
-                let object2 = scene.get_object_by_id(*j_id).unwrap();
-                let j_cell = j_collider.0.borrow();
-                let collision_info = cell.collision(&object.transform, &*j_cell, &object2.transform);
-                info.set(collision_info);
+                let j_cell = j_attached.collider.borrow();
+                let collision_info = cell.collision(&object.world_transform(), &*j_cell, &object2.world_transform());
+                attached.info.set(collision_info);
+                if let Some(info) = collision_info {
+                    events.push(CollisionEvent { entity: *id, other: *j_id, info });
+                }
             }
         }
+        events
     }
 }
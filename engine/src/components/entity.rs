@@ -0,0 +1,69 @@
+/// # Entity
+/// Handle to an object in the engine. The index of a freed entity gets reused,
+/// but the generation is bumped every time that happens, so old handles to the
+/// same index stop being alive instead of silently pointing at a new object.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Entity {
+    index: u32,
+    generation: u32,
+}
+
+impl Entity {
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+    /// packs the entity into a single number, e.g. to hand it to scripts.
+    pub fn to_bits(&self) -> u64 {
+        (self.generation as u64) << 32 | self.index as u64
+    }
+    pub fn from_bits(bits: u64) -> Self {
+        Self { index: bits as u32, generation: (bits >> 32) as u32 }
+    }
+}
+
+/// # EntityAllocator
+/// hands out [`Entity`] handles and keeps track of which are still alive.
+#[derive(Default)]
+pub struct EntityAllocator {
+    generations: Vec<u32>,
+    alive: Vec<bool>,
+    free: Vec<u32>,
+}
+
+impl EntityAllocator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn allocate(&mut self) -> Entity {
+        if let Some(index) = self.free.pop() {
+            self.alive[index as usize] = true;
+            Entity { index, generation: self.generations[index as usize] }
+        } else {
+            self.generations.push(0);
+            self.alive.push(true);
+            Entity { index: (self.generations.len() - 1) as u32, generation: 0 }
+        }
+    }
+    /// frees the entity so its index can be reused, returns false if it was already dead.
+    pub fn free(&mut self, entity: Entity) -> bool {
+        if !self.is_alive(entity) {
+            return false;
+        }
+        let index = entity.index as usize;
+        self.alive[index] = false;
+        self.generations[index] = self.generations[index].wrapping_add(1);
+        self.free.push(entity.index);
+        true
+    }
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        let index = entity.index as usize;
+        index < self.generations.len() && self.alive[index] && self.generations[index] == entity.generation
+    }
+    /// amount of entities currently alive.
+    pub fn len(&self) -> usize {
+        self.generations.len() - self.free.len()
+    }
+}
@@ -0,0 +1,21 @@
+use drowsed_math::{TransformMatrix, TransformQuaternion3D};
+use mofongo::solid::transform;
+
+/// # Hierarchical
+/// transforms that can be nested inside each other, needed for objects
+/// with a parent in the [`super::scene::Scene`].
+pub trait Hierarchical: TransformMatrix<f32> + Copy {
+    /// where `local` ends up when placed inside the space of `self`.
+    fn compose(&self, local: &Self) -> Self;
+    /// the local transform that ends up at `world` when placed inside the space of `self`.
+    fn relative(&self, world: &Self) -> Self;
+}
+
+impl Hierarchical for TransformQuaternion3D {
+    fn compose(&self, local: &Self) -> Self {
+        transform::compose(self, local)
+    }
+    fn relative(&self, world: &Self) -> Self {
+        transform::relative(self, world)
+    }
+}
@@ -2,4 +2,10 @@ pub mod object;
 pub mod rendering;
 pub mod scene;
 pub mod physics;
-pub mod collisions;
\ No newline at end of file
+pub mod collisions;
+pub mod entity;
+pub mod hierarchy;
+pub mod behaviour;
+pub mod scripting;
+pub mod animation;
+pub mod spatial;
\ No newline at end of file
@@ -1,15 +1,56 @@
 use drowsed_math::{Transform, TransformMatrix};
 
+use super::entity::Entity;
+
+/// # BasicObject
+/// `transform` is relative to the parent of the object, or to the world if it
+/// has none. The world transform is cached and only recomputed by
+/// [`crate::motor::scene_manager::SceneManager::propagate`] when the object or one
+/// of its parents is dirty. Objects are components in the
+/// [`crate::ecs::world::World`] of the [`crate::motor::scene_manager::SceneManager`].
 pub struct BasicObject<T: Transform + drowsed_math::TransformMatrix<f32>> {
-    pub transform: T,
+    transform: T,
+    pub(crate) world: T,
+    pub(crate) parent: Option<Entity>,
+    pub(crate) children: Vec<Entity>,
+    pub(crate) dirty: bool,
+    /// the world transform might have changed since [`crate::motor::scene_manager::SceneManager::take_changes`] last ran.
+    pub(crate) moved: bool,
 }
 impl<T: Transform + drowsed_math::TransformMatrix<f32>> BasicObject<T> {
     pub fn transform(&self) -> T {
         self.transform
     }
+    pub fn set_transform(&mut self, transform: T) {
+        self.transform = transform;
+        self.dirty = true;
+        self.moved = true;
+    }
+    /// marks the object as dirty, so its children follow along on the next propagation.
+    pub fn transform_mut(&mut self) -> &mut T {
+        self.dirty = true;
+        self.moved = true;
+        &mut self.transform
+    }
+    /// the transform of the object in world space, as of the last propagation. That
+    /// happens after every physics step and in the `propagate` systems, so objects with
+    /// a parent read their old world transform between moving and the next of those.
+    pub fn world_transform(&self) -> T {
+        if self.parent.is_none() {
+            self.transform
+        } else {
+            self.world
+        }
+    }
+    pub fn parent(&self) -> Option<Entity> {
+        self.parent
+    }
+    pub fn children(&self) -> &[Entity] {
+        &self.children
+    }
 }
 impl<T: Transform + drowsed_math::TransformMatrix<f32>> BasicObject<T> {
     pub fn new(t: T) -> Self {
-        Self { transform: t }
+        Self { transform: t, world: t, parent: None, children: vec![], dirty: true, moved: true }
     }
-}
\ No newline at end of file
+}
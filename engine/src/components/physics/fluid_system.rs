@@ -0,0 +1,54 @@
+use drowsed_math::{FVec3, FMat4, TransformQuaternion3D, SquareMatrix, TransformMatrix};
+use mofongo::{bodies::RigidBody, solid::{hull::ConvexHull, physics::fluid::FluidVolume}};
+
+use crate::{motor::scene_manager::SceneManager, components::{entity::Entity, object::BasicObject}};
+
+use super::physics_system::PhysicsSystem;
+
+/// # FluidShape
+/// the shape of a body that reacts to fluids, in the local space of the object.
+/// Only bodies with one float.
+pub struct FluidShape(pub ConvexHull<FVec3>);
+
+/// # FluidSystem
+/// Holds the fluid volumes of the scene and pushes around every body with a
+/// [`FluidShape`] in them. Has to run before the [`PhysicsSystem`] so the forces
+/// it adds get used in that frames step.
+pub struct FluidSystem {
+    volumes: Vec<FluidVolume>,
+}
+
+impl FluidSystem {
+    pub fn new() -> Self {
+        Self { volumes: vec![] }
+    }
+    pub fn push_volume(&mut self, volume: FluidVolume) -> usize {
+        self.volumes.push(volume);
+        self.volumes.len() - 1
+    }
+    pub fn get_volume_mut(&mut self, index: usize) -> Option<&mut FluidVolume> {
+        self.volumes.get_mut(index)
+    }
+    /// swaps out every volume at once, used to keep separate volumes per scene.
+    pub fn replace_volumes(&mut self, volumes: Vec<FluidVolume>) -> Vec<FluidVolume> {
+        std::mem::replace(&mut self.volumes, volumes)
+    }
+    pub fn render<R>(&mut self, physics: &PhysicsSystem<R>, scenemanager: &SceneManager<TransformQuaternion3D>)
+    where R: RigidBody<SpatialVector = FVec3, Transformation = TransformQuaternion3D> + 'static {
+        let scene = scenemanager.get_selected_scene();
+        let gravity = physics.gravity();
+        let mut query = scenemanager.world.query::<(Entity, &BasicObject<TransformQuaternion3D>, &FluidShape, &mut R)>();
+        for (id, object, FluidShape(shape), rigidbody) in query.iter() {
+            if !scene.contains(id) {
+                continue;
+            }
+            let transform = object.world_transform();
+            let mut mat = FMat4::identity();
+            transform.apply_matrix4(&mut mat);
+            let hull = shape.transformed(&mat);
+            for volume in &self.volumes {
+                volume.apply(rigidbody, &hull, transform.translation, gravity);
+            }
+        }
+    }
+}
@@ -1 +1,2 @@
-pub mod physics_system;
\ No newline at end of file
+pub mod physics_system;
+pub mod fluid_system;
\ No newline at end of file
@@ -1,36 +1,35 @@
 #![allow(unused)]
-use std::{collections::HashMap, sync::Arc};
-
-use yum_mocha::vk_obj::{self, device::ReplacingDevice};
-
-use crate::motor::scene_manager::SceneManager;
+use crate::{motor::scene_manager::SceneManager, components::{entity::Entity, hierarchy::Hierarchical, object::BasicObject}, ecs::sparse_set::SparseSet};
 
+/// # PhysicsSystem
+/// Steps the rigidbodies, which are `R` components in the world of the
+/// [`SceneManager`] on entities with a [`BasicObject`].
 pub struct PhysicsSystem<R: mofongo::bodies::RigidBody> {
     global_gravity: R::SpatialVector,
-    rigidbodies: HashMap<i128, R>
 }
 
-impl<R: mofongo::bodies::RigidBody> PhysicsSystem<R> {
+impl<R: mofongo::bodies::RigidBody + 'static> PhysicsSystem<R> {
     pub fn new(gravity: R::SpatialVector) -> Self {
-        Self { global_gravity: gravity, rigidbodies: HashMap::new() }
+        Self { global_gravity: gravity }
+    }
+    pub fn gravity(&self) -> R::SpatialVector {
+        self.global_gravity
     }
     pub fn set_gravity(&mut self, global_gravity: R::SpatialVector) {
         self.global_gravity = global_gravity;
     }
-    pub fn push(&mut self, id: i128, rigidbody: R) {
-        self.rigidbodies.insert(id, rigidbody);
-    }
-    pub fn render(&mut self, device: Arc<ReplacingDevice>, deltatime: R::DeltaTime, scenemanager: &mut SceneManager<R::Transformation>) {
-        let scene = scenemanager.get_selected_scene_mut();
-        for (id, rigidbody) in &mut self.rigidbodies {
-            let object = scene.get_object_by_id_mut(*id).unwrap();
-            object.transform = rigidbody.step(deltatime, self.global_gravity, &object.transform);
+    /// steps every body of the selected scene in world space, bodies with a parent
+    /// get their local transform worked back out so they stay attached to it.
+    pub fn render(&mut self, deltatime: R::DeltaTime, scenemanager: &mut SceneManager<R::Transformation>)
+    where R::Transformation: Hierarchical {
+        let scene = scenemanager.get_selected_scene();
+        let moved: Vec<(Entity, R::Transformation)> = scenemanager.world.query::<(Entity, &BasicObject<R::Transformation>, &mut R)>()
+            .iter()
+            .filter(|(id, _, _)| scene.contains(*id))
+            .map(|(id, object, rigidbody)| (id, rigidbody.step(deltatime, self.global_gravity, &object.world_transform())))
+            .collect();
+        for (id, world) in moved {
+            scenemanager.set_world_transform(id, world);
         }
     }
-    pub fn get_rigidbody_by_id(&self, id: i128) -> Option<&R> {
-        self.rigidbodies.get(&id)
-    }
-    pub fn get_rigidbody_by_id_mut(&mut self, id: i128) -> Option<&mut R> {
-        self.rigidbodies.get_mut(&id)
-    }
-}
\ No newline at end of file
+}
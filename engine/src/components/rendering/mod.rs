@@ -1,2 +1,3 @@
 pub mod rendersystem;
-pub mod models;
\ No newline at end of file
+pub mod models;
+pub mod skinning;
\ No newline at end of file
@@ -1,6 +1,7 @@
 use std::sync::Arc;
 
 use ash::vk;
+use yum_mocha::error::MochaError;
 use yum_mocha::model::model_loader::StandardModelData;
 use yum_mocha::model::vertex::{Vertex3DTexture, Vertex3DNormalUV};
 use yum_mocha::vk_obj::buffer::raw::Buffer;
@@ -14,8 +15,13 @@ pub trait Renderable<V: Vertex, I: VulkanIndexable> {
 }
 
 pub trait FromFBX {
-    fn from_fbx(filepath: &str) -> Self::Output
+    fn try_from_fbx(filepath: &str) -> Result<Self::Output, MochaError>
         where Self: Sized;
+    /// panics when the file can't be loaded, see [`FromFBX::try_from_fbx`].
+    fn from_fbx(filepath: &str) -> Self::Output
+        where Self: Sized {
+        Self::try_from_fbx(filepath).unwrap_or_else(|error| panic!("couldn't load {filepath}: {error}"))
+    }
     type Output;
 }
 #[derive(Debug, Clone)]
@@ -39,8 +45,8 @@ impl<T: Clone> Model<T> {
     }
 }
 impl FromFBX for Model<Vertex3DTexture> {
-    fn from_fbx(filepath: &str) -> Vec<Self> {
-        let data = StandardModelData::new(filepath);
+    fn try_from_fbx(filepath: &str) -> Result<Vec<Self>, MochaError> {
+        let data = StandardModelData::try_new(filepath)?;
 
         let return_type = data.iter().filter_map(|model| {
             if model.vertices.is_empty() || model.indices.is_empty() {
@@ -58,14 +64,14 @@ impl FromFBX for Model<Vertex3DTexture> {
                 });
             }
         }).collect();
-        return_type
+        Ok(return_type)
     }
     type Output = Vec<Self>;
 }
 
 impl FromFBX for Model<Vertex3DNormalUV> {
-    fn from_fbx(filepath: &str) -> Vec<Self> {
-        let data = StandardModelData::new(filepath);
+    fn try_from_fbx(filepath: &str) -> Result<Vec<Self>, MochaError> {
+        let data = StandardModelData::try_new(filepath)?;
 
         let return_type = data.iter().filter_map(|model| {
             if model.vertices.is_empty() || model.indices.is_empty() {
@@ -75,7 +81,7 @@ impl FromFBX for Model<Vertex3DNormalUV> {
                     Vertex3DNormalUV {
                         pos: *vertex,
                         normal: model.normals[i],
-                        ..Default::default()                    
+                        uv: model.uvs.get(i).copied().unwrap_or_default(),                    
                     }
                 }).collect();
                 return Some(Model {
@@ -84,7 +90,7 @@ impl FromFBX for Model<Vertex3DNormalUV> {
                 });
             }
         }).collect();
-        return_type
+        Ok(return_type)
     }
     type Output = Vec<Self>;
 }
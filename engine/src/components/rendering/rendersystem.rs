@@ -1,45 +1,86 @@
-use std::{sync::Arc, collections::HashMap, rc::Rc, i128, marker::PhantomData};
+use std::{sync::Arc, rc::Rc, marker::PhantomData};
 
 use ash::vk;
 use drowsed_math::{Transform, FMat4, FMat3, SquareMatrix, Matrix4, Vector, TransformMatrix};
-use yum_mocha::vk_obj::{device::ReplacingDevice, rendering::mesh::{VulkanIndexable, Vertex}, buffer::raw::Buffer};
-use crate::motor::{device_manager::PushData3D, scene_manager::SceneManager};
+use yum_mocha::{error::MochaError, vk_obj::{device::ReplacingDevice, rendering::mesh::{VulkanIndexable, Vertex}, buffer::raw::Buffer}};
+use crate::{motor::{device_manager::PushData3D, scene_manager::SceneManager}, components::{entity::Entity, object::BasicObject}, ecs::world::World};
 
 use super::models::Renderable;
+
+/// # push_object_data
+/// pushes the matrices of an object at `world` seen through `projection`, for
+/// the vertex shaders taking [`PushData3D`].
+pub fn push_object_data<T: TransformMatrix<f32>>(device: &ReplacingDevice, command_buffer: vk::CommandBuffer, layout: vk::PipelineLayout, projection: FMat4, world: &T) {
+    let mut mat = FMat4::identity();
+    world.apply_matrix4(&mut mat);
+    let push_constants: PushData3D = PushData3D  {
+        transform: projection * mat,
+        model: world.normal_matrix().into()
+    };
+    let data = unsafe { std::mem::transmute::<&PushData3D, &[u8; std::mem::size_of::<PushData3D>()]>(&push_constants) };
+    unsafe { device.device.cmd_push_constants(command_buffer, layout, vk::ShaderStageFlags::ALL_GRAPHICS, 0, data) };
+}
+
+/// # RenderObject
+/// what an entity is drawn with, a component in the world of the [`SceneManager`].
+pub struct RenderObject<V: Vertex, I: VulkanIndexable> {
+    renderable: Rc<dyn Renderable<V, I>>,
+    vertices: Vec<Buffer<V>>,
+    indices: Buffer<I>,
+}
+
+impl<V: Vertex, I: VulkanIndexable> RenderObject<V, I> {
+    pub fn new(device: Arc<ReplacingDevice>, renderable: Rc<dyn Renderable<V, I>>) -> Self {
+        let (vertices, indices) = renderable.get_buffers(device);
+        Self { renderable, vertices, indices }
+    }
+    pub fn renderable(&self) -> &Rc<dyn Renderable<V, I>> {
+        &self.renderable
+    }
+    /// # update_vertices
+    /// overwrites the vertex buffer, for meshes that change every frame like skinned ones.
+    /// `vertices` can't be longer than the vertices it was made with. `Ok(false)` when
+    /// there is no vertex buffer.
+    pub fn update_vertices(&mut self, vertices: &[V]) -> Result<bool, MochaError> {
+        let Some(buffer) = self.vertices.first_mut() else {
+            return Ok(false);
+        };
+        buffer.write(vertices)?;
+        Ok(true)
+    }
+}
+
+/// # RenderSystem
+/// draws every [`RenderObject`] of the selected scene at the world transform of its object.
 pub struct RenderSystem<V: Vertex, I: VulkanIndexable, T: TransformMatrix<f32>> {
-    /// 0: Renderable containing the vector of vertices and indices
-    /// 1: Vertex Buffer for rendering
-    /// 2: Index Buffer for rendering
-    /// 3: Transform Matrix for scaling, rotations, translations and other linear transformations
-    /// 4: Model Matrix for normals.
-    objects: HashMap<i128, (Rc<dyn Renderable<V, I>>, Vec<Buffer<V>>, Buffer<I>, FMat4, FMat3)>,
-    phantom: PhantomData<T>
+    phantom: PhantomData<(V, I, T)>
 }
 
-impl<V: Vertex, I: VulkanIndexable, T: TransformMatrix<f32>> RenderSystem<V, I, T> {
-    
-    pub fn push(&mut self, device: Arc<ReplacingDevice>, id: i128, renderable: Rc<dyn Renderable<V, I>>) {
-        let (vertex, index) = renderable.get_buffers(device.clone());
-        self.objects.insert(id, (renderable, vertex, index, FMat4::identity(), FMat3::identity()));
+impl<V: Vertex + 'static, I: VulkanIndexable + 'static, T: TransformMatrix<f32> + 'static> RenderSystem<V, I, T> {
+    /// # replace
+    /// renders `new` for every object rendering `old`, with new buffers.
+    /// Returns the objects that were rendering it.
+    pub fn replace(&mut self, world: &World, device: Arc<ReplacingDevice>, old: &Rc<dyn Renderable<V, I>>, new: Rc<dyn Renderable<V, I>>) -> Vec<Entity> {
+        let mut replaced = vec![];
+        for (id, object) in world.query::<(Entity, &mut RenderObject<V, I>)>().iter() {
+            if !std::ptr::addr_eq(Rc::as_ptr(&object.renderable), Rc::as_ptr(old)) {
+                continue;
+            }
+            *object = RenderObject::new(device.clone(), new.clone());
+            replaced.push(id);
+        }
+        replaced
     }
     pub fn render(&mut self, device: Arc<ReplacingDevice>, command_buffer: vk::CommandBuffer, layout: vk::PipelineLayout, scenemanager: &SceneManager<T>) {
         let scene = scenemanager.get_selected_scene();
         let camera = scene.get_camera();
         let projection = camera.projection * camera.view;
-        // scene.objects()
-        for (id, (renderable, vertices, indices, mut transform, mut model)) in &mut self.objects {
-            let object = scene.get_object_by_id(*id).unwrap();
-            let mut mat = FMat4::identity();
-            object.transform().apply_matrix4(&mut mat);
-            transform = projection * mat;
-            model = object.transform().normal_matrix();
-            
-            let push_constants: PushData3D = PushData3D  {
-                transform: transform,
-                model: model.into()
-            };
-            let data = unsafe { std::mem::transmute::<&PushData3D, &[u8; std::mem::size_of::<PushData3D>()]>(&push_constants) };
-            unsafe { device.device.cmd_push_constants(command_buffer, layout, vk::ShaderStageFlags::ALL_GRAPHICS, 0, data) };
+        let mut query = scenemanager.world.query::<(Entity, &BasicObject<T>, &RenderObject<V, I>)>();
+        for (id, object, RenderObject { renderable, vertices, indices }) in query.iter() {
+            if !scene.contains(id) {
+                continue;
+            }
+            push_object_data(&device, command_buffer, layout, projection, &object.world_transform());
     
             // renderable.transformations(device.clone(), command_buffer, layout, scene.get_camera());
             let (_, index) = renderable.bind_data(device.clone(), command_buffer);
@@ -56,5 +97,5 @@ impl<V: Vertex, I: VulkanIndexable, T: TransformMatrix<f32>> RenderSystem<V, I,
 }
 
 impl<V: Vertex, I: VulkanIndexable, T: TransformMatrix<f32>> Default for RenderSystem<V, I, T> {
-    fn default() -> Self { Self { objects: HashMap::new(), phantom: PhantomData::default() } }
+    fn default() -> Self { Self { phantom: PhantomData::default() } }
 }
\ No newline at end of file
@@ -0,0 +1,297 @@
+use std::{rc::Rc, sync::Arc};
+
+use ash::vk;
+use drowsed_math::{FMat4, TransformMatrix, Vector};
+use mofongo::bodies::RigidBody;
+use yum_mocha::{error::MochaError, model::{model_loader::StandardModelData, skeleton::SkinData, vertex::{GlobalDebugVertex, Vertex3DSkinned}}, vk_obj::{buffer::raw::Buffer, descriptors::DescriptorWriter, device::ReplacingDevice}};
+
+use crate::{motor::{system_manager::{SystemManager, RenderTarget, SkinnedTarget}, schedule::Stage, scene_manager::SceneManager}, components::entity::Entity, ecs::{sparse_set::SparseSet, world::World}};
+
+use super::{models::{FromFBX, Model}, rendersystem::{RenderSystem, RenderObject, push_object_data}};
+
+/// # SkinnedModel
+/// a rigged model, the bind pose of the mesh along with the skin binding it to
+/// its skeleton. Animations live on the skeleton.
+pub struct SkinnedModel {
+    pub model: Rc<Model<GlobalDebugVertex>>,
+    pub skin: SkinData,
+}
+
+impl SkinnedModel {
+    /// the bind pose with joints and weights, for skinning in the vertex shader.
+    pub fn skinned_vertices(&self) -> Vec<Vertex3DSkinned> {
+        self.model.vertices.iter().enumerate().map(|(i, vertex)| {
+            let influence = self.skin.influences.get(i).copied().unwrap_or_default();
+            Vertex3DSkinned {
+                pos: vertex.pos,
+                normal: vertex.normal,
+                uv: vertex.uv,
+                joints: influence.joints,
+                weights: influence.weights,
+            }
+        }).collect()
+    }
+}
+
+impl FromFBX for SkinnedModel {
+    /// only the rigged models of the file.
+    fn try_from_fbx(filepath: &str) -> Result<Vec<Self>, MochaError> {
+        Ok(StandardModelData::try_new(filepath)?.into_iter().filter_map(|model| {
+            let skin = model.skin?;
+            if model.vertices.is_empty() || model.indices.is_empty() {
+                return None;
+            }
+            let vertices = model.vertices.iter().enumerate().map(|(i, vertex)| GlobalDebugVertex {
+                pos: *vertex,
+                normal: model.normals.get(i).copied().unwrap_or_default(),
+                uv: model.uvs.get(i).copied().unwrap_or_default(),
+            }).collect();
+            Some(SkinnedModel { model: Rc::new(Model { vertices, indices: model.indices }), skin })
+        }).collect())
+    }
+    type Output = Vec<Self>;
+}
+
+/// where skinning happens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SkinningMode {
+    /// vertices get skinned on the cpu and written to the vertex buffer every frame.
+    #[default]
+    Cpu,
+    /// only the joint matrices get written, to a storage buffer per object, see
+    /// [`SkinningSystem::palette`]. The meshes are drawn by [`SkinningSystem::draw`]
+    /// with the [`SkinnedTarget`] of the frame instead of by the [`RenderSystem`].
+    Gpu,
+}
+
+/// # SkinnedInstance
+/// a skinned model on an object and the clip it is playing.
+pub struct SkinnedInstance {
+    pub model: Rc<SkinnedModel>,
+    /// index into the clips of the skeleton, `None` holds the rest pose.
+    pub clip: Option<usize>,
+    pub time: f32,
+    pub speed: f32,
+    pub looping: bool,
+    matrices: Vec<FMat4>,
+    gpu: Option<GpuSkin>,
+}
+
+impl SkinnedInstance {
+    pub fn new(model: Rc<SkinnedModel>) -> Self {
+        Self { model, clip: None, time: 0.0, speed: 1.0, looping: true, matrices: vec![], gpu: None }
+    }
+    /// the skinning matrix of every joint as of the last time the system ran.
+    pub fn matrices(&self) -> &[FMat4] {
+        &self.matrices
+    }
+}
+
+/// # GpuSkin
+/// the buffers an instance is drawn from in [`SkinningMode::Gpu`] and the
+/// descriptor set pointing the vertex shader at its joint matrices.
+struct GpuSkin {
+    device: Arc<ReplacingDevice>,
+    vertices: Buffer<Vertex3DSkinned>,
+    indices: Buffer<u32>,
+    palette: Buffer<FMat4>,
+    pool: vk::DescriptorPool,
+    set: vk::DescriptorSet,
+}
+
+impl GpuSkin {
+    fn new(device: Arc<ReplacingDevice>, target: &SkinnedTarget, model: &SkinnedModel, matrices: &Vec<FMat4>) -> Result<Self, MochaError> {
+        let properties = vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT;
+        let vertices = Buffer::from_vec(device.clone(), vk::BufferUsageFlags::VERTEX_BUFFER, properties, &model.skinned_vertices());
+        let indices = Buffer::from_vec(device.clone(), vk::BufferUsageFlags::INDEX_BUFFER, properties, &model.model.indices);
+        let palette = Buffer::from_vec(device.clone(), vk::BufferUsageFlags::STORAGE_BUFFER, properties, matrices);
+        let allocate_info = vk::DescriptorSetAllocateInfo {
+            descriptor_pool: target.palette_pool,
+            descriptor_set_count: 1,
+            p_set_layouts: &target.palette_layout,
+            ..Default::default()
+        };
+        let set = unsafe { device.device.allocate_descriptor_sets(&allocate_info)? }[0];
+        let info = vk::DescriptorBufferInfo { buffer: palette.buffer, offset: 0, range: vk::WHOLE_SIZE };
+        DescriptorWriter::new().add_storage_buffer(set, 1, 0, 0, &info).write(device.clone());
+        Ok(Self { device, vertices, indices, palette, pool: target.palette_pool, set })
+    }
+}
+
+impl Drop for GpuSkin {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = self.device.device.free_descriptor_sets(self.pool, &[self.set]);
+        }
+    }
+}
+
+/// # SkinningSystem
+/// Plays the clips of skinned models and deforms them, has to run after
+/// anything changing the clips and before rendering.
+pub struct SkinningSystem {
+    instances: SparseSet<SkinnedInstance>,
+    pub mode: SkinningMode,
+}
+
+impl SkinningSystem {
+    pub fn new() -> Self {
+        Self { instances: SparseSet::new(), mode: SkinningMode::Cpu }
+    }
+    pub fn push(&mut self, id: Entity, instance: SkinnedInstance) {
+        self.instances.insert(id, instance);
+    }
+    pub fn remove(&mut self, id: Entity) -> Option<SkinnedInstance> {
+        self.instances.remove(id)
+    }
+    pub fn get_mut(&mut self, id: Entity) -> Option<&mut SkinnedInstance> {
+        self.instances.get_mut(id)
+    }
+    /// # play
+    /// starts the clip called `name` from the beginning, false if the skeleton has no such clip.
+    pub fn play(&mut self, id: Entity, name: &str) -> bool {
+        let Some(instance) = self.instances.get_mut(id) else {
+            return false;
+        };
+        let Some(clip) = instance.model.skin.skeleton.clips.iter().position(|clip| clip.name == name) else {
+            return false;
+        };
+        instance.clip = Some(clip);
+        instance.time = 0.0;
+        true
+    }
+    /// the storage buffer with the joint matrices of the object, only written in [`SkinningMode::Gpu`].
+    pub fn palette(&self, id: Entity) -> Option<&Buffer<FMat4>> {
+        self.instances.get(id)?.gpu.as_ref().map(|gpu| &gpu.palette)
+    }
+    /// # render
+    /// poses every instance. In [`SkinningMode::Gpu`] the instances trade their
+    /// [`RenderObject`] in `world` for their own buffers the first frame there is a
+    /// `target` with a [`SkinnedTarget`], and get it back when the mode goes back to the cpu.
+    pub fn render(&mut self, deltatime: f32, target: Option<&RenderTarget>, world: &mut World) {
+        for (id, instance) in self.instances.iter_mut() {
+            let skeleton = &instance.model.skin.skeleton;
+            let pose = match instance.clip.and_then(|clip| skeleton.clips.get(clip)) {
+                Some(clip) => {
+                    instance.time += deltatime * instance.speed;
+                    if instance.looping && clip.duration > 0.0 {
+                        instance.time = instance.time.rem_euclid(clip.duration);
+                    } else {
+                        instance.time = instance.time.clamp(0.0, clip.duration);
+                    }
+                    skeleton.pose(clip, instance.time)
+                }
+                None => skeleton.rest_pose(),
+            };
+            instance.matrices = instance.model.skin.skinning_matrices(&pose);
+
+            match self.mode {
+                SkinningMode::Cpu => {
+                    if instance.gpu.is_some() {
+                        let Some(target) = target else {
+                            continue;
+                        };
+                        instance.gpu = None;
+                        world.insert(id, RenderObject::<GlobalDebugVertex, u32>::new(target.device.clone(), instance.model.model.clone()));
+                    }
+                    let model = &instance.model.model;
+                    let positions: Vec<_> = model.vertices.iter().map(|vertex| vertex.pos).collect();
+                    let normals: Vec<_> = model.vertices.iter().map(|vertex| vertex.normal).collect();
+                    let (positions, normals) = instance.model.skin.skin(&positions, &normals, &instance.matrices);
+                    let vertices: Vec<_> = model.vertices.iter().zip(positions).zip(normals)
+                        .map(|((vertex, pos), normal)| GlobalDebugVertex { pos, normal, ..*vertex })
+                        .collect();
+                    let Some(object) = world.component_mut::<RenderObject<GlobalDebugVertex, u32>>(id) else {
+                        continue;
+                    };
+                    if let Err(error) = object.update_vertices(&vertices) {
+                        log::error!("couldn't skin {id:?}: {error}");
+                    }
+                }
+                SkinningMode::Gpu => match &mut instance.gpu {
+                    Some(gpu) => {
+                        if let Err(error) = gpu.palette.write(&instance.matrices) {
+                            log::error!("couldn't write the joints of {id:?}: {error}");
+                        }
+                    }
+                    None => {
+                        let Some((target, skinned)) = target.and_then(|target| Some((target, target.skinned?))) else {
+                            continue;
+                        };
+                        match GpuSkin::new(target.device.clone(), &skinned, &instance.model, &instance.matrices) {
+                            Ok(gpu) => {
+                                instance.gpu = Some(gpu);
+                                world.remove::<RenderObject<GlobalDebugVertex, u32>>(id);
+                            }
+                            Err(error) => log::error!("couldn't skin {id:?} on the gpu: {error}"),
+                        }
+                    }
+                },
+            }
+        }
+    }
+    /// # draw
+    /// draws the instances skinned on the gpu, binding the skinned pipeline. It
+    /// shares set 0 and the push constants with the pipeline of `target.layout`,
+    /// so the descriptor sets bound for that stay bound.
+    pub fn draw<T: TransformMatrix<f32> + 'static>(&self, target: &RenderTarget, scenemanager: &SceneManager<T>) {
+        let Some(skinned) = target.skinned else {
+            return;
+        };
+        if self.instances.iter().all(|(_, instance)| instance.gpu.is_none()) {
+            return;
+        }
+        let device = &target.device;
+        let command_buffer = target.command_buffer;
+        let scene = scenemanager.get_selected_scene();
+        let camera = scene.get_camera();
+        let projection = camera.projection * camera.view;
+        unsafe { device.device.cmd_bind_pipeline(command_buffer, vk::PipelineBindPoint::GRAPHICS, skinned.pipeline) };
+        for (id, instance) in self.instances.iter() {
+            let (Some(gpu), Some(object)) = (&instance.gpu, scenemanager.get_object_by_id(id)) else {
+                continue;
+            };
+            push_object_data(device, command_buffer, skinned.layout, projection, &object.world_transform());
+            unsafe {
+                device.device.cmd_bind_descriptor_sets(command_buffer, vk::PipelineBindPoint::GRAPHICS, skinned.layout, 1, &[gpu.set], &[]);
+                device.device.cmd_bind_vertex_buffers(command_buffer, 0, &[gpu.vertices.buffer], &[0]);
+                device.device.cmd_bind_index_buffer(command_buffer, gpu.indices.buffer, 0, vk::IndexType::UINT32);
+                device.device.cmd_draw_indexed(command_buffer, instance.model.model.indices.len() as u32, 1, 0, 0, 0);
+            }
+        }
+    }
+}
+
+impl Default for SkinningSystem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E: Vector + 'static, T: TransformMatrix<f32> + 'static, R: RigidBody<Transformation = T> + 'static> SystemManager<GlobalDebugVertex, E, u32, T, R> {
+    /// # attach_skinned
+    /// renders the skinned model for `id`, starting in the rest pose.
+    pub fn attach_skinned(&mut self, device: Arc<ReplacingDevice>, id: Entity, model: Rc<SkinnedModel>) {
+        self.scene_manager.world.insert(id, RenderObject::<GlobalDebugVertex, u32>::new(device, model.model.clone()));
+        self.skinning.push(id, SkinnedInstance::new(model));
+    }
+    /// # add_skinning_system
+    /// puts `skinning` in [`Stage::RenderPrep`] and `skinned_rendering` in
+    /// [`Stage::Render`] after `rendering`, which draws the models skinned on the gpu.
+    pub fn add_skinning_system(&mut self)
+    where Self: 'static, R: RigidBody<DeltaTime = f32> {
+        self.schedule.add(Stage::RenderPrep, "skinning", |systems: &mut Self| {
+            let Some(frame) = &systems.frame else {
+                return;
+            };
+            systems.skinning.render(frame.delta_time, frame.target.as_ref(), &mut systems.scene_manager.world);
+        });
+        self.schedule.add(Stage::Render, "skinned_rendering", |systems: &mut Self| {
+            let Some(target) = systems.frame.as_ref().and_then(|frame| frame.target.as_ref()) else {
+                return;
+            };
+            systems.skinning.draw(target, &systems.scene_manager);
+        })
+            .after("rendering");
+    }
+}
@@ -1,26 +1,56 @@
 #![allow(unused)]
-use std::collections::{HashMap, hash_map::Iter};
+
+use std::marker::PhantomData;
 
 use drowsed_math::{Transform, TransformMatrix};
 use yum_mocha::camera::Camera;
 
-use super::object::BasicObject;
+use crate::ecs::sparse_set::SparseSet;
+
+use super::{object::BasicObject, entity::Entity};
 
+/// # Scene
+/// the objects making up a level and the cameras looking at it. The objects
+/// themselves are [`BasicObject`] components in the [`crate::ecs::world::World`]
+/// of the [`crate::motor::scene_manager::SceneManager`], a scene only knows which
+/// entities belong to it.
 pub struct Scene<T: TransformMatrix<f32>> {
-    objects: HashMap<i128, BasicObject<T>>,
+    objects: SparseSet<()>,
     pub current_camera: usize,
-    cameras: Vec<Camera>
+    cameras: Vec<Camera>,
+    /// objects removed since [`Scene::take_removed`] last ran.
+    removed: Vec<Entity>,
+    /// whether anything calls [`Scene::take_removed`], nobody would clear `removed` otherwise.
+    tracking: bool,
+    phantom: PhantomData<T>,
 }
 
 impl<T: TransformMatrix<f32>> Scene<T> {
     pub fn new(cameras: Vec<Camera>) -> Self {
-        Self { objects: HashMap::new(), current_camera: 0, cameras }
+        Self { objects: SparseSet::new(), current_camera: 0, cameras, removed: vec![], tracking: false, phantom: PhantomData }
+    }
+    pub(crate) fn insert(&mut self, id: Entity) {
+        self.objects.insert(id, ());
     }
-    pub fn push_object(&mut self, id: i128, object: BasicObject<T>) {
-        self.objects.insert(id, object);
+    /// forgets the entity, returns false if it wasn't in the scene.
+    pub(crate) fn remove(&mut self, id: Entity) -> bool {
+        if self.objects.remove(id).is_none() {
+            return false;
+        }
+        if self.tracking {
+            self.removed.push(id);
+        }
+        true
     }
-    pub fn objects(&self) -> Iter<i128, BasicObject<T>> {
-        self.objects.iter()
+    pub fn contains(&self, id: Entity) -> bool {
+        self.objects.contains(id)
+    }
+    /// the entities in the scene.
+    pub fn objects(&self) -> &[Entity] {
+        self.objects.entities()
+    }
+    pub fn cameras(&self) -> &[Camera] {
+        &self.cameras
     }
     pub fn get_camera(&self) -> &Camera {
         &self.cameras[self.current_camera]
@@ -28,10 +58,18 @@ impl<T: TransformMatrix<f32>> Scene<T> {
     pub fn get_camera_mut(&mut self) -> &mut Camera {
         &mut self.cameras[self.current_camera]
     }
-    pub fn get_object_by_id(&self, id: i128) -> Option<&BasicObject<T>> {
-        self.objects.get(&id)
+    /// # take_removed
+    /// the objects removed since the last call, see
+    /// [`crate::motor::scene_manager::SceneManager::take_changes`]. Removals are only
+    /// recorded once this was called, until [`Scene::stop_tracking`].
+    pub(crate) fn take_removed(&mut self) -> Vec<Entity> {
+        self.tracking = true;
+        std::mem::take(&mut self.removed)
     }
-    pub fn get_object_by_id_mut(&mut self, id: i128) -> Option<&mut BasicObject<T>> {
-        self.objects.get_mut(&id)
+    /// forgets the changes and stops recording removals, for when nothing reads
+    /// them anymore, like after switching away from the scene.
+    pub(crate) fn stop_tracking(&mut self) {
+        self.tracking = false;
+        self.removed.clear();
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,21 @@
+use winit::event::VirtualKeyCode;
+
+/// # key_from_name
+/// the key scripts mean with `name`, using the names of [`VirtualKeyCode`]
+/// (`"W"`, `"Space"`, `"Key1"`, `"Left"`...).
+pub fn key_from_name(name: &str) -> Option<VirtualKeyCode> {
+    use VirtualKeyCode::*;
+    Some(match name {
+        "A" => A, "B" => B, "C" => C, "D" => D, "E" => E, "F" => F, "G" => G,
+        "H" => H, "I" => I, "J" => J, "K" => K, "L" => L, "M" => M, "N" => N,
+        "O" => O, "P" => P, "Q" => Q, "R" => R, "S" => S, "T" => T, "U" => U,
+        "V" => V, "W" => W, "X" => X, "Y" => Y, "Z" => Z,
+        "Key0" => Key0, "Key1" => Key1, "Key2" => Key2, "Key3" => Key3, "Key4" => Key4,
+        "Key5" => Key5, "Key6" => Key6, "Key7" => Key7, "Key8" => Key8, "Key9" => Key9,
+        "Left" => Left, "Right" => Right, "Up" => Up, "Down" => Down,
+        "Space" => Space, "Return" => Return, "Escape" => Escape, "Tab" => Tab, "Back" => Back,
+        "LShift" => LShift, "RShift" => RShift, "LControl" => LControl, "RControl" => RControl,
+        "LAlt" => LAlt, "RAlt" => RAlt,
+        _ => return None,
+    })
+}
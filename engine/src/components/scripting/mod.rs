@@ -0,0 +1,313 @@
+use std::{cell::RefCell, collections::HashMap, path::{Path, PathBuf}, rc::Rc, sync::{Arc, Mutex}, time::{Instant, SystemTime}};
+
+use drowsed_math::{FVec3, TransformQuaternion3D, complex::quaternion::Quaternion, Vector, EuclideanGeometry};
+use mofongo::{bodies::RigidBody, collider::CollisionInfo, solid::physics::rigidbody::RigidBody3D};
+use rhai::{CallFnOptions, Dynamic, Engine, FuncArgs, Scope, AST, FLOAT, INT};
+use yum_mocha::{input::input_state::GlobalInputState, model::vertex::GlobalDebugVertex};
+
+use super::{behaviour::{Behaviour, BehaviourContext}, entity::Entity, object::BasicObject};
+
+pub mod keys;
+
+type SolidContext<'a> = BehaviourContext<'a, GlobalDebugVertex, FVec3, u32, TransformQuaternion3D, RigidBody3D>;
+
+/// how often script files get checked for changes, in real seconds so it keeps
+/// happening while the game is paused.
+const RELOAD_INTERVAL: f32 = 0.5;
+/// operations a single call into a script can run before it gets stopped, so a
+/// script stuck in a loop doesn't freeze the engine.
+const MAX_OPERATIONS: u64 = 1_000_000;
+const MAX_CALL_LEVELS: usize = 64;
+
+/// something a script asked for, applied once the script function returns.
+enum ScriptCommand {
+    SetPosition(FVec3),
+    SetRotation(Quaternion<f32>),
+    SetScale(FVec3),
+    Translate(FVec3),
+    Rotate { axis: FVec3, angle: f32 },
+    ApplyForce { force: FVec3, position: FVec3 },
+    ApplyTorque(FVec3),
+    Spawn { position: FVec3, script: Option<String> },
+    Despawn(Entity),
+    SwitchScene(usize),
+}
+
+/// what the functions registered in the engine can see of the object whose
+/// script is running, filled in right before every call.
+struct ScriptFrame {
+    id: Entity,
+    position: FVec3,
+    rotation: Quaternion<f32>,
+    scale: FVec3,
+    velocity: FVec3,
+    angular_velocity: FVec3,
+    input: Option<Arc<Mutex<GlobalInputState>>>,
+    commands: Vec<ScriptCommand>,
+}
+
+/// a compiled script file, shared by every [`ScriptBehaviour`] running it.
+struct CompiledScript {
+    /// `None` until the file compiled once.
+    ast: Option<Rc<AST>>,
+    /// goes up every time the file compiles, so behaviours know to load it again.
+    version: u64,
+    modified: Option<SystemTime>,
+    /// `None` until the file was looked at the first time.
+    last_check: Option<Instant>,
+}
+
+/// # ScriptEngine
+/// A [Rhai](https://rhai.rs) engine with the engine api registered, shared by
+/// every [`ScriptBehaviour`]. It compiles every script file once, checking it for
+/// changes every [`RELOAD_INTERVAL`] no matter how many objects run it. Scripts can define `on_start()`, `on_update(dt)`,
+/// `on_collision(other, normal, depth)` and `on_destroy()`, and call:
+/// - `vec3(x, y, z)`, with `.x`/`.y`/`.z`, `+`, `-` and `*` by a number
+/// - `quat(axis, angle)`, `quat_euler(v)`, with `.euler`, `*` by another quaternion and `*` by a vector
+/// - `self_id()`, `position()`, `rotation()`, `scale()`, `velocity()`, `angular_velocity()`
+/// - `set_position(v)`, `set_rotation(q)`, `set_scale(v)`, `translate(v)`, `rotate(axis, angle)`
+/// - `apply_force(force)`, `apply_force(force, position)`, `apply_torque(torque)`
+/// - `is_pressed(key)` with key names like `"W"` or `"Space"`
+/// - `spawn(position)`, `spawn(position, script)`, `despawn(id)`, `switch_scene(index)`
+///
+/// positions, rotations and scales are relative to the parent of the object. A
+/// call that runs more than [`MAX_OPERATIONS`] operations is stopped with an error.
+pub struct ScriptEngine {
+    engine: Engine,
+    frame: Rc<RefCell<ScriptFrame>>,
+    scripts: RefCell<HashMap<PathBuf, CompiledScript>>,
+}
+
+impl ScriptEngine {
+    pub fn new() -> Rc<Self> {
+        let frame = Rc::new(RefCell::new(ScriptFrame {
+            id: Entity::from_bits(0),
+            position: FVec3::from(0.0),
+            rotation: TransformQuaternion3D::default().rotation,
+            scale: FVec3::from(1.0),
+            velocity: FVec3::from(0.0),
+            angular_velocity: FVec3::from(0.0),
+            input: None,
+            commands: vec![],
+        }));
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        engine.set_max_call_levels(MAX_CALL_LEVELS);
+
+        engine.register_type_with_name::<FVec3>("Vec3")
+            .register_fn("vec3", |x: FLOAT, y: FLOAT, z: FLOAT| FVec3::new(x as f32, y as f32, z as f32))
+            .register_get("x", |v: &mut FVec3| v.x as FLOAT)
+            .register_get("y", |v: &mut FVec3| v.y as FLOAT)
+            .register_get("z", |v: &mut FVec3| v.z as FLOAT)
+            .register_fn("+", |a: FVec3, b: FVec3| a + b)
+            .register_fn("-", |a: FVec3, b: FVec3| a - b)
+            .register_fn("*", |a: FVec3, b: FLOAT| a * b as f32)
+            .register_fn("length", |v: &mut FVec3| v.length() as FLOAT)
+            .register_fn("to_string", |v: &mut FVec3| format!("vec3({}, {}, {})", v.x, v.y, v.z))
+            .register_fn("to_debug", |v: &mut FVec3| format!("vec3({}, {}, {})", v.x, v.y, v.z));
+        engine.register_type_with_name::<Quaternion<f32>>("Quat")
+            .register_fn("quat", |axis: FVec3, angle: FLOAT| Quaternion::<f32>::angle_axis(angle as f32, axis.normalize()))
+            .register_fn("quat_euler", |euler: FVec3| Quaternion::<f32>::from_euler(euler))
+            .register_get("euler", |q: &mut Quaternion<f32>| q.to_euler())
+            .register_fn("*", |a: Quaternion<f32>, b: Quaternion<f32>| a * b)
+            .register_fn("*", |q: Quaternion<f32>, v: FVec3| q * v);
+
+        let f = frame.clone();
+        engine.register_fn("self_id", move || f.borrow().id.to_bits() as INT);
+        let f = frame.clone();
+        engine.register_fn("position", move || f.borrow().position);
+        let f = frame.clone();
+        engine.register_fn("rotation", move || f.borrow().rotation);
+        let f = frame.clone();
+        engine.register_fn("scale", move || f.borrow().scale);
+        let f = frame.clone();
+        engine.register_fn("velocity", move || f.borrow().velocity);
+        let f = frame.clone();
+        engine.register_fn("angular_velocity", move || f.borrow().angular_velocity);
+        let f = frame.clone();
+        engine.register_fn("set_position", move |position: FVec3| f.borrow_mut().commands.push(ScriptCommand::SetPosition(position)));
+        let f = frame.clone();
+        engine.register_fn("set_rotation", move |rotation: Quaternion<f32>| f.borrow_mut().commands.push(ScriptCommand::SetRotation(rotation)));
+        let f = frame.clone();
+        engine.register_fn("set_scale", move |scale: FVec3| f.borrow_mut().commands.push(ScriptCommand::SetScale(scale)));
+        let f = frame.clone();
+        engine.register_fn("translate", move |offset: FVec3| f.borrow_mut().commands.push(ScriptCommand::Translate(offset)));
+        let f = frame.clone();
+        engine.register_fn("rotate", move |axis: FVec3, angle: FLOAT| f.borrow_mut().commands.push(ScriptCommand::Rotate { axis, angle: angle as f32 }));
+        let f = frame.clone();
+        engine.register_fn("apply_force", move |force: FVec3| f.borrow_mut().commands.push(ScriptCommand::ApplyForce { force, position: FVec3::from(0.0) }));
+        let f = frame.clone();
+        engine.register_fn("apply_force", move |force: FVec3, position: FVec3| f.borrow_mut().commands.push(ScriptCommand::ApplyForce { force, position }));
+        let f = frame.clone();
+        engine.register_fn("apply_torque", move |torque: FVec3| f.borrow_mut().commands.push(ScriptCommand::ApplyTorque(torque)));
+        let f = frame.clone();
+        engine.register_fn("is_pressed", move |key: &str| {
+            let frame = f.borrow();
+            let (Some(input), Some(key)) = (&frame.input, keys::key_from_name(key)) else {
+                return false;
+            };
+            let pressed = input.lock().unwrap().is_pressed(key);
+            pressed
+        });
+        let f = frame.clone();
+        engine.register_fn("spawn", move |position: FVec3| f.borrow_mut().commands.push(ScriptCommand::Spawn { position, script: None }));
+        let f = frame.clone();
+        engine.register_fn("spawn", move |position: FVec3, script: &str| f.borrow_mut().commands.push(ScriptCommand::Spawn { position, script: Some(script.to_string()) }));
+        let f = frame.clone();
+        engine.register_fn("despawn", move |id: INT| f.borrow_mut().commands.push(ScriptCommand::Despawn(Entity::from_bits(id as u64))));
+        let f = frame.clone();
+        engine.register_fn("switch_scene", move |index: INT| f.borrow_mut().commands.push(ScriptCommand::SwitchScene(index.max(0) as usize)));
+
+        Rc::new(Self { engine, frame, scripts: RefCell::new(HashMap::new()) })
+    }
+    /// # script
+    /// the latest version of the script at `path` that compiled, along with its version.
+    /// The file is compiled the first time and again when it changed, a version that
+    /// doesn't compile keeps the last one around.
+    fn script(&self, path: &Path) -> Option<(Rc<AST>, u64)> {
+        let mut scripts = self.scripts.borrow_mut();
+        let script = scripts.entry(path.to_path_buf()).or_insert_with(|| CompiledScript {
+            ast: None,
+            version: 0,
+            modified: None,
+            last_check: None,
+        });
+        let first = script.last_check.is_none();
+        if first || script.last_check.is_some_and(|last| last.elapsed().as_secs_f32() >= RELOAD_INTERVAL) {
+            script.last_check = Some(Instant::now());
+            let modified = std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok();
+            if first || modified != script.modified {
+                script.modified = modified;
+                match self.engine.compile_file(path.to_path_buf()) {
+                    Ok(ast) => {
+                        script.ast = Some(Rc::new(ast));
+                        script.version += 1;
+                    }
+                    Err(err) => log::error!("script {}: {}", path.display(), err),
+                }
+            }
+        }
+        Some((script.ast.clone()?, script.version))
+    }
+    fn begin(&self, context: &mut SolidContext) {
+        let mut frame = self.frame.borrow_mut();
+        frame.id = context.id;
+        let transform = context.object().map_or(TransformQuaternion3D::default(), |object| object.transform());
+        frame.position = transform.translation;
+        frame.rotation = transform.rotation;
+        frame.scale = transform.scale;
+        let body = context.systems.get_rigidbody(&context.id);
+        frame.velocity = body.as_ref().map_or(FVec3::from(0.0), |body| body.velocity());
+        frame.angular_velocity = body.as_ref().map_or(FVec3::from(0.0), |body| body.angular_velocity());
+        frame.input = context.systems.input.clone();
+        frame.commands.clear();
+    }
+}
+
+/// # ScriptBehaviour
+/// Runs a script file as a [`Behaviour`]. The [`ScriptEngine`] recompiles the
+/// file when it changed while the game runs. Globals of the script live in a
+/// scope that is kept across reloads, and the top level of the script and its
+/// `on_start` run again after every reload.
+pub struct ScriptBehaviour {
+    engine: Rc<ScriptEngine>,
+    path: PathBuf,
+    ast: Option<Rc<AST>>,
+    /// the version of the script `ast` is, see [`ScriptEngine::script`].
+    version: u64,
+    scope: Scope<'static>,
+}
+
+impl ScriptBehaviour {
+    pub fn new(engine: Rc<ScriptEngine>, path: impl Into<PathBuf>) -> Self {
+        Self { engine, path: path.into(), ast: None, version: 0, scope: Scope::new() }
+    }
+    /// picks up a newer version of the script and runs its top level and `on_start`.
+    fn load(&mut self, context: &mut SolidContext) {
+        let Some((ast, version)) = self.engine.script(&self.path) else {
+            return;
+        };
+        if version == self.version {
+            return;
+        }
+        self.version = version;
+        self.engine.begin(context);
+        if let Err(err) = self.engine.engine.run_ast_with_scope(&mut self.scope, &ast) {
+            log::error!("script {}: {}", self.path.display(), err);
+        }
+        self.apply(context);
+        self.ast = Some(ast);
+        self.call(context, "on_start", ());
+    }
+    fn call(&mut self, context: &mut SolidContext, name: &str, args: impl FuncArgs) {
+        let Some(ast) = self.ast.clone() else {
+            return;
+        };
+        if !ast.iter_functions().any(|function| function.name == name) {
+            return;
+        }
+        self.engine.begin(context);
+        let options = CallFnOptions::new().eval_ast(false);
+        if let Err(err) = self.engine.engine.call_fn_with_options::<Dynamic>(options, &mut self.scope, &ast, name, args) {
+            log::error!("script {} in {}: {}", self.path.display(), name, err);
+        }
+        self.apply(context);
+    }
+    fn apply(&self, context: &mut SolidContext) {
+        let commands = std::mem::take(&mut self.engine.frame.borrow_mut().commands);
+        for command in commands {
+            match command {
+                ScriptCommand::SetPosition(position) => if let Some(object) = context.object_mut() {
+                    object.transform_mut().translation = position;
+                },
+                ScriptCommand::SetRotation(rotation) => if let Some(object) = context.object_mut() {
+                    object.transform_mut().rotation = rotation;
+                },
+                ScriptCommand::SetScale(scale) => if let Some(object) = context.object_mut() {
+                    object.transform_mut().scale = scale;
+                },
+                ScriptCommand::Translate(offset) => if let Some(object) = context.object_mut() {
+                    object.transform_mut().translation += offset;
+                },
+                ScriptCommand::Rotate { axis, angle } => if let Some(object) = context.object_mut() {
+                    let transform = object.transform_mut();
+                    transform.rotation = transform.rotation * Quaternion::<f32>::angle_axis(angle, axis.normalize());
+                },
+                ScriptCommand::ApplyForce { force, position } => if let Some(body) = context.rigidbody_mut() {
+                    body.apply_force(force, position);
+                },
+                ScriptCommand::ApplyTorque(torque) => if let Some(body) = context.rigidbody_mut() {
+                    body.apply_torque(torque);
+                },
+                ScriptCommand::Spawn { position, script } => {
+                    let id = context.systems.spawn(BasicObject::new(TransformQuaternion3D { translation: position, ..Default::default() }));
+                    if let Some(script) = script {
+                        context.systems.attach(id, ScriptBehaviour::new(self.engine.clone(), script));
+                    }
+                }
+                ScriptCommand::Despawn(id) => {
+                    context.systems.despawn(id);
+                }
+                ScriptCommand::SwitchScene(index) => {
+                    context.systems.switch_scene(index);
+                }
+            }
+        }
+    }
+}
+
+impl Behaviour<GlobalDebugVertex, FVec3, u32, TransformQuaternion3D, RigidBody3D> for ScriptBehaviour {
+    fn on_start(&mut self, context: &mut SolidContext) {
+        self.load(context);
+    }
+    fn on_update(&mut self, context: &mut SolidContext, deltatime: f32) {
+        self.load(context);
+        self.call(context, "on_update", (deltatime as FLOAT,));
+    }
+    fn on_collision(&mut self, context: &mut SolidContext, other: Entity, info: &CollisionInfo<FVec3>) {
+        self.call(context, "on_collision", (other.to_bits() as INT, info.normal, info.depth as FLOAT));
+    }
+    fn on_destroy(&mut self, context: &mut SolidContext) {
+        self.call(context, "on_destroy", ());
+    }
+}
@@ -0,0 +1,293 @@
+use std::{cmp::Ordering, collections::BinaryHeap};
+
+use drowsed_math::FVec3;
+
+use crate::components::entity::Entity;
+
+use super::{Aabb, Frustum};
+
+const NONE: usize = usize::MAX;
+
+struct Node {
+    aabb: Aabb,
+    parent: usize,
+    /// both `NONE` for leaves.
+    children: [usize; 2],
+    entity: Option<Entity>,
+}
+
+impl Node {
+    fn is_leaf(&self) -> bool {
+        self.children[0] == NONE
+    }
+}
+
+/// # Bvh
+/// A dynamic bounding volume hierarchy: a binary tree of boxes with an entity
+/// in every leaf. Leaves are inserted next to the node that grows the tree the
+/// least, and removed without rebuilding anything, so moving a single entity
+/// only touches its path to the root.
+pub struct Bvh {
+    nodes: Vec<Node>,
+    free: Vec<usize>,
+    root: usize,
+}
+
+/// an entry of the k-nearest search, ordered so the closest pops first.
+/// `resolved` entries carry the exact distance of a leaf.
+struct Candidate {
+    distance: f32,
+    node: usize,
+    resolved: bool,
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.distance == other.distance
+    }
+}
+impl Eq for Candidate {}
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.distance.total_cmp(&self.distance)
+    }
+}
+
+impl Bvh {
+    pub fn new() -> Self {
+        Self { nodes: vec![], free: vec![], root: NONE }
+    }
+    fn allocate(&mut self, node: Node) -> usize {
+        match self.free.pop() {
+            Some(index) => {
+                self.nodes[index] = node;
+                index
+            }
+            None => {
+                self.nodes.push(node);
+                self.nodes.len() - 1
+            }
+        }
+    }
+    pub fn aabb(&self, leaf: usize) -> Aabb {
+        self.nodes[leaf].aabb
+    }
+    /// # insert
+    /// adds a leaf for `entity`, returning the index of the leaf which is needed to remove it.
+    pub fn insert(&mut self, entity: Entity, aabb: Aabb) -> usize {
+        let leaf = self.allocate(Node { aabb, parent: NONE, children: [NONE; 2], entity: Some(entity) });
+        if self.root == NONE {
+            self.root = leaf;
+            return leaf;
+        }
+
+        // walk down to the sibling that grows the tree the least
+        let mut sibling = self.root;
+        while !self.nodes[sibling].is_leaf() {
+            let node = &self.nodes[sibling];
+            let combined = node.aabb.union(&aabb).surface_area();
+            // cost of making a new parent for this node and the leaf here
+            let here = 2.0 * combined;
+            let inherited = 2.0 * (combined - node.aabb.surface_area());
+            let descend = |child: &Node| {
+                let grown = child.aabb.union(&aabb).surface_area();
+                if child.is_leaf() { grown + inherited } else { grown - child.aabb.surface_area() + inherited }
+            };
+            let (left, right) = (node.children[0], node.children[1]);
+            let (cost_left, cost_right) = (descend(&self.nodes[left]), descend(&self.nodes[right]));
+            if here < cost_left && here < cost_right {
+                break;
+            }
+            sibling = if cost_left < cost_right { left } else { right };
+        }
+
+        let old_parent = self.nodes[sibling].parent;
+        let parent = self.allocate(Node {
+            aabb: self.nodes[sibling].aabb.union(&aabb),
+            parent: old_parent,
+            children: [sibling, leaf],
+            entity: None,
+        });
+        self.nodes[sibling].parent = parent;
+        self.nodes[leaf].parent = parent;
+        if old_parent == NONE {
+            self.root = parent;
+        } else {
+            let slot = if self.nodes[old_parent].children[0] == sibling { 0 } else { 1 };
+            self.nodes[old_parent].children[slot] = parent;
+        }
+        self.refit(old_parent);
+        leaf
+    }
+    /// # remove
+    /// takes the leaf out, its sibling takes the place of their parent.
+    pub fn remove(&mut self, leaf: usize) {
+        let parent = self.nodes[leaf].parent;
+        self.free.push(leaf);
+        if parent == NONE {
+            self.root = NONE;
+            return;
+        }
+        let children = self.nodes[parent].children;
+        let sibling = if children[0] == leaf { children[1] } else { children[0] };
+        let grandparent = self.nodes[parent].parent;
+        self.nodes[sibling].parent = grandparent;
+        self.free.push(parent);
+        if grandparent == NONE {
+            self.root = sibling;
+        } else {
+            let slot = if self.nodes[grandparent].children[0] == parent { 0 } else { 1 };
+            self.nodes[grandparent].children[slot] = sibling;
+            self.refit(grandparent);
+        }
+    }
+    /// recomputes the boxes from `node` up to the root.
+    fn refit(&mut self, mut node: usize) {
+        while node != NONE {
+            let [left, right] = self.nodes[node].children;
+            self.nodes[node].aabb = self.nodes[left].aabb.union(&self.nodes[right].aabb);
+            node = self.nodes[node].parent;
+        }
+    }
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.free.clear();
+        self.root = NONE;
+    }
+    /// calls `visit` for every leaf whose box passes `test`, skipping every
+    /// branch whose box doesn't.
+    pub fn traverse(&self, test: impl Fn(&Aabb) -> bool, mut visit: impl FnMut(Entity, &Aabb)) {
+        if self.root == NONE {
+            return;
+        }
+        let mut stack = vec![self.root];
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if !test(&node.aabb) {
+                continue;
+            }
+            match node.entity {
+                Some(entity) => visit(entity, &node.aabb),
+                None => stack.extend(node.children),
+            }
+        }
+    }
+    pub fn query_aabb(&self, aabb: &Aabb) -> Vec<Entity> {
+        let mut found = vec![];
+        self.traverse(|node| node.intersects(aabb), |entity, _| found.push(entity));
+        found
+    }
+    pub fn query_sphere(&self, center: FVec3, radius: f32) -> Vec<Entity> {
+        let mut found = vec![];
+        self.traverse(|node| node.intersects_sphere(center, radius), |entity, _| found.push(entity));
+        found
+    }
+    pub fn query_frustum(&self, frustum: &Frustum) -> Vec<Entity> {
+        let mut found = vec![];
+        self.traverse(|node| frustum.intersects(node), |entity, _| found.push(entity));
+        found
+    }
+    /// # nearest
+    /// up to `k` leaves closest to `point`, closest first, along with their distance.
+    /// `distance_squared` gives the exact squared distance of a leaf, which can't be
+    /// less than the distance to its box, or `None` to leave it out.
+    pub fn nearest(&self, point: FVec3, k: usize, distance_squared: impl Fn(Entity) -> Option<f32>) -> Vec<(Entity, f32)> {
+        let mut found = vec![];
+        if self.root == NONE || k == 0 {
+            return found;
+        }
+        let mut heap = BinaryHeap::new();
+        heap.push(Candidate { distance: self.nodes[self.root].aabb.distance_squared(point), node: self.root, resolved: false });
+        // boxes are never further than what they hold, so whatever pops first is the closest left
+        while let Some(Candidate { distance, node: index, resolved }) = heap.pop() {
+            let node = &self.nodes[index];
+            match node.entity {
+                Some(entity) if resolved => {
+                    found.push((entity, distance.sqrt()));
+                    if found.len() == k {
+                        break;
+                    }
+                }
+                Some(entity) => {
+                    if let Some(distance) = distance_squared(entity) {
+                        heap.push(Candidate { distance, node: index, resolved: true });
+                    }
+                }
+                None => {
+                    for child in node.children {
+                        heap.push(Candidate { distance: self.nodes[child].aabb.distance_squared(point), node: child, resolved: false });
+                    }
+                }
+            }
+        }
+        found
+    }
+}
+
+impl Default for Bvh {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::entity::EntityAllocator;
+
+    fn unit_box(x: f32) -> Aabb {
+        Aabb::new(FVec3::new(x - 0.5, -0.5, -0.5), FVec3::new(x + 0.5, 0.5, 0.5))
+    }
+
+    #[test]
+    fn query_finds_overlapping_leaves() {
+        let mut entities = EntityAllocator::new();
+        let mut bvh = Bvh::new();
+        let ids: Vec<Entity> = (0..3).map(|_| entities.allocate()).collect();
+        for (i, id) in ids.iter().enumerate() {
+            bvh.insert(*id, unit_box(i as f32 * 5.0));
+        }
+        assert_eq!(bvh.query_aabb(&unit_box(5.2)), vec![ids[1]]);
+        assert_eq!(bvh.query_sphere(FVec3::new(0.0, 0.0, 0.0), 0.1), vec![ids[0]]);
+        assert!(bvh.query_aabb(&unit_box(2.5)).is_empty());
+        let mut all = bvh.query_aabb(&Aabb::new(FVec3::new(-1.0, -1.0, -1.0), FVec3::new(11.0, 1.0, 1.0)));
+        all.sort_by_key(|id| id.index());
+        assert_eq!(all, ids);
+    }
+
+    #[test]
+    fn removed_leaves_are_not_found() {
+        let mut entities = EntityAllocator::new();
+        let mut bvh = Bvh::new();
+        let first = entities.allocate();
+        let second = entities.allocate();
+        let leaf = bvh.insert(first, unit_box(0.0));
+        bvh.insert(second, unit_box(5.0));
+        bvh.remove(leaf);
+        assert!(bvh.query_aabb(&unit_box(0.0)).is_empty());
+        assert_eq!(bvh.query_aabb(&unit_box(5.0)), vec![second]);
+
+        let leaf = bvh.insert(first, unit_box(10.0));
+        assert_eq!(bvh.aabb(leaf), unit_box(10.0));
+        assert_eq!(bvh.query_aabb(&unit_box(10.0)), vec![first]);
+    }
+
+    #[test]
+    fn nearest_is_sorted_by_distance() {
+        let mut entities = EntityAllocator::new();
+        let mut bvh = Bvh::new();
+        let boxes: Vec<(Entity, Aabb)> = [0.0, 5.0, 10.0].into_iter().map(|x| (entities.allocate(), unit_box(x))).collect();
+        for (id, aabb) in &boxes {
+            bvh.insert(*id, *aabb);
+        }
+        let point = FVec3::new(9.0, 0.0, 0.0);
+        let distance = |id: Entity| boxes.iter().find(|(other, _)| *other == id).map(|(_, aabb)| aabb.distance_squared(point));
+        let nearest: Vec<Entity> = bvh.nearest(point, 2, distance).into_iter().map(|(id, _)| id).collect();
+        assert_eq!(nearest, vec![boxes[2].0, boxes[1].0]);
+    }
+}
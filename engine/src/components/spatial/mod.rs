@@ -0,0 +1,147 @@
+use drowsed_math::{FMat4, FVec3, TransformQuaternion3D};
+use yum_mocha::model::vertex::GlobalDebugVertex;
+
+use super::rendering::models::Model;
+
+pub mod bvh;
+pub mod spatial_index;
+
+/// # Aabb
+/// axis aligned box, `min` is smaller than `max` on every axis.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: FVec3,
+    pub max: FVec3,
+}
+
+impl Aabb {
+    pub fn new(min: FVec3, max: FVec3) -> Self {
+        Self { min, max }
+    }
+    pub fn point(point: FVec3) -> Self {
+        Self { min: point, max: point }
+    }
+    /// the smallest box around `points`, `None` without any.
+    pub fn from_points(points: impl IntoIterator<Item = FVec3>) -> Option<Self> {
+        let mut points = points.into_iter();
+        let first = Self::point(points.next()?);
+        Some(points.fold(first, |aabb, point| aabb.union(&Self::point(point))))
+    }
+    pub fn union(&self, other: &Self) -> Self {
+        Self {
+            min: FVec3::new(self.min.x.min(other.min.x), self.min.y.min(other.min.y), self.min.z.min(other.min.z)),
+            max: FVec3::new(self.max.x.max(other.max.x), self.max.y.max(other.max.y), self.max.z.max(other.max.z)),
+        }
+    }
+    /// grown by `margin` on every side.
+    pub fn fattened(&self, margin: f32) -> Self {
+        Self { min: self.min - FVec3::from(margin), max: self.max + FVec3::from(margin) }
+    }
+    pub fn center(&self) -> FVec3 {
+        (self.min + self.max) * 0.5
+    }
+    pub fn surface_area(&self) -> f32 {
+        let size = self.max - self.min;
+        2.0 * (size.x * size.y + size.y * size.z + size.z * size.x)
+    }
+    pub fn contains(&self, other: &Self) -> bool {
+        self.min.x <= other.min.x && self.min.y <= other.min.y && self.min.z <= other.min.z
+            && self.max.x >= other.max.x && self.max.y >= other.max.y && self.max.z >= other.max.z
+    }
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.min.x <= other.max.x && self.max.x >= other.min.x
+            && self.min.y <= other.max.y && self.max.y >= other.min.y
+            && self.min.z <= other.max.z && self.max.z >= other.min.z
+    }
+    /// squared distance from `point` to the closest point of the box, 0 inside it.
+    pub fn distance_squared(&self, point: FVec3) -> f32 {
+        let axis = |value: f32, min: f32, max: f32| if value < min { min - value } else if value > max { value - max } else { 0.0 };
+        let dx = axis(point.x, self.min.x, self.max.x);
+        let dy = axis(point.y, self.min.y, self.max.y);
+        let dz = axis(point.z, self.min.z, self.max.z);
+        dx * dx + dy * dy + dz * dz
+    }
+    pub fn intersects_sphere(&self, center: FVec3, radius: f32) -> bool {
+        self.distance_squared(center) <= radius * radius
+    }
+    /// the box around this one after it was moved by `transform`.
+    pub fn transformed(&self, transform: &TransformQuaternion3D) -> Self {
+        let corners = (0..8).map(|i| {
+            let corner = FVec3::new(
+                if i & 1 == 0 { self.min.x } else { self.max.x },
+                if i & 2 == 0 { self.min.y } else { self.max.y },
+                if i & 4 == 0 { self.min.z } else { self.max.z },
+            );
+            let scaled = FVec3::new(corner.x * transform.scale.x, corner.y * transform.scale.y, corner.z * transform.scale.z);
+            transform.translation + transform.rotation * scaled
+        });
+        Self::from_points(corners).unwrap()
+    }
+}
+
+/// # LocalBounds
+/// component with the bounds of an object in its local space, used by the
+/// [`spatial_index::SpatialIndex`]. Objects without one are treated as a point.
+#[derive(Debug, Clone, Copy)]
+pub struct LocalBounds(pub Aabb);
+
+impl LocalBounds {
+    /// the box around every vertex of the model, `None` for an empty one.
+    pub fn of_model(model: &Model<GlobalDebugVertex>) -> Option<Self> {
+        Aabb::from_points(model.vertices.iter().map(|vertex| vertex.pos)).map(LocalBounds)
+    }
+}
+
+/// a plane facing `normal`, points with `normal . p + distance >= 0` are in front of it.
+#[derive(Debug, Clone, Copy)]
+pub struct Plane {
+    pub normal: FVec3,
+    pub distance: f32,
+}
+
+/// # Frustum
+/// the six planes of a camera, facing inwards.
+#[derive(Debug, Clone, Copy)]
+pub struct Frustum {
+    pub planes: [Plane; 6],
+}
+
+impl Frustum {
+    /// # from_matrix
+    /// the frustum of `projection * view`, with depth going from 0 to 1 like in Vulkan.
+    pub fn from_matrix(m: &FMat4) -> Self {
+        let row = |i: usize| {
+            let pick = |column: &drowsed_math::FVec4| [column.x, column.y, column.z, column.w][i];
+            [pick(&m.x), pick(&m.y), pick(&m.z), pick(&m.w)]
+        };
+        let (r0, r1, r2, r3) = (row(0), row(1), row(2), row(3));
+        let plane = |a: [f32; 4], b: [f32; 4], sign: f32| {
+            let normal = FVec3::new(a[0] + sign * b[0], a[1] + sign * b[1], a[2] + sign * b[2]);
+            let length = (normal.x * normal.x + normal.y * normal.y + normal.z * normal.z).sqrt().max(f32::EPSILON);
+            Plane { normal: normal * (1.0 / length), distance: (a[3] + sign * b[3]) / length }
+        };
+        Self {
+            planes: [
+                plane(r3, r0, 1.0),
+                plane(r3, r0, -1.0),
+                plane(r3, r1, 1.0),
+                plane(r3, r1, -1.0),
+                plane(r2, r2, 0.0),
+                plane(r3, r2, -1.0),
+            ],
+        }
+    }
+    /// false only when the box is completely behind one of the planes, so boxes
+    /// close to the corners can count as inside even when they aren't.
+    pub fn intersects(&self, aabb: &Aabb) -> bool {
+        self.planes.iter().all(|plane| {
+            // the corner furthest along the normal
+            let corner = FVec3::new(
+                if plane.normal.x >= 0.0 { aabb.max.x } else { aabb.min.x },
+                if plane.normal.y >= 0.0 { aabb.max.y } else { aabb.min.y },
+                if plane.normal.z >= 0.0 { aabb.max.z } else { aabb.min.z },
+            );
+            plane.normal.x * corner.x + plane.normal.y * corner.y + plane.normal.z * corner.z + plane.distance >= 0.0
+        })
+    }
+}
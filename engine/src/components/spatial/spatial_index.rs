@@ -0,0 +1,146 @@
+use drowsed_math::{FVec3, TransformQuaternion3D};
+
+use crate::{components::entity::Entity, ecs::{sparse_set::SparseSet, query::Changed}, motor::scene_manager::SceneManager};
+
+use super::{bvh::Bvh, Aabb, Frustum, LocalBounds};
+
+/// where an object is in the tree.
+struct Indexed {
+    leaf: usize,
+    /// the actual world bounds, the leaf holds them fattened by the margin.
+    bounds: Aabb,
+}
+
+/// # SpatialIndex
+/// Finds objects of the selected scene by their world bounds. The tree holds
+/// every box grown by `margin`, so objects moving a little stay in their leaf
+/// and only the ones leaving it get reinserted by [`SpatialIndex::update`].
+/// Queries test the exact bounds, so the margin never adds results.
+pub struct SpatialIndex {
+    tree: Bvh,
+    objects: SparseSet<Indexed>,
+    pub margin: f32,
+    /// the scene the index holds, switching scenes builds it again.
+    scene: Option<usize>,
+    /// world tick of the last update, [`LocalBounds`] changed after it get picked up.
+    tick: u32,
+}
+
+impl SpatialIndex {
+    pub fn new() -> Self {
+        Self { tree: Bvh::new(), objects: SparseSet::new(), margin: 0.1, scene: None, tick: 0 }
+    }
+    /// world bounds of the object as of the last update.
+    pub fn bounds(&self, id: Entity) -> Option<Aabb> {
+        Some(self.objects.get(id)?.bounds)
+    }
+    pub fn len(&self) -> usize {
+        self.objects.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+    /// sets the world bounds of `id`, reinserting it only if they left its leaf.
+    pub fn set_bounds(&mut self, id: Entity, bounds: Aabb) {
+        if let Some(indexed) = self.objects.get_mut(id) {
+            indexed.bounds = bounds;
+            if self.tree.aabb(indexed.leaf).contains(&bounds) {
+                return;
+            }
+            self.tree.remove(indexed.leaf);
+            indexed.leaf = self.tree.insert(id, bounds.fattened(self.margin));
+            return;
+        }
+        let leaf = self.tree.insert(id, bounds.fattened(self.margin));
+        self.objects.insert(id, Indexed { leaf, bounds });
+    }
+    pub fn remove(&mut self, id: Entity) -> bool {
+        let Some(indexed) = self.objects.remove(id) else {
+            return false;
+        };
+        self.tree.remove(indexed.leaf);
+        true
+    }
+    pub fn clear(&mut self) {
+        self.tree.clear();
+        self.objects.clear();
+        self.scene = None;
+    }
+    /// # update
+    /// brings the index up to the world transforms of the selected scene, has to
+    /// run after they were propagated. Only objects that moved, got new
+    /// [`LocalBounds`] or were added or removed since the last update are touched,
+    /// see [`SceneManager::take_changes`]. Objects use their
+    /// [`LocalBounds`], or their position without one.
+    pub fn update(&mut self, scenemanager: &mut SceneManager<TransformQuaternion3D>) {
+        let selected = scenemanager.selected_scene;
+        let (mut changed, removed) = scenemanager.take_changes();
+        if self.scene != Some(selected) {
+            self.clear();
+            self.scene = Some(selected);
+            changed = scenemanager.get_selected_scene().objects().to_vec();
+        } else {
+            for id in removed {
+                self.remove(id);
+            }
+        }
+        let since = self.tick.wrapping_sub(1);
+        self.tick = scenemanager.world.tick();
+        changed.extend(scenemanager.world.query::<(Entity, Changed<LocalBounds>)>().since(since).iter().map(|(id, _)| id));
+
+        let local = scenemanager.world.storage::<LocalBounds>();
+        for id in changed {
+            let Some(object) = scenemanager.get_object_by_id(id) else {
+                continue;
+            };
+            let transform = object.world_transform();
+            let bounds = match local.as_ref().and_then(|local| local.get(id)) {
+                Some(LocalBounds(bounds)) => bounds.transformed(&transform),
+                None => Aabb::point(transform.translation),
+            };
+            self.set_bounds(id, bounds);
+        }
+    }
+    /// objects whose bounds overlap `aabb`.
+    pub fn query_aabb(&self, aabb: &Aabb) -> Vec<Entity> {
+        let mut found = vec![];
+        self.tree.traverse(|node| node.intersects(aabb), |id, _| {
+            if self.objects.get(id).is_some_and(|indexed| indexed.bounds.intersects(aabb)) {
+                found.push(id);
+            }
+        });
+        found
+    }
+    /// objects whose bounds reach into the sphere.
+    pub fn query_sphere(&self, center: FVec3, radius: f32) -> Vec<Entity> {
+        let mut found = vec![];
+        self.tree.traverse(|node| node.intersects_sphere(center, radius), |id, _| {
+            if self.objects.get(id).is_some_and(|indexed| indexed.bounds.intersects_sphere(center, radius)) {
+                found.push(id);
+            }
+        });
+        found
+    }
+    /// objects that might be visible in `frustum`, see [`Frustum::intersects`].
+    pub fn query_frustum(&self, frustum: &Frustum) -> Vec<Entity> {
+        let mut found = vec![];
+        self.tree.traverse(|node| frustum.intersects(node), |id, _| {
+            if self.objects.get(id).is_some_and(|indexed| frustum.intersects(&indexed.bounds)) {
+                found.push(id);
+            }
+        });
+        found
+    }
+    /// # nearest
+    /// the `k` objects with bounds closest to `point`, closest first, along with
+    /// their distance. Objects containing `point` are 0 away.
+    pub fn nearest(&self, point: FVec3, k: usize) -> Vec<(Entity, f32)> {
+        self.tree.nearest(point, k, |id| Some(self.objects.get(id)?.bounds.distance_squared(point)))
+    }
+}
+
+impl Default for SpatialIndex {
+    fn default() -> Self {
+        Self::new()
+    }
+}
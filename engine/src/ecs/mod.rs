@@ -0,0 +1,3 @@
+pub mod sparse_set;
+pub mod world;
+pub mod query;
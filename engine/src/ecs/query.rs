@@ -0,0 +1,228 @@
+use std::{cell::{Ref, RefMut}, marker::PhantomData};
+
+use crate::components::entity::Entity;
+
+use super::{sparse_set::SparseSet, world::World};
+
+/// # QueryData
+/// Something that can be fetched for every entity of a [`Query`]: `&T`, `&mut T`,
+/// [`Changed<T>`], [`Entity`] or a tuple of those.
+pub trait QueryData {
+    /// the borrowed storages, kept alive for as long as the query is.
+    type State<'w>;
+    type Item<'a>;
+    fn borrow(world: &World) -> Option<Self::State<'_>>;
+    /// entities that could match, `None` if this doesn't restrict them.
+    fn entities<'a, 'w>(state: &'a Self::State<'w>) -> Option<&'a [Entity]>;
+    /// whether `entity` would be fetched, without touching anything. Tuples check
+    /// every element with this before fetching any, so a `&mut T` doesn't mark an
+    /// entity as changed that a later element of the tuple rejects.
+    fn matches(state: &Self::State<'_>, entity: Entity, since: u32) -> bool;
+    /// # Safety
+    /// mutable items hand out references through raw pointers, so the same
+    /// entity must not be fetched again while a previous item of it is alive.
+    unsafe fn fetch<'a, 'w>(state: &'a Self::State<'w>, entity: Entity, since: u32, tick: u32) -> Option<Self::Item<'a>>;
+}
+
+/// shared borrow of a component storage.
+pub struct ColumnRef<'w, T> {
+    set: Ref<'w, SparseSet<T>>,
+}
+
+/// mutable borrow of a component storage. The pointers are taken once when the
+/// query is created so items can be handed out from a shared reference.
+pub struct ColumnMut<'w, T> {
+    set: RefMut<'w, SparseSet<T>>,
+    values: *mut T,
+    changed: *mut u32,
+}
+
+impl<T: 'static> QueryData for &T {
+    type State<'w> = ColumnRef<'w, T>;
+    type Item<'a> = &'a T;
+    fn borrow(world: &World) -> Option<Self::State<'_>> {
+        Some(ColumnRef { set: world.storage_cell::<T>()?.borrow() })
+    }
+    fn entities<'a, 'w>(state: &'a Self::State<'w>) -> Option<&'a [Entity]> {
+        Some(state.set.entities())
+    }
+    fn matches(state: &Self::State<'_>, entity: Entity, _since: u32) -> bool {
+        state.set.dense_index(entity).is_some()
+    }
+    unsafe fn fetch<'a, 'w>(state: &'a Self::State<'w>, entity: Entity, _since: u32, _tick: u32) -> Option<Self::Item<'a>> {
+        state.set.get(entity)
+    }
+}
+
+impl<T: 'static> QueryData for &mut T {
+    type State<'w> = ColumnMut<'w, T>;
+    type Item<'a> = &'a mut T;
+    fn borrow(world: &World) -> Option<Self::State<'_>> {
+        let mut set = world.storage_cell::<T>()?.borrow_mut();
+        let values = set.values.as_mut_ptr();
+        let changed = set.changed.as_mut_ptr();
+        Some(ColumnMut { set, values, changed })
+    }
+    fn entities<'a, 'w>(state: &'a Self::State<'w>) -> Option<&'a [Entity]> {
+        Some(state.set.entities())
+    }
+    fn matches(state: &Self::State<'_>, entity: Entity, _since: u32) -> bool {
+        state.set.dense_index(entity).is_some()
+    }
+    unsafe fn fetch<'a, 'w>(state: &'a Self::State<'w>, entity: Entity, _since: u32, tick: u32) -> Option<Self::Item<'a>> {
+        let index = state.set.dense_index(entity)?;
+        // SAFETY: `borrow` took the `RefMut` of the whole storage and the state keeps it
+        // for as long as the query lives, so nothing outside the query can reach these
+        // values, and a tuple naming `T` twice (`(&mut T, &T)` too) panics in `borrow`
+        // instead of aliasing. A borrowed storage can't grow or shrink, so `values` and
+        // `changed` still point at its buffers, and `index` comes from `dense_index` so
+        // it is in bounds. Callers fetch every entity only once while its item lives
+        // (see the trait), so this is the only `&mut` to the value.
+        *state.changed.add(index) = tick;
+        Some(&mut *state.values.add(index))
+    }
+}
+
+/// # Changed
+/// matches entities whose `T` was inserted or mutably accessed after the tick the
+/// query looks from (see [`Query::since`]), and yields it as `&T`.
+pub struct Changed<T>(PhantomData<T>);
+
+impl<T: 'static> QueryData for Changed<T> {
+    type State<'w> = ColumnRef<'w, T>;
+    type Item<'a> = &'a T;
+    fn borrow(world: &World) -> Option<Self::State<'_>> {
+        <&T as QueryData>::borrow(world)
+    }
+    fn entities<'a, 'w>(state: &'a Self::State<'w>) -> Option<&'a [Entity]> {
+        Some(state.set.entities())
+    }
+    fn matches(state: &Self::State<'_>, entity: Entity, since: u32) -> bool {
+        // ticks wrap around, so compare the distance instead of the values
+        state.set.dense_index(entity).is_some_and(|index| (state.set.changed[index].wrapping_sub(since) as i32) > 0)
+    }
+    unsafe fn fetch<'a, 'w>(state: &'a Self::State<'w>, entity: Entity, since: u32, _tick: u32) -> Option<Self::Item<'a>> {
+        if !Self::matches(state, entity, since) {
+            return None;
+        }
+        state.set.get(entity)
+    }
+}
+
+impl QueryData for Entity {
+    type State<'w> = ();
+    type Item<'a> = Entity;
+    fn borrow(_world: &World) -> Option<Self::State<'_>> {
+        Some(())
+    }
+    fn entities<'a, 'w>(_state: &'a Self::State<'w>) -> Option<&'a [Entity]> {
+        None
+    }
+    fn matches(_state: &Self::State<'_>, _entity: Entity, _since: u32) -> bool {
+        true
+    }
+    unsafe fn fetch<'a, 'w>(_state: &'a Self::State<'w>, entity: Entity, _since: u32, _tick: u32) -> Option<Self::Item<'a>> {
+        Some(entity)
+    }
+}
+
+macro_rules! impl_query_tuple {
+    ($($name:ident),+) => {
+        #[allow(non_snake_case)]
+        impl<$($name: QueryData),+> QueryData for ($($name,)+) {
+            type State<'w> = ($($name::State<'w>,)+);
+            type Item<'a> = ($($name::Item<'a>,)+);
+            fn borrow(world: &World) -> Option<Self::State<'_>> {
+                Some(($($name::borrow(world)?,)+))
+            }
+            fn entities<'a, 'w>(state: &'a Self::State<'w>) -> Option<&'a [Entity]> {
+                let ($($name,)+) = state;
+                let mut smallest: Option<&'a [Entity]> = None;
+                $(
+                    if let Some(entities) = $name::entities($name) {
+                        if smallest.map_or(true, |smallest| entities.len() < smallest.len()) {
+                            smallest = Some(entities);
+                        }
+                    }
+                )+
+                smallest
+            }
+            fn matches(state: &Self::State<'_>, entity: Entity, since: u32) -> bool {
+                let ($($name,)+) = state;
+                $($name::matches($name, entity, since))&&+
+            }
+            unsafe fn fetch<'a, 'w>(state: &'a Self::State<'w>, entity: Entity, since: u32, tick: u32) -> Option<Self::Item<'a>> {
+                // nothing gets marked as changed unless the whole tuple matches
+                if !Self::matches(state, entity, since) {
+                    return None;
+                }
+                let ($($name,)+) = state;
+                Some(($($name::fetch($name, entity, since, tick)?,)+))
+            }
+        }
+    };
+}
+
+impl_query_tuple!(A);
+impl_query_tuple!(A, B);
+impl_query_tuple!(A, B, C);
+impl_query_tuple!(A, B, C, D);
+impl_query_tuple!(A, B, C, D, E);
+impl_query_tuple!(A, B, C, D, E, F);
+
+/// # Query
+/// Borrow of the storages needed by `Q`, created with [`World::query`].
+/// Iterating walks the smallest storage in the query densely and looks the
+/// entity up in the others.
+pub struct Query<'w, Q: QueryData> {
+    state: Option<Q::State<'w>>,
+    since: u32,
+    tick: u32,
+}
+
+impl<'w, Q: QueryData> Query<'w, Q> {
+    pub(crate) fn new(world: &'w World) -> Self {
+        Self { state: Q::borrow(world), since: world.tick().wrapping_sub(1), tick: world.tick() }
+    }
+    /// makes [`Changed`] match everything changed after `tick` instead of only
+    /// during the current one, e.g. the tick a system last ran at.
+    pub fn since(mut self, tick: u32) -> Self {
+        self.since = tick;
+        self
+    }
+    pub fn iter(&mut self) -> QueryIter<'_, 'w, Q> {
+        let state = self.state.as_ref();
+        let entities = state.and_then(|state| Q::entities(state)).unwrap_or(&[]);
+        QueryIter { state, entities: entities.iter(), since: self.since, tick: self.tick }
+    }
+    pub fn for_each(&mut self, mut f: impl FnMut(Q::Item<'_>)) {
+        for item in self.iter() {
+            f(item);
+        }
+    }
+    pub fn get(&mut self, entity: Entity) -> Option<Q::Item<'_>> {
+        // SAFETY: the item borrows the query mutably, so nothing else can be fetched while it lives
+        unsafe { Q::fetch(self.state.as_ref()?, entity, self.since, self.tick) }
+    }
+}
+
+pub struct QueryIter<'a, 'w, Q: QueryData> {
+    state: Option<&'a Q::State<'w>>,
+    entities: std::slice::Iter<'a, Entity>,
+    since: u32,
+    tick: u32,
+}
+
+impl<'a, 'w, Q: QueryData> Iterator for QueryIter<'a, 'w, Q> {
+    type Item = Q::Item<'a>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let state = self.state?;
+        for entity in self.entities.by_ref() {
+            // SAFETY: every entity is only in a storage once, so each one is fetched a single time
+            if let Some(item) = unsafe { Q::fetch(state, *entity, self.since, self.tick) } {
+                return Some(item);
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,115 @@
+use crate::components::entity::Entity;
+
+/// # SparseSet
+/// Maps entities to values while keeping the values packed together, so iterating
+/// is just walking a `Vec` instead of hashing every id. Lookups go through the
+/// sparse array which is indexed by [`Entity::index`], and the generation is
+/// checked so a stale entity never finds the value of whoever reused its index.
+///
+/// every value also keeps the tick it was last inserted or mutably accessed at,
+/// which is what [`super::world::World`] uses for change detection.
+pub struct SparseSet<T> {
+    sparse: Vec<Option<usize>>,
+    pub(crate) entities: Vec<Entity>,
+    pub(crate) values: Vec<T>,
+    pub(crate) changed: Vec<u32>,
+}
+
+impl<T> Default for SparseSet<T> {
+    fn default() -> Self {
+        Self { sparse: vec![], entities: vec![], values: vec![], changed: vec![] }
+    }
+}
+
+impl<T> SparseSet<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub(crate) fn dense_index(&self, entity: Entity) -> Option<usize> {
+        let index = (*self.sparse.get(entity.index() as usize)?)?;
+        if self.entities[index] == entity {
+            Some(index)
+        } else {
+            None
+        }
+    }
+    /// inserts or replaces the value of `entity`, returning the old one.
+    pub fn insert(&mut self, entity: Entity, value: T) -> Option<T> {
+        self.insert_with_tick(entity, value, 0)
+    }
+    pub fn insert_with_tick(&mut self, entity: Entity, value: T, tick: u32) -> Option<T> {
+        if let Some(index) = self.dense_index(entity) {
+            self.changed[index] = tick;
+            return Some(std::mem::replace(&mut self.values[index], value));
+        }
+        let sparse_index = entity.index() as usize;
+        if sparse_index >= self.sparse.len() {
+            self.sparse.resize(sparse_index + 1, None);
+        }
+        // an older generation of this entity might still be in here
+        if let Some(old) = self.sparse[sparse_index] {
+            self.swap_remove(old);
+        }
+        self.sparse[sparse_index] = Some(self.values.len());
+        self.entities.push(entity);
+        self.values.push(value);
+        self.changed.push(tick);
+        None
+    }
+    pub fn remove(&mut self, entity: Entity) -> Option<T> {
+        let index = self.dense_index(entity)?;
+        Some(self.swap_remove(index))
+    }
+    fn swap_remove(&mut self, index: usize) -> T {
+        let entity = self.entities.swap_remove(index);
+        self.changed.swap_remove(index);
+        let value = self.values.swap_remove(index);
+        self.sparse[entity.index() as usize] = None;
+        if index < self.entities.len() {
+            self.sparse[self.entities[index].index() as usize] = Some(index);
+        }
+        value
+    }
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.dense_index(entity).is_some()
+    }
+    pub fn get(&self, entity: Entity) -> Option<&T> {
+        let index = self.dense_index(entity)?;
+        Some(&self.values[index])
+    }
+    /// mutable access without touching the change tick.
+    pub fn get_mut(&mut self, entity: Entity) -> Option<&mut T> {
+        let index = self.dense_index(entity)?;
+        Some(&mut self.values[index])
+    }
+    /// mutable access that also marks the value as changed at `tick`.
+    pub fn get_mut_with_tick(&mut self, entity: Entity, tick: u32) -> Option<&mut T> {
+        let index = self.dense_index(entity)?;
+        self.changed[index] = tick;
+        Some(&mut self.values[index])
+    }
+    pub fn changed_tick(&self, entity: Entity) -> Option<u32> {
+        Some(self.changed[self.dense_index(entity)?])
+    }
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+    pub fn entities(&self) -> &[Entity] {
+        &self.entities
+    }
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, &T)> {
+        self.entities.iter().copied().zip(self.values.iter())
+    }
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Entity, &mut T)> {
+        self.entities.iter().copied().zip(self.values.iter_mut())
+    }
+    pub fn clear(&mut self) {
+        self.sparse.clear();
+        self.entities.clear();
+        self.values.clear();
+        self.changed.clear();
+    }
+}
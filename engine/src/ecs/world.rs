@@ -0,0 +1,112 @@
+use std::{any::{Any, TypeId}, cell::{RefCell, Ref, RefMut}, collections::HashMap};
+
+use crate::components::entity::Entity;
+
+use super::{sparse_set::SparseSet, query::{Query, QueryData}};
+
+/// type erased storage so every component type can live in the same map.
+trait ComponentStorage {
+    fn remove_entity(&mut self, entity: Entity);
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: 'static> ComponentStorage for RefCell<SparseSet<T>> {
+    fn remove_entity(&mut self, entity: Entity) {
+        self.get_mut().remove(entity);
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// # World
+/// Component store for entities, every component type gets its own [`SparseSet`].
+/// Storages sit behind a `RefCell` so a query can borrow several of them at
+/// once through a shared reference, borrowing the same type mutably twice panics.
+///
+/// change detection works in ticks: inserting a component or accessing it
+/// mutably stamps it with the current tick, and [`super::query::Changed`] only
+/// matches components stamped since the last [`World::advance_tick`].
+pub struct World {
+    storages: HashMap<TypeId, Box<dyn ComponentStorage>>,
+    tick: u32,
+}
+
+impl World {
+    pub fn new() -> Self {
+        Self { storages: HashMap::new(), tick: 1 }
+    }
+    pub fn tick(&self) -> u32 {
+        self.tick
+    }
+    /// should be called once per frame, after every system ran.
+    pub fn advance_tick(&mut self) {
+        self.tick = self.tick.wrapping_add(1);
+    }
+    fn cell<T: 'static>(&self) -> Option<&RefCell<SparseSet<T>>> {
+        self.storages.get(&TypeId::of::<T>())?.as_any().downcast_ref::<RefCell<SparseSet<T>>>()
+    }
+    pub fn insert<T: 'static>(&mut self, entity: Entity, component: T) -> Option<T> {
+        let tick = self.tick;
+        let storage = self.storages.entry(TypeId::of::<T>()).or_insert_with(|| {
+            Box::new(RefCell::new(SparseSet::<T>::new()))
+        });
+        storage.as_any_mut().downcast_mut::<RefCell<SparseSet<T>>>().unwrap().get_mut().insert_with_tick(entity, component, tick)
+    }
+    pub fn remove<T: 'static>(&mut self, entity: Entity) -> Option<T> {
+        self.storages.get_mut(&TypeId::of::<T>())?
+            .as_any_mut().downcast_mut::<RefCell<SparseSet<T>>>()?
+            .get_mut().remove(entity)
+    }
+    /// removes every component of the entity.
+    pub fn despawn(&mut self, entity: Entity) {
+        for storage in self.storages.values_mut() {
+            storage.remove_entity(entity);
+        }
+    }
+    pub fn has<T: 'static>(&self, entity: Entity) -> bool {
+        self.cell::<T>().map_or(false, |cell| cell.borrow().contains(entity))
+    }
+    pub fn get<T: 'static>(&self, entity: Entity) -> Option<Ref<T>> {
+        Ref::filter_map(self.cell::<T>()?.borrow(), |set| set.get(entity)).ok()
+    }
+    /// mutable access to a single component, marks it as changed.
+    pub fn get_mut<T: 'static>(&self, entity: Entity) -> Option<RefMut<T>> {
+        let tick = self.tick;
+        RefMut::filter_map(self.cell::<T>()?.borrow_mut(), |set| set.get_mut_with_tick(entity, tick)).ok()
+    }
+    /// mutable access through an exclusive borrow of the world, which doesn't need a
+    /// `RefMut` to be kept around. Marks the component as changed.
+    pub fn component_mut<T: 'static>(&mut self, entity: Entity) -> Option<&mut T> {
+        let tick = self.tick;
+        self.storages.get_mut(&TypeId::of::<T>())?
+            .as_any_mut().downcast_mut::<RefCell<SparseSet<T>>>()?
+            .get_mut().get_mut_with_tick(entity, tick)
+    }
+    /// direct access to the storage of a component type, e.g. to iterate it densely.
+    pub fn storage<T: 'static>(&self) -> Option<Ref<SparseSet<T>>> {
+        Some(self.cell::<T>()?.borrow())
+    }
+    pub fn storage_mut<T: 'static>(&self) -> Option<RefMut<SparseSet<T>>> {
+        Some(self.cell::<T>()?.borrow_mut())
+    }
+    pub(crate) fn storage_cell<T: 'static>(&self) -> Option<&RefCell<SparseSet<T>>> {
+        self.cell::<T>()
+    }
+    /// # query
+    /// iterates every entity having all the components in `Q`, for example
+    /// `world.query::<(&mut TransformQuaternion3D, &RigidBody3D)>()`.
+    pub fn query<Q: QueryData>(&self) -> Query<'_, Q> {
+        Query::new(self)
+    }
+}
+
+impl Default for World {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,117 @@
+use std::fmt;
+
+use mofongo::error::MofongoError;
+use yum_mocha::error::MochaError;
+
+use crate::{components::entity::Entity, motor::{assets::AssetError, config::ConfigError, hot_reload::HotReloadError, schedule::ScheduleError, serialization::SceneFormatError}};
+
+/// # EngineError
+/// Any error of the engine, wrapping the ones of the crates and modules below
+/// it so they can all be handled in one place with `?`.
+#[derive(Debug)]
+pub enum EngineError {
+    Io(std::io::Error),
+    Asset(AssetError),
+    /// the renderer or a file it loads, like a model or a texture.
+    Render(MochaError),
+    Physics(MofongoError),
+    Scene(SceneFormatError),
+    Schedule(ScheduleError),
+    HotReload(HotReloadError),
+    Config(ConfigError),
+    /// the window couldn't be opened.
+    Window(winit::error::OsError),
+    /// the asset at the path didn't load, why is in the log of the asset server.
+    NotLoaded(String),
+    /// the entity isn't alive or isn't in the selected scene.
+    MissingEntity(Entity),
+}
+
+impl fmt::Display for EngineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EngineError::Io(error) => write!(f, "io error: {error}"),
+            EngineError::Asset(error) => write!(f, "asset error: {error}"),
+            EngineError::Render(error) => write!(f, "render error: {error}"),
+            EngineError::Physics(error) => write!(f, "physics error: {error}"),
+            EngineError::Scene(error) => write!(f, "scene error: {error}"),
+            EngineError::Schedule(error) => write!(f, "schedule error: {error}"),
+            EngineError::HotReload(error) => write!(f, "hot reload error: {error}"),
+            EngineError::Config(error) => write!(f, "config error: {error}"),
+            EngineError::Window(error) => write!(f, "couldn't open the window: {error}"),
+            EngineError::NotLoaded(path) => write!(f, "{path} didn't load"),
+            EngineError::MissingEntity(id) => write!(f, "no object {id:?} in the selected scene"),
+        }
+    }
+}
+
+impl std::error::Error for EngineError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            EngineError::Io(error) => Some(error),
+            EngineError::Asset(error) => Some(error),
+            EngineError::Render(error) => Some(error),
+            EngineError::Physics(error) => Some(error),
+            EngineError::Scene(error) => Some(error),
+            EngineError::Schedule(error) => Some(error),
+            EngineError::HotReload(error) => Some(error),
+            EngineError::Config(error) => Some(error),
+            EngineError::Window(error) => Some(error),
+            EngineError::NotLoaded(_) | EngineError::MissingEntity(_) => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for EngineError {
+    fn from(error: std::io::Error) -> Self {
+        EngineError::Io(error)
+    }
+}
+
+impl From<AssetError> for EngineError {
+    fn from(error: AssetError) -> Self {
+        EngineError::Asset(error)
+    }
+}
+
+impl From<MochaError> for EngineError {
+    fn from(error: MochaError) -> Self {
+        EngineError::Render(error)
+    }
+}
+
+impl From<MofongoError> for EngineError {
+    fn from(error: MofongoError) -> Self {
+        EngineError::Physics(error)
+    }
+}
+
+impl From<SceneFormatError> for EngineError {
+    fn from(error: SceneFormatError) -> Self {
+        EngineError::Scene(error)
+    }
+}
+
+impl From<ScheduleError> for EngineError {
+    fn from(error: ScheduleError) -> Self {
+        EngineError::Schedule(error)
+    }
+}
+
+impl From<HotReloadError> for EngineError {
+    fn from(error: HotReloadError) -> Self {
+        EngineError::HotReload(error)
+    }
+}
+
+impl From<ConfigError> for EngineError {
+    fn from(error: ConfigError) -> Self {
+        EngineError::Config(error)
+    }
+}
+
+impl From<winit::error::OsError> for EngineError {
+    fn from(error: winit::error::OsError) -> Self {
+        EngineError::Window(error)
+    }
+}
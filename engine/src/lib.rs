@@ -1,4 +1,8 @@
 mod components;
 mod motor;
+mod ecs;
+mod error;
 pub use components::*;
-pub use motor::*;
\ No newline at end of file
+pub use motor::*;
+pub use ecs::*;
+pub use error::EngineError;
\ No newline at end of file
@@ -1,82 +1,100 @@
 #![allow(unused)]
-use std::{time::Instant, rc::Rc, cell::RefCell};
+use std::{rc::Rc, cell::RefCell};
 
 use ash::{vk, Entry};
 use drowsed_math::{{FVec3, TransformQuaternion3D}, complex::quaternion::Quaternion, LinearSegment};
-use components::{object::BasicObject, scene::Scene, rendering::models::{Model, FromFBX}};
-use motor::{SchonMotor, system_manager::SystemManagerInfo, SolidMotor};
+use components::{object::BasicObject, scene::Scene, rendering::models::{Model, FromFBX}, behaviour::{Behaviour, BehaviourContext}, entity::Entity};
+use error::EngineError;
+use motor::{logging::Logger, config::EngineConfig, SchonMotor, system_manager::{RenderTarget, FrameInfo}, SolidMotor, prefab::{Prefab, PrefabCollider}};
 use winit::{window::WindowBuilder, event_loop::{EventLoop, ControlFlow}, dpi::LogicalSize, event::WindowEvent};
 use yum_mocha::{self, input::input_state::GlobalInputState, debug::DebugMovement, camera, vk_obj::{device::WindowOption, buffer::img::ImageTexture, self}, model::vertex::GlobalDebugVertex};
-use mofongo::{bodies::RigidBody, solid::collisions::gjk::GJKColliderSolid};
+use mofongo::{bodies::RigidBody, collider::CollisionInfo, solid::{collisions::gjk::GJKColliderSolid, physics::rigidbody::RigidBody3D}};
 mod components;
 mod motor;
+mod ecs;
+mod error;
 use drowsed_math::Segment;
-fn main() {
+/// prints the collisions of the object it is attached to.
+struct CollisionLogger;
+impl Behaviour<GlobalDebugVertex, FVec3, u32, TransformQuaternion3D, RigidBody3D> for CollisionLogger {
+    fn on_collision(&mut self, _context: &mut BehaviourContext<GlobalDebugVertex, FVec3, u32, TransformQuaternion3D, RigidBody3D>, _other: Entity, info: &CollisionInfo<FVec3>) {
+        log::info!("collision {:?}", info);
+    }
+}
+/// points the descriptor sets of both frames at the texture.
+fn bind_texture(schonmotor: &SchonMotor<SolidMotor>, texture: &ImageTexture) {
+    for i in 0..2 {
+        let info = texture.get_info(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL);
+        let writer = vk_obj::descriptors::DescriptorWriter::new().add_image_buffer(schonmotor.device_manager.sets[i], 1, 0, 0, &info);
+        writer.write(schonmotor.device_manager.device.clone());
+    }
+}
+fn main() -> Result<(), EngineError> {
+    Logger::init_from_env().expect("couldn't set the logger");
+    // where to write a chrome trace of the run, profiling is off without it
+    let trace_path = std::env::var_os("SCHON_TRACE").map(std::path::PathBuf::from);
+    let config = EngineConfig::from_args(std::env::args().skip(1))?;
     let global_input = GlobalInputState::new();
     let mut debug_movement = DebugMovement::new(global_input.clone());
     
     let mut camera = camera::Camera::default();
     camera.set_direction(debug_movement.transform.translation, debug_movement.transform.rotation.to_euler(), FVec3::new(0.0, -1.0, 0.0));
     
-    let monke = Rc::new(Model::<GlobalDebugVertex>::from_fbx("untitled.fbx")[0].clone());
-    let cube = Rc::new(Model::<GlobalDebugVertex>::from_fbx("untitled.fbx")[0].clone());
-    let mut scene = Scene::new(vec![camera]);
-    
-    scene.push_object(0, BasicObject::new(TransformQuaternion3D { translation: FVec3::new(0.0, -10.0, 0.0), ..Default::default() }));
-    scene.push_object(1, BasicObject::new(TransformQuaternion3D::default()));
-
-    let vertices1: Vec<_> = cube.vertices.clone().iter().map(|v| {
-        v.pos
-    }).collect();
-    let vertices2: Vec<_> = cube.vertices.clone().iter().map(|v| {
-        v.pos
-    }).collect();
-    let collider1 = Rc::new(RefCell::new(GJKColliderSolid::new(vertices1.clone())));
-    let collider2 = Rc::new(RefCell::new(GJKColliderSolid::new(vertices2.clone())));
+    let scene = Scene::new(vec![camera]);
 
     let event_loop = EventLoop::new();
     let mut resized = false;
     let window = std::sync::Arc::new(WindowBuilder::new()
-        .with_title("Holly Tree")
-        .with_inner_size(LogicalSize::new(1024, 768))
-        .build(&event_loop).unwrap()
+        .with_title(config.window.title.as_str())
+        .with_inner_size(LogicalSize::new(config.window.width, config.window.height))
+        .build(&event_loop)?
     );
-    window.set_cursor_visible(false);
+    window.set_cursor_visible(config.window.cursor_visible);
 
     let entry = Entry::linked();
-    let info = SystemManagerInfo {
-        global_gravity: FVec3::new(0.0, 0.0, 0.0),
-    };
-    let mut schonmotor = SchonMotor::<SolidMotor>::new(&entry, WindowOption::Winit(window.clone()), &info);
+    let mut schonmotor = SchonMotor::<SolidMotor>::try_new(&entry, WindowOption::Winit(window.clone()), &config)?;
+    let device = schonmotor.device_manager.device.clone();
+    let model_handle = schonmotor.system_manager.assets.load::<Model<GlobalDebugVertex>>("untitled.fbx");
+    let texture_handle = schonmotor.system_manager.assets.load::<ImageTexture>("Miles.JPG");
     schonmotor.push_scene(scene);
-    let texture = ImageTexture::new(schonmotor.device_manager.device.clone(), "Miles.JPG");
-    for i in 0..2 {
-        let info = texture.get_info(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL);
-        let writer = vk_obj::descriptors::DescriptorWriter::new().add_image_buffer(schonmotor.device_manager.sets[i], 1, 0, 0, &info);
-        writer.write(schonmotor.device_manager.device.clone());
+    schonmotor.system_manager.add_solid_systems();
+    // a misspelled or contradicting constraint shows up here instead of on the first frame
+    schonmotor.system_manager.schedule.build()?;
+    schonmotor.system_manager.input = Some(global_input.clone());
+
+    let model = schonmotor.system_manager.assets.wait(&model_handle, Some(&device)).ok_or_else(|| EngineError::NotLoaded("untitled.fbx".to_string()))?;
+    let cube = Prefab::new(model).with_collider(PrefabCollider::Gjk { max_vertices: Some(32) });
+    let monke = cube.clone().with_rigidbody(&RigidBody3D::new(0.6));
+    let monke_id = schonmotor.spawn(&monke, TransformQuaternion3D { translation: FVec3::new(0.0, -10.0, 0.0), ..Default::default() })?;
+    schonmotor.spawn(&cube, TransformQuaternion3D::default())?;
+    let mut texture = schonmotor.system_manager.assets.wait(&texture_handle, Some(&device)).ok_or_else(|| EngineError::NotLoaded("Miles.JPG".to_string()))?;
+    bind_texture(&schonmotor, &texture);
+    schonmotor.enable_hot_reload();
+    if trace_path.is_some() {
+        if let Err(error) = schonmotor.enable_profiling() {
+            log::warn!("no gpu timestamps, only profiling the cpu: {error}");
+        }
     }
-    let mut current_time = Instant::now();
-    let mut delta_time = 0.0;
-    let mut y= 0.0;
     {
-        schonmotor.system_manager.collisions.push(0, collider1.clone());
-        schonmotor.system_manager.collisions.push(1, collider2.clone());
-        schonmotor.system_manager.rendering.push(schonmotor.device_manager.device.clone(), 0, monke.clone());
-        schonmotor.system_manager.rendering.push(schonmotor.device_manager.device.clone(), 1, cube.clone());
-        schonmotor.system_manager.physics.push(0, mofongo::solid::physics::rigidbody::RigidBody3D::new(0.6));
+        schonmotor.system_manager.attach(monke_id, CollisionLogger);
         event_loop.run(move |event, _, control_flow| {
             *control_flow = ControlFlow::Poll;
 
-            #[allow(unused_assignments)]
-            let mut new_time = Instant::now();
-
             match event {
                 winit::event::Event::DeviceEvent { device_id: _, event: _ } => {}
                 winit::event::Event::WindowEvent {
                     event: WindowEvent::CloseRequested,
                     window_id,
                 } 
-                if window_id == window.id() => *control_flow = ControlFlow::Exit,
+                if window_id == window.id() => {
+                    if let Some(path) = &trace_path {
+                        match schonmotor.system_manager.profiler.save_chrome_trace(path) {
+                            Ok(()) => log::info!("wrote trace to {}", path.display()),
+                            Err(error) => log::error!("couldn't write trace to {}: {error}", path.display()),
+                        }
+                    }
+                    *control_flow = ControlFlow::Exit;
+                }
                 winit::event::Event::WindowEvent { window_id: _, event: WindowEvent::Resized(_) } => {
                     resized = true;
                 }
@@ -88,69 +106,90 @@ fn main() {
                     inputlock.poll(input);
                 }
                 winit::event::Event::RedrawRequested(window_id) if window_id == window.id() => {
+                    let frame_start = std::time::Instant::now();
+                    if let Err(error) = schonmotor.hot_reload() {
+                        log::error!("hot reload failed: {error}");
+                    }
                     let aspect = schonmotor.device_manager.renderer.get_aspect_ratio();
                     let scene = schonmotor.system_manager.scene_manager.get_selected_scene_mut();
                     let get_camera = scene.get_camera_mut();
-                    get_camera.set_perspective_projection(0.872665, aspect, 0.1, 150.0);
-                    new_time = Instant::now();
-                    let cmd_buffer = schonmotor.device_manager.renderer.begin_command_buffer().unwrap();
+                    get_camera.set_perspective_projection(config.camera.fov, aspect, config.camera.near, config.camera.far);
+                    schonmotor.system_manager.time.tick();
+                    let cmd_buffer = match schonmotor.device_manager.renderer.begin_command_buffer() {
+                        Ok(cmd_buffer) => cmd_buffer,
+                        // the swapchain was just made again, the next redraw uses it
+                        Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => return,
+                        Err(error) => {
+                            log::error!("couldn't begin the frame: {error}");
+                            *control_flow = ControlFlow::Exit;
+                            return;
+                        }
+                    };
+                    // the timings of the frame that last used this command buffer are in now
+                    for span in schonmotor.device_manager.renderer.take_gpu_spans() {
+                        schonmotor.system_manager.profiler.record_gpu(span.name, span.start, span.duration);
+                    }
 
                     schonmotor.device_manager.renderer.begin_render_pass(cmd_buffer);
 
                     unsafe { schonmotor.device_manager.device.device.cmd_bind_pipeline(cmd_buffer, vk::PipelineBindPoint::GRAPHICS, schonmotor.device_manager.graphics.pipelines[0]) };
-                    let body = schonmotor.system_manager.physics.get_rigidbody_by_id_mut(0).unwrap();
 
                     unsafe { schonmotor.device_manager.device.device.cmd_bind_descriptor_sets(cmd_buffer, vk::PipelineBindPoint::GRAPHICS, schonmotor.device_manager.layout, 0, &[schonmotor.device_manager.sets[schonmotor.device_manager.renderer.swapchain.current_frame]], &[]) };
                     {
-                        debug_movement.transform = debug_movement.movement(global_input.clone(), delta_time);
+                        debug_movement.transform = debug_movement.movement(global_input.clone(), schonmotor.system_manager.time.real_delta());
 
                         get_camera.set_view_yxz(debug_movement.transform.translation, debug_movement.transform.rotation.to_euler());
 
-                        // Model Rotation Code
-                        if let Some(entity) = scene.get_object_by_id_mut(0) {}
-                        if let Some(entity) = scene.get_object_by_id_mut(1) {}
-                        if let Some(entity) = scene.get_object_by_id_mut(2) {
-                            entity.transform.rotation = Quaternion::<f32>::from_euler(FVec3::new(0.0, y, y));
-                            entity.transform.translation.y -= 1.0 * delta_time;
-                        }
-                        if let Some((collider, info)) = schonmotor.system_manager.collisions.get_collider_by_id(0) {
-                            let cell = collider.borrow();
-                            if let Some(i) = info.get() {
-                                println!("info {:?}", i);
-                            }
-                        } else {
-                        }
-                        y += 0.1  * delta_time;
                     }
                     let lock = global_input.lock().unwrap();
 
-                    if lock.is_pressed(winit::event::VirtualKeyCode::F) {
-                        body.apply_force(FVec3::new(0.006, 10.0, 0.0), FVec3::new(0.006, 0.0, 0.0));
-                    }
-                    if lock.is_pressed(winit::event::VirtualKeyCode::H) {
-                        body.apply_force(FVec3::new(0.5, 0.0, 0.0), FVec3::new(0.0, 0.0, 0.0));
-                    }
-                    if lock.is_pressed(winit::event::VirtualKeyCode::T) {
-                        body.apply_force(FVec3::new(-0.5, 0.0, 0.0), FVec3::new(0.0, 0.0, 0.0));
+                    // scripts can despawn it
+                    if let Some(body) = schonmotor.system_manager.get_rigidbody_mut(&monke_id) {
+                        if lock.is_pressed(winit::event::VirtualKeyCode::F) {
+                            body.apply_force(FVec3::new(0.006, 10.0, 0.0), FVec3::new(0.006, 0.0, 0.0));
+                        }
+                        if lock.is_pressed(winit::event::VirtualKeyCode::H) {
+                            body.apply_force(FVec3::new(0.5, 0.0, 0.0), FVec3::new(0.0, 0.0, 0.0));
+                        }
+                        if lock.is_pressed(winit::event::VirtualKeyCode::T) {
+                            body.apply_force(FVec3::new(-0.5, 0.0, 0.0), FVec3::new(0.0, 0.0, 0.0));
+                        }
                     }
                     drop(lock);
-                    schonmotor.system_manager.collisions.render(schonmotor.device_manager.device.clone(), &mut schonmotor.system_manager.scene_manager);
-                    schonmotor.system_manager.physics.render(schonmotor.device_manager.device.clone(), delta_time, &mut schonmotor.system_manager.scene_manager);
-                    schonmotor.system_manager.rendering.render(schonmotor.device_manager.device.clone(), cmd_buffer, schonmotor.device_manager.layout, &schonmotor.system_manager.scene_manager);
+                    // time was already ticked for the camera, so run the schedule with this frames delta
+                    let delta_time = schonmotor.system_manager.time.delta();
+                    let result = schonmotor.system_manager.run_schedule(FrameInfo { delta_time, target: Some(RenderTarget {
+                        device: schonmotor.device_manager.device.clone(),
+                        command_buffer: cmd_buffer,
+                        layout: schonmotor.device_manager.layout,
+                        skinned: Some(schonmotor.device_manager.skinned_target()),
+                    }) });
+                    if let Err(error) = result {
+                        log::error!("couldn't run the systems: {error}");
+                        *control_flow = ControlFlow::Exit;
+                    }
                     schonmotor.device_manager.renderer.clear_value = vk::ClearColorValue {float32: [0.0, 0.0, 0.0, 1.0] };
 
                     schonmotor.device_manager.renderer.end(cmd_buffer);
 
                     let suboptimal = schonmotor.device_manager.renderer.draw(vec![cmd_buffer]);
-                    unsafe { schonmotor.device_manager.device.device.device_wait_idle().unwrap() };
+                    if let Err(error) = unsafe { schonmotor.device_manager.device.device.device_wait_idle() } {
+                        log::error!("lost the device: {error}");
+                        *control_flow = ControlFlow::Exit;
+                        return;
+                    }
+                    // the descriptor sets can only change once the frame using them is done
+                    if let Some((_, _, reloaded)) = schonmotor.system_manager.assets.take_replaced::<ImageTexture>().pop() {
+                        bind_texture(&schonmotor, &reloaded);
+                        texture = reloaded;
+                    }
 
-                    delta_time = (new_time - current_time).as_secs_f32();
-                    current_time = new_time;
                     if suboptimal == Err(vk::Result::ERROR_OUT_OF_DATE_KHR) || suboptimal == Ok(true) || resized
                     {
                         resized = false;
                         schonmotor.device_manager.renderer.recreate_swapchain();
                     }
+                    schonmotor.system_manager.profiler.record("frame", "frame", frame_start, std::time::Instant::now());
                 }
                 winit::event::Event::MainEventsCleared => {
                     window.request_redraw();
@@ -0,0 +1,409 @@
+use std::{any::{Any, TypeId}, collections::HashMap, fmt, marker::PhantomData, panic::AssertUnwindSafe, path::{Path, PathBuf}, rc::{Rc, Weak}, sync::{Arc, mpsc::{self, Receiver, Sender}}, thread};
+
+use yum_mocha::{error::MochaError, model::vertex::GlobalDebugVertex, vk_obj::{buffer::img::{DecodedImage, ImageTexture}, device::ReplacingDevice}};
+
+use crate::components::rendering::models::{FromFBX, Model};
+
+use super::hot_reload::FileWatcher;
+
+/// # Asset
+/// Something the [`AssetServer`] can load from a file. Loading is split in two:
+/// `decode` reads the file on the worker thread, `finish` turns the result into
+/// the asset on the main thread, where the device lives.
+pub trait Asset: Sized + 'static {
+    type Decoded: Send + 'static;
+    fn decode(path: &Path) -> Result<Self::Decoded, AssetError>;
+    /// gives the decoded data back when it can't finish yet, like without a device.
+    fn finish(decoded: Self::Decoded, device: Option<&Arc<ReplacingDevice>>) -> Result<Self, Self::Decoded>;
+}
+
+/// the first model of an fbx file.
+impl Asset for Model<GlobalDebugVertex> {
+    type Decoded = Self;
+    fn decode(path: &Path) -> Result<Self, AssetError> {
+        Model::<GlobalDebugVertex>::try_from_fbx(&path.to_string_lossy())?
+            .into_iter()
+            .next()
+            .ok_or(AssetError::Empty)
+    }
+    fn finish(decoded: Self, _device: Option<&Arc<ReplacingDevice>>) -> Result<Self, Self> {
+        Ok(decoded)
+    }
+}
+
+/// decoded on the worker, uploaded once there is a device.
+impl Asset for ImageTexture {
+    type Decoded = DecodedImage;
+    fn decode(path: &Path) -> Result<DecodedImage, AssetError> {
+        DecodedImage::open(&path.to_string_lossy()).map_err(|error| AssetError::Decode(error.into()))
+    }
+    fn finish(decoded: DecodedImage, device: Option<&Arc<ReplacingDevice>>) -> Result<Self, DecodedImage> {
+        match device {
+            Some(device) => Ok(ImageTexture::from_decoded(device.clone(), &decoded)),
+            None => Err(decoded),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum AssetError {
+    NotFound(PathBuf),
+    Decode(MochaError),
+    /// the file was read but had nothing in it to load.
+    Empty,
+    /// the loader panicked, the message if it had one.
+    Panicked(String),
+}
+
+impl fmt::Display for AssetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AssetError::NotFound(path) => write!(f, "no file at {}", path.display()),
+            AssetError::Decode(error) => write!(f, "couldn't decode the file: {error}"),
+            AssetError::Empty => write!(f, "the file has nothing to load"),
+            AssetError::Panicked(message) => write!(f, "the loader panicked: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for AssetError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AssetError::Decode(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<MochaError> for AssetError {
+    fn from(error: MochaError) -> Self {
+        AssetError::Decode(error)
+    }
+}
+
+#[derive(Debug)]
+pub enum LoadState {
+    Loading,
+    Loaded,
+    Failed(AssetError),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AssetId(u64);
+
+/// # AssetEvent
+/// sent by the `assets` system once a load is over, see [`AssetServer::update`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssetEvent {
+    Loaded(AssetId),
+    Failed(AssetId),
+    /// the file changed and the asset was loaded again, see [`AssetServer::take_replaced`].
+    Reloaded(AssetId),
+}
+
+struct HandleInner {
+    id: AssetId,
+    path: PathBuf,
+}
+
+/// # Handle
+/// Typed reference to an asset of the [`AssetServer`]. Clones point at the same
+/// asset, which gets unloaded once the last handle to it is dropped.
+pub struct Handle<A> {
+    inner: Rc<HandleInner>,
+    phantom: PhantomData<fn() -> A>,
+}
+
+impl<A> Handle<A> {
+    pub fn id(&self) -> AssetId {
+        self.inner.id
+    }
+    pub fn path(&self) -> &Path {
+        &self.inner.path
+    }
+}
+
+impl<A> Clone for Handle<A> {
+    fn clone(&self) -> Self {
+        Self { inner: self.inner.clone(), phantom: PhantomData }
+    }
+}
+
+impl<A> PartialEq for Handle<A> {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner.id == other.inner.id
+    }
+}
+
+impl<A> Eq for Handle<A> {}
+
+impl<A> fmt::Debug for Handle<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Handle").field("id", &self.inner.id).field("path", &self.inner.path).finish()
+    }
+}
+
+type Decoded = Box<dyn Any + Send>;
+/// reads the file of one asset type, see [`Asset::decode`].
+type Decode = fn(&Path) -> Result<Decoded, AssetError>;
+/// finishes the decoded data of one asset type, see [`Asset::finish`].
+type Finish = fn(Decoded, Option<&Arc<ReplacingDevice>>) -> Result<Rc<dyn Any>, Decoded>;
+
+struct Job {
+    id: AssetId,
+    decode: Box<dyn FnOnce() -> Result<Decoded, AssetError> + Send>,
+}
+
+struct Done {
+    id: AssetId,
+    result: Result<Decoded, AssetError>,
+}
+
+struct Entry {
+    key: (TypeId, PathBuf),
+    handle: Weak<HandleInner>,
+    state: LoadState,
+    asset: Option<Rc<dyn Any>>,
+    decode: Decode,
+    finish: Finish,
+    /// decoded but waiting for a device to finish.
+    pending: Option<Decoded>,
+}
+
+fn decode_erased<A: Asset>(path: &Path) -> Result<Decoded, AssetError> {
+    if !path.exists() {
+        return Err(AssetError::NotFound(path.to_path_buf()));
+    }
+    A::decode(path).map(|decoded| Box::new(decoded) as Decoded)
+}
+
+fn finish_erased<A: Asset>(decoded: Decoded, device: Option<&Arc<ReplacingDevice>>) -> Result<Rc<dyn Any>, Decoded> {
+    let decoded = *decoded.downcast::<A::Decoded>().expect("decoded data of another asset type");
+    match A::finish(decoded, device) {
+        Ok(asset) => Ok(Rc::new(asset)),
+        Err(decoded) => Err(Box::new(decoded)),
+    }
+}
+
+/// # AssetServer
+/// Loads assets on a worker thread and hands out [`Handle`]s to them. Loading the
+/// same path as the same type twice gives a handle to the same asset instead of
+/// reading the file again. Assets are finished and unloaded by [`AssetServer::update`],
+/// which has to run every frame on the main thread, the default systems of the
+/// [`super::system_manager::SystemManager`] take care of it.
+/// With [`AssetServer::enable_hot_reload`] assets get loaded again when their file changes.
+pub struct AssetServer {
+    entries: HashMap<AssetId, Entry>,
+    paths: HashMap<(TypeId, PathBuf), AssetId>,
+    next_id: u64,
+    jobs: Sender<Job>,
+    done: Receiver<Done>,
+    events: Vec<AssetEvent>,
+    /// the old and new asset of every reload finished by the last update.
+    replaced: Vec<(AssetId, Rc<dyn Any>, Rc<dyn Any>)>,
+    watcher: Option<FileWatcher>,
+}
+
+impl AssetServer {
+    pub fn new() -> Self {
+        let (jobs, job_receiver) = mpsc::channel::<Job>();
+        let (done_sender, done) = mpsc::channel();
+        thread::Builder::new()
+            .name("asset loader".to_string())
+            .spawn(move || {
+                // ends once the server drops its sender
+                for job in job_receiver {
+                    let result = std::panic::catch_unwind(AssertUnwindSafe(job.decode)).unwrap_or_else(|panic| {
+                        let message = panic.downcast_ref::<&str>().map(|message| message.to_string())
+                            .or_else(|| panic.downcast_ref::<String>().cloned())
+                            .unwrap_or_default();
+                        Err(AssetError::Panicked(message))
+                    });
+                    if done_sender.send(Done { id: job.id, result }).is_err() {
+                        break;
+                    }
+                }
+            })
+            .expect("couldn't spawn the asset loader thread");
+        Self { entries: HashMap::new(), paths: HashMap::new(), next_id: 0, jobs, done, events: vec![], replaced: vec![], watcher: None }
+    }
+    /// # load
+    /// starts loading the file at `path` in the background, or returns a handle
+    /// to the asset if it is already loaded or loading.
+    pub fn load<A: Asset>(&mut self, path: impl AsRef<Path>) -> Handle<A> {
+        let path = path.as_ref();
+        let path = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        let key = (TypeId::of::<A>(), path.clone());
+        if let Some(inner) = self.paths.get(&key).and_then(|id| self.entries.get(id)).and_then(|entry| entry.handle.upgrade()) {
+            return Handle { inner, phantom: PhantomData };
+        }
+
+        let id = AssetId(self.next_id);
+        self.next_id += 1;
+        let inner = Rc::new(HandleInner { id, path: path.clone() });
+        self.entries.insert(id, Entry {
+            key: key.clone(),
+            handle: Rc::downgrade(&inner),
+            state: LoadState::Loading,
+            asset: None,
+            decode: decode_erased::<A>,
+            finish: finish_erased::<A>,
+            pending: None,
+        });
+        self.paths.insert(key, id);
+        if let Some(watcher) = &mut self.watcher {
+            watcher.watch(path.clone());
+        }
+        self.queue(id, path, decode_erased::<A>);
+        Handle { inner, phantom: PhantomData }
+    }
+    fn queue(&mut self, id: AssetId, path: PathBuf, decode: Decode) {
+        let decode = Box::new(move || decode(&path));
+        if self.jobs.send(Job { id, decode }).is_err() {
+            if let Some(entry) = self.entries.get_mut(&id) {
+                entry.state = LoadState::Failed(AssetError::Panicked("the asset loader thread is gone".to_string()));
+            }
+        }
+    }
+    /// # enable_hot_reload
+    /// watches the files of every asset, loading them again once they change.
+    pub fn enable_hot_reload(&mut self) {
+        let mut watcher = FileWatcher::default();
+        for entry in self.entries.values() {
+            watcher.watch(entry.key.1.clone());
+        }
+        self.watcher = Some(watcher);
+    }
+    /// # reload
+    /// loads every asset of the file at `path` again. They keep their old value
+    /// until the new one is finished, and keep it if loading fails.
+    pub fn reload(&mut self, path: impl AsRef<Path>) {
+        let path = path.as_ref();
+        let path = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        let reloading: Vec<(AssetId, Decode)> = self.entries.iter()
+            .filter(|(_, entry)| entry.key.1 == path)
+            .map(|(id, entry)| (*id, entry.decode))
+            .collect();
+        for (id, decode) in reloading {
+            self.queue(id, path.clone(), decode);
+        }
+    }
+    /// # take_replaced
+    /// the assets of type `A` reloaded by the last [`AssetServer::update`], the old
+    /// one and the new one, for swapping out whatever was made from the old one.
+    pub fn take_replaced<A: Asset>(&mut self) -> Vec<(AssetId, Rc<A>, Rc<A>)> {
+        let (taken, rest): (Vec<_>, Vec<_>) = std::mem::take(&mut self.replaced).into_iter()
+            .partition(|(_, old, _)| old.is::<A>());
+        self.replaced = rest;
+        taken.into_iter()
+            .filter_map(|(id, old, new)| Some((id, old.downcast::<A>().ok()?, new.downcast::<A>().ok()?)))
+            .collect()
+    }
+    pub fn state<A>(&self, handle: &Handle<A>) -> &LoadState {
+        &self.entries[&handle.id()].state
+    }
+    pub fn is_loaded<A>(&self, handle: &Handle<A>) -> bool {
+        matches!(self.state(handle), LoadState::Loaded)
+    }
+    /// the asset, `None` until it is loaded. When reloading it fails the old one is still there.
+    pub fn get<A: Asset>(&self, handle: &Handle<A>) -> Option<Rc<A>> {
+        let asset = self.entries.get(&handle.id())?.asset.clone()?;
+        asset.downcast::<A>().ok()
+    }
+    fn receive(&mut self, done: Done) {
+        // unloaded while it was loading
+        let Some(entry) = self.entries.get_mut(&done.id) else {
+            return;
+        };
+        match done.result {
+            Ok(decoded) => entry.pending = Some(decoded),
+            Err(error) => {
+                log::warn!("couldn't load {}: {error}", entry.key.1.display());
+                entry.state = LoadState::Failed(error);
+                self.events.push(AssetEvent::Failed(done.id));
+            }
+        }
+    }
+    fn finish_pending(&mut self, device: Option<&Arc<ReplacingDevice>>) {
+        for (id, entry) in &mut self.entries {
+            let Some(decoded) = entry.pending.take() else {
+                continue;
+            };
+            match (entry.finish)(decoded, device) {
+                Ok(asset) => {
+                    entry.state = LoadState::Loaded;
+                    match entry.asset.replace(asset.clone()) {
+                        Some(old) => {
+                            log::info!("reloaded {}", entry.key.1.display());
+                            self.replaced.push((*id, old, asset));
+                            self.events.push(AssetEvent::Reloaded(*id));
+                        }
+                        None => self.events.push(AssetEvent::Loaded(*id)),
+                    }
+                }
+                Err(decoded) => entry.pending = Some(decoded),
+            }
+        }
+    }
+    /// # update
+    /// finishes the assets the worker decoded since the last call and unloads
+    /// the ones without handles left, returning what happened since the last call.
+    /// Assets only get dropped once everything holding on to what
+    /// [`AssetServer::get`] returned lets go too.
+    pub fn update(&mut self, device: Option<&Arc<ReplacingDevice>>) -> Vec<AssetEvent> {
+        self.replaced.clear();
+        let changed = self.watcher.as_mut().map(FileWatcher::poll).unwrap_or_default();
+        for path in changed {
+            self.reload(path);
+        }
+        while let Ok(done) = self.done.try_recv() {
+            self.receive(done);
+        }
+        self.finish_pending(device);
+
+        let unused: Vec<AssetId> = self.entries.iter()
+            .filter(|(_, entry)| entry.handle.strong_count() == 0)
+            .map(|(id, _)| *id)
+            .collect();
+        for id in unused {
+            let Some(entry) = self.entries.remove(&id) else {
+                continue;
+            };
+            // the path might have been loaded again since
+            if self.paths.get(&entry.key) == Some(&id) {
+                self.paths.remove(&entry.key);
+            }
+            if let Some(watcher) = &mut self.watcher {
+                if !self.entries.values().any(|other| other.key.1 == entry.key.1) {
+                    watcher.unwatch(&entry.key.1);
+                }
+            }
+        }
+        std::mem::take(&mut self.events)
+    }
+    /// # wait
+    /// blocks until the asset is done loading, `None` if it failed, see
+    /// [`AssetServer::state`] for why. Events of assets finished while waiting
+    /// come with the next [`AssetServer::update`].
+    pub fn wait<A: Asset>(&mut self, handle: &Handle<A>, device: Option<&Arc<ReplacingDevice>>) -> Option<Rc<A>> {
+        while matches!(self.state(handle), LoadState::Loading) {
+            if self.entries[&handle.id()].pending.is_none() {
+                let Ok(done) = self.done.recv() else {
+                    break;
+                };
+                self.receive(done);
+            } else if device.is_none() {
+                // can't finish without a device, waiting won't change that
+                break;
+            }
+            self.finish_pending(device);
+        }
+        self.get(handle)
+    }
+}
+
+impl Default for AssetServer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,256 @@
+use std::{fmt, path::{Path, PathBuf}};
+
+use ash::vk;
+use serde::{Deserialize, Serialize};
+use yum_mocha::vk_obj::rendering::RenderOptions;
+
+/// file [`EngineConfig::from_args`] reads when no `--config` is given, if it exists.
+pub const DEFAULT_CONFIG_PATH: &str = "./engine.toml";
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io { path: PathBuf, error: std::io::Error },
+    /// the file or the overrides don't fit an [`EngineConfig`].
+    Parse(String),
+    /// a command line argument that isn't `--config <path>` or `--<key> <value>`.
+    Argument(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io { path, error } => write!(f, "couldn't read config \"{}\": {error}", path.display()),
+            Self::Parse(error) => write!(f, "couldn't read config: {error}"),
+            Self::Argument(argument) => write!(f, "don't know what to do with argument \"{argument}\""),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// # EngineConfig
+/// Settings [`super::SchonMotor::try_new`] starts the engine with. Everything left out
+/// of the file keeps its default, so an empty file is the same as no file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EngineConfig {
+    pub window: WindowConfig,
+    pub graphics: GraphicsConfig,
+    pub camera: CameraConfig,
+    pub physics: PhysicsConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindowConfig {
+    pub title: String,
+    /// logical size of the inside of the window.
+    pub width: u32,
+    pub height: u32,
+    pub cursor_visible: bool,
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        Self { title: String::from("Holly Tree"), width: 1024, height: 768, cursor_visible: false }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PresentMode {
+    Fifo,
+    FifoRelaxed,
+    Mailbox,
+    Immediate,
+}
+
+impl From<PresentMode> for vk::PresentModeKHR {
+    fn from(mode: PresentMode) -> Self {
+        match mode {
+            PresentMode::Fifo => vk::PresentModeKHR::FIFO,
+            PresentMode::FifoRelaxed => vk::PresentModeKHR::FIFO_RELAXED,
+            PresentMode::Mailbox => vk::PresentModeKHR::MAILBOX,
+            PresentMode::Immediate => vk::PresentModeKHR::IMMEDIATE,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GraphicsConfig {
+    /// waits for the display instead of tearing, only used without a `present_mode`.
+    pub vsync: bool,
+    /// the present mode to try first, falls back on what `vsync` picks.
+    pub present_mode: Option<PresentMode>,
+    /// turns on the Vulkan validation layers.
+    pub validation: bool,
+    /// samples per pixel, 1 turns msaa off. Lowered to what the device supports.
+    pub msaa: u32,
+    pub vertex_shader: String,
+    pub fragment_shader: String,
+    /// vertex shader of skinned models in [`crate::components::rendering::skinning::SkinningMode::Gpu`],
+    /// drawn with `fragment_shader`.
+    pub skinned_vertex_shader: String,
+}
+
+impl Default for GraphicsConfig {
+    fn default() -> Self {
+        Self {
+            vsync: true,
+            present_mode: None,
+            validation: true,
+            msaa: 1,
+            vertex_shader: String::from("./shaders/vertex3normaluv.vert.spv"),
+            fragment_shader: String::from("./shaders/vertex3normaluv.frag.spv"),
+            skinned_vertex_shader: String::from("./shaders/vertex3skinned.vert.spv"),
+        }
+    }
+}
+
+impl GraphicsConfig {
+    /// # render_options
+    /// the present modes in order of preference and the samples, rounded down to a power of two.
+    pub fn render_options(&self) -> RenderOptions {
+        let mut present_modes: Vec<vk::PresentModeKHR> = self.present_mode.into_iter().map(Into::into).collect();
+        if self.vsync {
+            present_modes.extend([vk::PresentModeKHR::MAILBOX, vk::PresentModeKHR::FIFO]);
+        } else {
+            present_modes.extend([vk::PresentModeKHR::IMMEDIATE, vk::PresentModeKHR::MAILBOX, vk::PresentModeKHR::FIFO_RELAXED]);
+        }
+        let samples = 1 << (31 - self.msaa.clamp(1, 64).leading_zeros());
+        RenderOptions { present_modes, samples: vk::SampleCountFlags::from_raw(samples) }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CameraConfig {
+    /// vertical field of view in radians.
+    pub fov: f32,
+    pub near: f32,
+    pub far: f32,
+}
+
+impl Default for CameraConfig {
+    fn default() -> Self {
+        Self { fov: 0.872665, near: 0.1, far: 150.0 }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PhysicsConfig {
+    /// physics steps per second, 0 steps once every frame with its delta.
+    pub rate: f32,
+    pub gravity: [f32; 3],
+}
+
+impl PhysicsConfig {
+    /// seconds per physics step, `None` for a variable step.
+    pub fn step(&self) -> Option<f32> {
+        (self.rate > 0.0).then(|| 1.0 / self.rate)
+    }
+}
+
+impl EngineConfig {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        Self::load_with_overrides(path.as_ref(), toml::Table::new())
+    }
+    pub fn from_toml(text: &str) -> Result<Self, ConfigError> {
+        let table = text.parse::<toml::Table>().map_err(|error| ConfigError::Parse(error.to_string()))?;
+        Self::from_table(table)
+    }
+    fn from_table(table: toml::Table) -> Result<Self, ConfigError> {
+        toml::Value::Table(table).try_into().map_err(|error: toml::de::Error| ConfigError::Parse(error.to_string()))
+    }
+    fn load_with_overrides(path: &Path, overrides: toml::Table) -> Result<Self, ConfigError> {
+        let text = std::fs::read_to_string(path).map_err(|error| ConfigError::Io { path: path.to_path_buf(), error })?;
+        let mut table = text.parse::<toml::Table>().map_err(|error| ConfigError::Parse(format!("{}: {error}", path.display())))?;
+        merge(&mut table, overrides);
+        Self::from_table(table)
+    }
+    /// # from_args
+    /// reads the file given with `--config <path>`, or [`DEFAULT_CONFIG_PATH`] when
+    /// there is one, then sets every other `--<key> <value>` on top of it. Keys are
+    /// dotted paths into the file like `--window.width 1280` or `--graphics.msaa=4`,
+    /// values are read as TOML and as a string when that fails. A key without a
+    /// value is set to `true`.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Result<Self, ConfigError> {
+        let mut path = None;
+        let mut overrides = toml::Table::new();
+        let mut args = args.into_iter().peekable();
+        while let Some(argument) = args.next() {
+            let Some(key) = argument.strip_prefix("--") else {
+                return Err(ConfigError::Argument(argument));
+            };
+            let (key, value) = match key.split_once('=') {
+                Some((key, value)) => (key.to_string(), value.to_string()),
+                None => {
+                    let value = args.next_if(|next| !next.starts_with("--"));
+                    (key.to_string(), value.unwrap_or_else(|| String::from("true")))
+                }
+            };
+            if key.is_empty() {
+                return Err(ConfigError::Argument(argument));
+            }
+            if key == "config" {
+                path = Some(PathBuf::from(value));
+            } else {
+                set(&mut overrides, &key, parse_value(&value));
+            }
+        }
+        match path {
+            Some(path) => Self::load_with_overrides(&path, overrides),
+            None if Path::new(DEFAULT_CONFIG_PATH).exists() => Self::load_with_overrides(Path::new(DEFAULT_CONFIG_PATH), overrides),
+            None => Self::from_table(overrides),
+        }
+    }
+}
+
+fn parse_value(value: &str) -> toml::Value {
+    format!("value = {value}")
+        .parse::<toml::Table>()
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| toml::Value::String(value.to_string()))
+}
+
+/// puts `value` at the dotted `key`, making the tables on the way.
+fn set(table: &mut toml::Table, key: &str, value: toml::Value) {
+    let mut table = table;
+    let mut parts = key.split('.').peekable();
+    while let Some(part) = parts.next() {
+        if parts.peek().is_none() {
+            table.insert(part.to_string(), value);
+            return;
+        }
+        let entry = table.entry(part.to_string()).or_insert_with(|| toml::Value::Table(toml::Table::new()));
+        if !entry.is_table() {
+            *entry = toml::Value::Table(toml::Table::new());
+        }
+        let toml::Value::Table(inner) = entry else {
+            unreachable!();
+        };
+        table = inner;
+    }
+}
+
+/// sets everything in `overrides` on `table`, going into tables both have.
+fn merge(table: &mut toml::Table, overrides: toml::Table) {
+    for (key, value) in overrides {
+        match (table.get_mut(&key), value) {
+            (Some(toml::Value::Table(inner)), toml::Value::Table(value)) => merge(inner, value),
+            (_, value) => {
+                table.insert(key, value);
+            }
+        }
+    }
+}
@@ -1,6 +1,11 @@
 use ash::{vk, Entry};
 use drowsed_math::{FMat4, FVec2, FMat2};
-use yum_mocha::{vk_obj::{descriptors, self, pipelines::graphics, device::{WindowOption, LogicalDevice, QueueFamilyIndices, LogicalDeviceBuilder}, rendering::Renderer}, model::vertex::GlobalDebugVertex};
+use yum_mocha::{error::MochaError, vk_obj::{descriptors, self, pipelines::{graphics, ShaderModuleError}, device::{WindowOption, LogicalDevice, QueueFamilyIndices, LogicalDeviceBuilder}, rendering::Renderer}, model::vertex::{GlobalDebugVertex, Vertex3DSkinned}};
+
+use super::{config::GraphicsConfig, system_manager::SkinnedTarget};
+
+/// the most skinned models that can be skinned on the gpu at once, one palette descriptor set each.
+pub const MAX_GPU_SKINNED: u32 = 64;
 
 #[repr(C)]
 #[derive(Clone, Copy) ]
@@ -21,14 +26,23 @@ pub struct DeviceManager {
     pub window: WindowOption,
     pub renderer: vk_obj::rendering::Renderer,
     pub graphics: graphics::GraphicsPipelines,
+    /// what `graphics` was built from, to build it again when its shaders change.
+    pub graphics_info: graphics::GraphicsPipelineInfo,
     pub descriptor_pool: descriptors::DescriptorPool,
     pub descriptor_layout: descriptors::DescriptorLayout,
     pub sets: Vec<vk::DescriptorSet>,
     pub layout: vk::PipelineLayout,
+    /// draws [`Vertex3DSkinned`] with the joint matrices in a storage buffer at set 1.
+    pub skinned_graphics: graphics::GraphicsPipelines,
+    pub skinned_info: graphics::GraphicsPipelineInfo,
+    /// `descriptor_layout` at set 0 and `palette_layout` at set 1.
+    pub skinned_layout: vk::PipelineLayout,
+    pub palette_pool: descriptors::DescriptorPool,
+    pub palette_layout: descriptors::DescriptorLayout,
 }
 
 impl DeviceManager {
-    pub fn new(entry: &Entry, window: WindowOption) -> Self {
+    pub fn try_new(entry: &Entry, window: WindowOption, config: &GraphicsConfig) -> Result<Self, MochaError> {
         let device: std::sync::Arc<LogicalDevice>;
         match window {
             WindowOption::Winit(ref b) => device = std::sync::Arc::new(LogicalDeviceBuilder::new()
@@ -36,6 +50,7 @@ impl DeviceManager {
             .check_queue_support(vk::QueueFlags::GRAPHICS)
             .check_queue_support(vk::QueueFlags::COMPUTE)
             .add_swapchain_extension()
+            .enable_validation(config.validation)
             .build(entry, |prop, physical_device, surface, funcs|{
                 let mut indices = QueueFamilyIndices::default();
                 let mut queueinfo: Vec<(u32, u32, vk::CommandPoolCreateFlags)> = vec![];
@@ -44,7 +59,12 @@ impl DeviceManager {
                     if family.queue_count > 0 && (family.queue_flags & vk::QueueFlags::GRAPHICS) == vk::QueueFlags::GRAPHICS {
                         indices.graphics = Some(i);
                     }
-                    let present_support = unsafe { ash::extensions::khr::Surface::get_physical_device_surface_support(funcs, *physical_device, i, surface.unwrap()).unwrap() };
+                    // a family that can't be asked about presenting is one that can't present
+                    let present_support = surface.is_some_and(|surface| unsafe { ash::extensions::khr::Surface::get_physical_device_surface_support(funcs, *physical_device, i, surface) }
+                        .unwrap_or_else(|error| {
+                            log::warn!("couldn't check if queue family {i} can present: {error}");
+                            false
+                        }));
                     if family.queue_count > 0 && present_support {
                         indices.surface = Some(i);
                     }
@@ -57,7 +77,7 @@ impl DeviceManager {
                 queueinfo
             })),
         }
-        let renderer = Renderer::new(device.clone(), window.clone());
+        let renderer = Renderer::try_with_options(device.clone(), window.clone(), config.render_options())?;
         let push_constant_range = vk::PushConstantRange {
             stage_flags: vk::ShaderStageFlags::ALL_GRAPHICS,
             size: std::mem::size_of::<PushData3D>() as u32,
@@ -89,19 +109,73 @@ impl DeviceManager {
             set_layout_count: 1,
             ..Default::default()
         };
-        let layout = unsafe { device.device.create_pipeline_layout(&layout_info, None).unwrap() };
+        let layout = unsafe { device.device.create_pipeline_layout(&layout_info, None)? };
+
+        let palette_pool = descriptors::DescriptorPoolBuilder::new(device.clone())
+        .set_max_sets(MAX_GPU_SKINNED)
+        .set_flag(vk::DescriptorPoolCreateFlags::FREE_DESCRIPTOR_SET)
+        .add_pool_size(vk::DescriptorType::STORAGE_BUFFER, MAX_GPU_SKINNED)
+        .build();
+        let palette_layout = descriptors::DescriptorLayoutBuilder::new(device.clone())
+        .add_binding(
+            0,
+            vk::DescriptorType::STORAGE_BUFFER,
+            1,
+            vk::ShaderStageFlags::VERTEX
+        )
+        .build();
+        let skinned_set_layouts = [descriptor_layout.layout, palette_layout.layout];
+        let skinned_layout_info = vk::PipelineLayoutCreateInfo {
+            push_constant_range_count: 1,
+            p_push_constant_ranges: &push_constant_range,
+            p_set_layouts: skinned_set_layouts.as_ptr(),
+            set_layout_count: skinned_set_layouts.len() as u32,
+            ..Default::default()
+        };
+        let skinned_layout = unsafe { device.device.create_pipeline_layout(&skinned_layout_info, None)? };
     
         let graphics_info = graphics::GraphicsPipelineInfo {
             vertex_entry: String::from("main\0"),
             fragment_entry: String::from("main\0"),
-            vertex_filepath: String::from("./shaders/vertex3normaluv.vert.spv"),
-            fragment_filepath: String::from("./shaders/vertex3normaluv.frag.spv"),
+            vertex_filepath: config.vertex_shader.clone(),
+            fragment_filepath: config.fragment_shader.clone(),
             layout: layout,
             renderpass: renderer.swapchain.renderpass,
+            samples: renderer.swapchain.samples,
             ..Default::default()
         };
 
-        let graphics = graphics::GraphicsPipelines::new::<GlobalDebugVertex>(device.clone(), &graphics_info);
-        Self { device, window, renderer, graphics, descriptor_pool, descriptor_layout, sets, layout }
+        let skinned_info = graphics::GraphicsPipelineInfo {
+            vertex_filepath: config.skinned_vertex_shader.clone(),
+            layout: skinned_layout,
+            ..graphics_info.clone()
+        };
+
+        let graphics = graphics::GraphicsPipelines::try_new::<GlobalDebugVertex>(device.clone(), &graphics_info)?;
+        let skinned_graphics = graphics::GraphicsPipelines::try_new::<Vertex3DSkinned>(device.clone(), &skinned_info)?;
+        Ok(Self {
+            device, window, renderer, graphics, graphics_info, descriptor_pool, descriptor_layout, sets, layout,
+            skinned_graphics, skinned_info, skinned_layout, palette_pool, palette_layout,
+        })
+    }
+    /// the skinned pipeline and palette descriptors for a [`super::system_manager::RenderTarget`].
+    pub fn skinned_target(&self) -> SkinnedTarget {
+        SkinnedTarget {
+            pipeline: self.skinned_graphics.pipelines[0],
+            layout: self.skinned_layout,
+            palette_pool: self.palette_pool.pool,
+            palette_layout: self.palette_layout.layout,
+        }
+    }
+    /// # reload_pipelines
+    /// builds the graphics pipelines again from the shaders on disk, waiting for the
+    /// device to be idle first. The old pipelines stay when the shaders are broken.
+    pub fn reload_pipelines(&mut self) -> Result<(), ShaderModuleError> {
+        let graphics = graphics::GraphicsPipelines::try_new::<GlobalDebugVertex>(self.device.clone(), &self.graphics_info)?;
+        let skinned_graphics = graphics::GraphicsPipelines::try_new::<Vertex3DSkinned>(self.device.clone(), &self.skinned_info)?;
+        unsafe { self.device.device.device_wait_idle().map_err(ShaderModuleError::Vulkan)? };
+        self.graphics = graphics;
+        self.skinned_graphics = skinned_graphics;
+        Ok(())
     }
 }
\ No newline at end of file
@@ -0,0 +1,122 @@
+use std::{any::{Any, TypeId}, collections::HashMap, marker::PhantomData};
+
+/// # Channel
+/// events of a single type. Events live for two frames: the one they were sent
+/// in and the next, so systems running before the sender in a frame still see them.
+/// Every event gets an increasing id which readers use as their cursor.
+struct Channel<E> {
+    previous: Vec<E>,
+    current: Vec<E>,
+    /// id of the first event in `previous`.
+    start: usize,
+}
+
+impl<E> Channel<E> {
+    fn new() -> Self {
+        Self { previous: vec![], current: vec![], start: 0 }
+    }
+    fn end(&self) -> usize {
+        self.start + self.previous.len() + self.current.len()
+    }
+}
+
+/// type erased channel so the bus can swap the buffers of every event type.
+trait EventStorage {
+    fn update(&mut self);
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<E: 'static> EventStorage for Channel<E> {
+    fn update(&mut self) {
+        self.start += self.previous.len();
+        self.previous = std::mem::take(&mut self.current);
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// # EventReader
+/// Cursor of a single reader into the events of type `E`, every system that
+/// reads events should keep its own so they don't steal events from each other.
+pub struct EventReader<E> {
+    cursor: usize,
+    phantom: PhantomData<fn() -> E>,
+}
+
+impl<E> Default for EventReader<E> {
+    fn default() -> Self {
+        Self { cursor: 0, phantom: PhantomData }
+    }
+}
+
+/// # EventBus
+/// Typed channels for systems to talk to each other without knowing about each
+/// other. [`EventBus::update`] has to run once a frame, which the default systems
+/// of the [`super::system_manager::SystemManager`] take care of.
+pub struct EventBus {
+    channels: HashMap<TypeId, Box<dyn EventStorage>>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self { channels: HashMap::new() }
+    }
+    fn channel<E: 'static>(&self) -> Option<&Channel<E>> {
+        self.channels.get(&TypeId::of::<E>())?.as_any().downcast_ref::<Channel<E>>()
+    }
+    fn channel_mut<E: 'static>(&mut self) -> &mut Channel<E> {
+        self.channels.entry(TypeId::of::<E>())
+            .or_insert_with(|| Box::new(Channel::<E>::new()))
+            .as_any_mut()
+            .downcast_mut::<Channel<E>>()
+            .unwrap()
+    }
+    pub fn send<E: 'static>(&mut self, event: E) {
+        self.channel_mut::<E>().current.push(event);
+    }
+    pub fn send_batch<E: 'static>(&mut self, events: impl IntoIterator<Item = E>) {
+        self.channel_mut::<E>().current.extend(events);
+    }
+    /// a reader that only sees events sent from now on.
+    pub fn reader<E: 'static>(&self) -> EventReader<E> {
+        EventReader { cursor: self.channel::<E>().map_or(0, |channel| channel.end()), phantom: PhantomData }
+    }
+    /// # read
+    /// every event of type `E` the reader hasn't seen yet, oldest first.
+    /// Events older than the previous frame are gone, even if they were never read.
+    pub fn read<'a, E: 'static>(&'a self, reader: &mut EventReader<E>) -> impl Iterator<Item = &'a E> {
+        let (previous, current): (&[E], &[E]) = match self.channel::<E>() {
+            Some(channel) => {
+                let skip = reader.cursor.saturating_sub(channel.start);
+                reader.cursor = channel.end();
+                let previous_skip = skip.min(channel.previous.len());
+                let current_skip = skip - previous_skip;
+                (&channel.previous[previous_skip..], &channel.current[current_skip.min(channel.current.len())..])
+            }
+            None => (&[], &[]),
+        };
+        previous.iter().chain(current.iter())
+    }
+    /// whether there is anything left for the reader.
+    pub fn is_empty<E: 'static>(&self, reader: &EventReader<E>) -> bool {
+        self.channel::<E>().map_or(true, |channel| reader.cursor >= channel.end())
+    }
+    /// # update
+    /// drops the events of the previous frame and starts a new one.
+    pub fn update(&mut self) {
+        for channel in self.channels.values_mut() {
+            channel.update();
+        }
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,64 @@
+use mofongo::bodies::RigidBody;
+
+use crate::components::scene::Scene;
+
+use super::{SchonMotorBase, schedule::ScheduleError, system_manager::{SystemManager, SystemManagerInfo, FrameInfo}};
+
+/// the most steps [`HeadlessMotor::advance`] runs in one call, the time left
+/// over after them is dropped so a long stall doesn't spiral into ever more steps.
+pub const MAX_ADVANCE_STEPS: usize = 8;
+
+/// # HeadlessMotor
+/// Runs the systems of a [`super::SchonMotor`] without a window or a Vulkan device,
+/// for servers and tests. Every frame runs the systems exactly `fixed_step` seconds
+/// forward, the time scale and pause of [`super::time::Time`] only change what
+/// systems read from the clock. Systems that need something to draw skip every frame.
+pub struct HeadlessMotor<Base: SchonMotorBase> {
+    pub system_manager: SystemManager<Base::VertexType, Base::VectorType, Base::VulkanIndexType, Base::UniversalTransformType, Base::RigidBodyType>,
+    pub fixed_step: f32,
+    accumulator: f32,
+}
+
+impl<Base: SchonMotorBase> HeadlessMotor<Base>
+where Base::RigidBodyType: RigidBody<DeltaTime = f32> {
+    /// `None` when `fixed_step` isn't a positive, finite number of seconds.
+    pub fn new(info: &SystemManagerInfo<Base::VectorType>, fixed_step: f32) -> Option<Self> {
+        if !(fixed_step > 0.0 && fixed_step.is_finite()) {
+            return None;
+        }
+        let mut system_manager = SystemManager::new(info);
+        // a step longer than the clock allows would be clamped and drift from `advance`
+        system_manager.time.max_delta = system_manager.time.max_delta.max(fixed_step);
+        Some(Self { system_manager, fixed_step, accumulator: 0.0 })
+    }
+    pub fn push_scene(&mut self, scene: Scene<Base::UniversalTransformType>) {
+        self.system_manager.scene_manager.push(scene);
+    }
+    /// runs the schedule once, `fixed_step` seconds forward. When no
+    /// [`SystemManager::physics_step`] is set the physics steps at `fixed_step` too.
+    pub fn step(&mut self) -> Result<(), ScheduleError> {
+        self.system_manager.physics_step.get_or_insert(self.fixed_step);
+        let time = &mut self.system_manager.time;
+        time.advance(self.fixed_step);
+        time.set_fixed(Some(self.fixed_step), 0.0);
+        self.system_manager.run_schedule(FrameInfo { delta_time: self.fixed_step, target: None })
+    }
+    /// # advance
+    /// adds `elapsed` seconds of real time and runs as many fixed steps as fit in
+    /// it, up to [`MAX_ADVANCE_STEPS`]. The remainder carries over to the next call.
+    /// Returns the steps run.
+    pub fn advance(&mut self, elapsed: f32) -> Result<usize, ScheduleError> {
+        self.accumulator += elapsed;
+        let mut steps = 0;
+        while self.accumulator >= self.fixed_step && steps < MAX_ADVANCE_STEPS {
+            self.step()?;
+            self.accumulator -= self.fixed_step;
+            steps += 1;
+        }
+        if steps == MAX_ADVANCE_STEPS {
+            self.accumulator = self.accumulator.min(self.fixed_step);
+        }
+        self.system_manager.time.set_fixed(Some(self.fixed_step), self.accumulator / self.fixed_step);
+        Ok(steps)
+    }
+}
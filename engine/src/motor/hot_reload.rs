@@ -0,0 +1,256 @@
+use std::{collections::HashMap, fmt, path::{Path, PathBuf}, process::Command, rc::Rc, time::{Duration, Instant, SystemTime}};
+
+use drowsed_math::{FVec3, TransformQuaternion3D};
+use mofongo::solid::physics::rigidbody::RigidBody3D;
+use yum_mocha::{model::vertex::GlobalDebugVertex, vk_obj::pipelines::{graphics::GraphicsPipelineInfo, ShaderModuleError}};
+
+use crate::components::{entity::Entity, rendering::models::{Model, Renderable}, spatial::LocalBounds};
+
+use super::{SchonMotor, SolidMotor, schedule::Stage, serialization::ColliderDescription, system_manager::SystemManager};
+
+/// what a file looked like the last time it was polled, `None` while it doesn't exist.
+type Stamp = Option<(SystemTime, u64)>;
+
+fn stamp(path: &Path) -> Stamp {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// # FileWatcher
+/// Notices changes to files by polling their modification time and size, at
+/// most once every `interval`. Files that get deleted and come back count as changed.
+pub struct FileWatcher {
+    files: HashMap<PathBuf, Stamp>,
+    pub interval: Duration,
+    last_poll: Option<Instant>,
+}
+
+impl FileWatcher {
+    pub fn new(interval: Duration) -> Self {
+        Self { files: HashMap::new(), interval, last_poll: None }
+    }
+    pub fn watch(&mut self, path: impl Into<PathBuf>) {
+        let path = path.into();
+        let stamp = stamp(&path);
+        self.files.entry(path).or_insert(stamp);
+    }
+    pub fn unwatch(&mut self, path: &Path) -> bool {
+        self.files.remove(path).is_some()
+    }
+    pub fn is_watched(&self, path: &Path) -> bool {
+        self.files.contains_key(path)
+    }
+    /// # poll
+    /// the files that changed since the last poll, nothing if the last poll
+    /// was less than `interval` ago.
+    pub fn poll(&mut self) -> Vec<PathBuf> {
+        let now = Instant::now();
+        if self.last_poll.is_some_and(|last| now - last < self.interval) {
+            return vec![];
+        }
+        self.last_poll = Some(now);
+        let mut changed = vec![];
+        for (path, last) in &mut self.files {
+            let current = stamp(path);
+            if current != *last {
+                *last = current;
+                // still being written, or deleted, pick it up once it's back
+                if current.is_some() {
+                    changed.push(path.clone());
+                }
+            }
+        }
+        changed
+    }
+}
+
+impl Default for FileWatcher {
+    fn default() -> Self {
+        Self::new(Duration::from_millis(500))
+    }
+}
+
+#[derive(Debug)]
+pub enum HotReloadError {
+    /// glslc couldn't be started, it has to be on the path or set with the `GLSLC` variable.
+    Compiler(std::io::Error),
+    /// the shader at `path` didn't compile, `message` is what the compiler said.
+    Compile { path: PathBuf, message: String },
+    Shader(ShaderModuleError),
+}
+
+impl fmt::Display for HotReloadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HotReloadError::Compiler(error) => write!(f, "couldn't run glslc: {error}"),
+            HotReloadError::Compile { path, message } => write!(f, "{} didn't compile:\n{message}", path.display()),
+            HotReloadError::Shader(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl std::error::Error for HotReloadError {}
+
+impl From<ShaderModuleError> for HotReloadError {
+    fn from(error: ShaderModuleError) -> Self {
+        HotReloadError::Shader(error)
+    }
+}
+
+/// # compile_shader
+/// compiles the glsl at `source` to SPIR-V at `output` with glslc.
+pub fn compile_shader(source: &Path, output: &Path) -> Result<(), HotReloadError> {
+    let compiler = std::env::var_os("GLSLC").unwrap_or_else(|| "glslc".into());
+    let result = Command::new(compiler)
+        .arg(source)
+        .arg("-o")
+        .arg(output)
+        .output()
+        .map_err(HotReloadError::Compiler)?;
+    if !result.status.success() {
+        return Err(HotReloadError::Compile { path: source.to_path_buf(), message: String::from_utf8_lossy(&result.stderr).into_owned() });
+    }
+    Ok(())
+}
+
+/// # ShaderReloader
+/// Watches the SPIR-V of a pipeline and the glsl it is compiled from. Changed
+/// sources get compiled again, and any change to the SPIR-V means the pipeline
+/// has to be rebuilt.
+pub struct ShaderReloader {
+    watcher: FileWatcher,
+    /// glsl source and the SPIR-V it compiles to.
+    sources: HashMap<PathBuf, PathBuf>,
+    binaries: Vec<PathBuf>,
+    /// SPIR-V changed but the pipeline wasn't rebuilt yet.
+    rebuild: bool,
+}
+
+impl ShaderReloader {
+    pub fn new() -> Self {
+        Self { watcher: FileWatcher::default(), sources: HashMap::new(), binaries: vec![], rebuild: false }
+    }
+    /// watches the SPIR-V `binary`, and `source` if there is one to compile it from.
+    pub fn watch(&mut self, binary: impl Into<PathBuf>, source: Option<PathBuf>) {
+        let binary = binary.into();
+        if let Some(source) = source {
+            self.watcher.watch(source.clone());
+            self.sources.insert(source, binary.clone());
+        }
+        self.watcher.watch(binary.clone());
+        self.binaries.push(binary);
+    }
+    /// # watch_pipeline
+    /// watches both shaders of the pipeline. Sources are looked for next to the
+    /// folder of the SPIR-V without the `.spv`, so `./shaders/a.vert.spv` is built
+    /// from `./a.vert`, the way the shaders of the engine are laid out.
+    pub fn watch_pipeline(&mut self, info: &GraphicsPipelineInfo) {
+        for binary in [&info.vertex_filepath, &info.fragment_filepath] {
+            let binary = PathBuf::from(binary);
+            let source = binary.file_stem()
+                .map(|stem| binary.parent().and_then(Path::parent).unwrap_or(Path::new(".")).join(stem))
+                .filter(|source| source.exists());
+            self.watch(binary, source);
+        }
+    }
+    /// # poll
+    /// compiles the sources that changed, true if the pipeline needs to be rebuilt.
+    /// The SPIR-V written by the compiler is picked up on the next poll, and a
+    /// source that doesn't compile doesn't keep the others from compiling. A
+    /// rebuild missed because of an error is asked for again on the next poll.
+    pub fn poll(&mut self) -> Result<bool, HotReloadError> {
+        let mut error = None;
+        for path in self.watcher.poll() {
+            if let Some(binary) = self.sources.get(&path) {
+                if let Err(compile) = compile_shader(&path, binary) {
+                    error.get_or_insert(compile);
+                }
+            } else if self.binaries.contains(&path) {
+                self.rebuild = true;
+            }
+        }
+        match error {
+            Some(error) => Err(error),
+            None => Ok(std::mem::take(&mut self.rebuild)),
+        }
+    }
+}
+
+impl Default for ShaderReloader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SystemManager<GlobalDebugVertex, FVec3, u32, TransformQuaternion3D, RigidBody3D> {
+    /// # add_model_reload_system
+    /// puts `reload_models` in [`Stage::Input`] after `assets`, which gives every object
+    /// rendering a model that got reloaded new buffers with the new vertices, new
+    /// [`LocalBounds`] and, for hull colliders, a collider built from the new vertices.
+    pub fn add_model_reload_system(&mut self) {
+        self.schedule.add(Stage::Input, "reload_models", |systems: &mut Self| {
+            let replaced = systems.assets.take_replaced::<Model<GlobalDebugVertex>>();
+            let Some(target) = systems.frame.as_ref().and_then(|frame| frame.target.as_ref()) else {
+                return;
+            };
+            let device = target.device.clone();
+            for (_, old, new) in replaced {
+                let bounds = LocalBounds::of_model(&new);
+                let points: Vec<[f32; 3]> = new.vertices.iter().map(|vertex| [vertex.pos.x, vertex.pos.y, vertex.pos.z]).collect();
+                let old: Rc<dyn Renderable<GlobalDebugVertex, u32>> = old;
+                for id in systems.rendering.replace(&systems.scene_manager.world, device.clone(), &old, new) {
+                    systems.refit_model(id, bounds, &points);
+                }
+            }
+        })
+            .after("assets");
+    }
+    /// gives `id` the bounds of its new model and builds its hull collider again
+    /// from `points`. The old collider stays when the new one can't be built.
+    fn refit_model(&mut self, id: Entity, bounds: Option<LocalBounds>, points: &[[f32; 3]]) {
+        match bounds {
+            Some(bounds) => { self.scene_manager.world.insert(id, bounds); }
+            None => { self.scene_manager.world.remove::<LocalBounds>(id); }
+        }
+        let Some(description) = self.scene_manager.world.get::<ColliderDescription>(id).map(|description| description.clone()) else {
+            return;
+        };
+        let description = match description {
+            ColliderDescription::Gjk { max_vertices, .. } => ColliderDescription::Gjk { points: points.to_vec(), max_vertices },
+            ColliderDescription::Sat { max_vertices, .. } => ColliderDescription::Sat { points: points.to_vec(), max_vertices },
+            ColliderDescription::Cuboid { .. } => return,
+        };
+        if let Err(error) = self.attach_collider(id, description) {
+            log::warn!("kept the old collider of {id:?}, the reloaded model doesn't make one: {error}");
+        }
+    }
+}
+
+impl SchonMotor<SolidMotor> {
+    /// # enable_hot_reload
+    /// starts watching the files of loaded assets and the shaders of the graphics
+    /// pipelines, see [`SchonMotor::hot_reload`]. Textures bound to descriptor sets
+    /// by hand have to be bound again, see [`super::assets::AssetServer::take_replaced`].
+    pub fn enable_hot_reload(&mut self) {
+        self.system_manager.assets.enable_hot_reload();
+        self.system_manager.add_model_reload_system();
+        let mut shaders = ShaderReloader::new();
+        shaders.watch_pipeline(&self.device_manager.graphics_info);
+        shaders.watch_pipeline(&self.device_manager.skinned_info);
+        self.shaders = Some(shaders);
+    }
+    /// # hot_reload
+    /// recompiles changed shaders and rebuilds the pipelines with them, should run
+    /// between frames. Assets reload on their own in the `assets` system.
+    /// When anything fails the old pipelines stay.
+    pub fn hot_reload(&mut self) -> Result<(), HotReloadError> {
+        let Some(shaders) = &mut self.shaders else {
+            return Ok(());
+        };
+        if shaders.poll()? {
+            self.device_manager.reload_pipelines()?;
+            log::info!("rebuilt the graphics pipelines");
+        }
+        Ok(())
+    }
+}
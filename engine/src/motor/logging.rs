@@ -0,0 +1,88 @@
+use std::{io::Write, time::Instant};
+
+use log::{LevelFilter, Log, Metadata, Record, SetLoggerError};
+
+/// variable [`Logger::init_from_env`] reads its levels from.
+pub const LOG_VARIABLE: &str = "SCHON_LOG";
+
+/// # Logger
+/// Writes the records of the `log` macros to stderr, with a level for every
+/// module. Modules without one of their own use the level of the closest
+/// parent module that has one, or `default`.
+pub struct Logger {
+    pub default: LevelFilter,
+    modules: Vec<(String, LevelFilter)>,
+    start: Instant,
+}
+
+impl Logger {
+    pub fn new(default: LevelFilter) -> Self {
+        Self { default, modules: vec![], start: Instant::now() }
+    }
+    /// # parse
+    /// reads levels written like `warn,engine::motor=debug,yum_mocha=error`: a bare
+    /// level sets the default, `module=level` the level of a module and everything
+    /// in it. Parts that can't be read are skipped.
+    pub fn parse(spec: &str) -> Self {
+        let mut logger = Self::new(LevelFilter::Info);
+        for part in spec.split(',').map(str::trim).filter(|part| !part.is_empty()) {
+            match part.split_once('=') {
+                Some((module, level)) => {
+                    if let Ok(level) = level.trim().parse() {
+                        logger = logger.with_module(module.trim(), level);
+                    }
+                }
+                None => {
+                    if let Ok(level) = part.parse() {
+                        logger.default = level;
+                    }
+                }
+            }
+        }
+        logger
+    }
+    pub fn with_module(mut self, module: impl Into<String>, level: LevelFilter) -> Self {
+        let module = module.into();
+        self.modules.retain(|(other, _)| *other != module);
+        self.modules.push((module, level));
+        self
+    }
+    /// the level records from `target` are let through at.
+    pub fn level_for(&self, target: &str) -> LevelFilter {
+        self.modules.iter()
+            .filter(|(module, _)| {
+                target.strip_prefix(module.as_str()).is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+            })
+            .max_by_key(|(module, _)| module.len())
+            .map_or(self.default, |(_, level)| *level)
+    }
+    /// # init
+    /// makes this the logger of the `log` macros, which can only happen once.
+    pub fn init(self) -> Result<(), SetLoggerError> {
+        let max = self.modules.iter().map(|(_, level)| *level).fold(self.default, LevelFilter::max);
+        log::set_boxed_logger(Box::new(self))?;
+        log::set_max_level(max);
+        Ok(())
+    }
+    /// [`Logger::init`] with the levels in [`LOG_VARIABLE`], `info` when it isn't set.
+    pub fn init_from_env() -> Result<(), SetLoggerError> {
+        Self::parse(&std::env::var(LOG_VARIABLE).unwrap_or_default()).init()
+    }
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level_for(metadata.target())
+    }
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let elapsed = self.start.elapsed().as_secs_f32();
+        let mut stderr = std::io::stderr().lock();
+        let _ = writeln!(stderr, "[{elapsed:>9.3} {:<5} {}] {}", record.level(), record.target(), record.args());
+    }
+    fn flush(&self) {
+        let _ = std::io::stderr().flush();
+    }
+}
@@ -2,16 +2,18 @@ use ash::Entry;
 use drowsed_math::{Transform, Vector, TransformQuaternion3D, FVec3, TransformMatrix};
 use yum_mocha::{vk_obj::{rendering::mesh::{Vertex, VulkanIndexable}, device::WindowOption}, model::vertex::GlobalDebugVertex};
 
-use crate::components::scene::Scene;
+use crate::{components::scene::Scene, error::EngineError};
 
-use self::system_manager::SystemManagerInfo;
+use self::{system_manager::SystemManagerInfo, config::EngineConfig};
 
+/// the types a motor is built from. They end up as components in the
+/// [`crate::ecs::world::World`], so they can't borrow anything.
 pub trait SchonMotorBase {
-    type VertexType: Vertex;
-    type VectorType: Vector;
-    type VulkanIndexType: VulkanIndexable;
-    type UniversalTransformType: TransformMatrix<f32>;
-    type RigidBodyType: mofongo::bodies::RigidBody<Transformation = Self::UniversalTransformType, SpatialVector = Self::VectorType>;
+    type VertexType: Vertex + 'static;
+    type VectorType: Vector + 'static;
+    type VulkanIndexType: VulkanIndexable + 'static;
+    type UniversalTransformType: TransformMatrix<f32> + 'static;
+    type RigidBodyType: mofongo::bodies::RigidBody<Transformation = Self::UniversalTransformType, SpatialVector = Self::VectorType> + 'static;
 }
 pub struct SolidMotor;
 impl SchonMotorBase for SolidMotor {
@@ -25,20 +27,43 @@ impl SchonMotorBase for SolidMotor {
 pub mod system_manager;
 pub mod device_manager;
 pub mod scene_manager;
+pub mod serialization;
+pub mod schedule;
+pub mod headless;
+pub mod events;
+pub mod prefab;
+pub mod time;
+pub mod assets;
+pub mod hot_reload;
+pub mod profiler;
+pub mod logging;
+pub mod config;
 /// Im trying to learn german which is why I named it
 /// **SchonMotor** meaning Beaatiful Motor.
 pub struct SchonMotor<Base: SchonMotorBase> {
 // <V: Vertex, E: Vector, I: VulkanIndexable, T: Transform, R: mofongo::bodies::RigidBody<Transformation = T>> {
     pub device_manager: device_manager::DeviceManager,
     pub system_manager: system_manager::SystemManager<Base::VertexType, Base::VectorType, Base::VulkanIndexType, Base::UniversalTransformType, Base::RigidBodyType>,
+    /// `None` until [`SchonMotor::enable_hot_reload`].
+    shaders: Option<hot_reload::ShaderReloader>,
 }
 
-impl<Base: SchonMotorBase> SchonMotor<Base> {
-    pub fn new(entry: &Entry, window: WindowOption, info: &SystemManagerInfo<Base::VectorType>) -> Self {
-        let device_manager = device_manager::DeviceManager::new(entry, window);
-        let system_manager = system_manager::SystemManager::new(info);
-        Self { system_manager, device_manager }
+impl<Base: SchonMotorBase> SchonMotor<Base>
+where Base::VectorType: From<FVec3> {
+    /// # try_new
+    /// starts the renderer with the `graphics` of the config and the systems with
+    /// its `physics`. The window has to be made from its `window` beforehand.
+    pub fn try_new(entry: &Entry, window: WindowOption, config: &EngineConfig) -> Result<Self, EngineError> {
+        let device_manager = device_manager::DeviceManager::try_new(entry, window, &config.graphics)?;
+        let [x, y, z] = config.physics.gravity;
+        let info = SystemManagerInfo { global_gravity: FVec3::new(x, y, z).into() };
+        let mut system_manager = system_manager::SystemManager::new(&info);
+        system_manager.physics_step = config.physics.step();
+        Ok(Self { system_manager, device_manager, shaders: None })
     }
+}
+
+impl<Base: SchonMotorBase> SchonMotor<Base> {
     pub fn push_scene(&mut self, scene: Scene<Base::UniversalTransformType>) {
         self.system_manager.scene_manager.push(scene);
     }
@@ -0,0 +1,132 @@
+use std::{path::Path, rc::Rc, sync::Arc};
+
+use drowsed_math::{FVec3, TransformQuaternion3D};
+use mofongo::solid::physics::rigidbody::RigidBody3D;
+use serde::{Deserialize, Serialize};
+use yum_mocha::{model::vertex::GlobalDebugVertex, vk_obj::device::ReplacingDevice};
+
+use crate::components::{entity::Entity, hierarchy::Hierarchical, object::BasicObject, rendering::{models::{FromFBX, Model}, rendersystem::RenderObject}, spatial::LocalBounds};
+
+use super::{SchonMotor, SolidMotor, serialization::{ColliderDescription, ModelSource, RigidBodyDescription, SceneFormatError, TransformDescription}, system_manager::SystemManager};
+
+/// # PrefabCollider
+/// the collider of a prefab, hulls are built from the vertices of its model.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum PrefabCollider {
+    Gjk { max_vertices: Option<usize> },
+    Sat { max_vertices: Option<usize> },
+    Cuboid { half_extents: [f32; 3] },
+}
+
+/// # PrefabDescription
+/// what gets written to prefab files.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrefabDescription {
+    /// fbx file the model is loaded from, the first model in it is used.
+    pub model: String,
+    #[serde(default)]
+    pub collider: Option<PrefabCollider>,
+    #[serde(default)]
+    pub rigidbody: Option<RigidBodyDescription>,
+    pub transform: TransformDescription,
+}
+
+impl PrefabDescription {
+    pub fn to_ron(&self) -> Result<String, SceneFormatError> {
+        ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()).map_err(|err| SceneFormatError::Serialize(err.to_string()))
+    }
+    pub fn from_ron(text: &str) -> Result<Self, SceneFormatError> {
+        ron::from_str(text).map_err(|err| SceneFormatError::Deserialize(err.to_string()))
+    }
+}
+
+/// # Prefab
+/// A preconfigured entity: a model, a collider, rigidbody parameters and a
+/// transform. The model is loaded once and shared by everything spawned from
+/// the prefab, while every spawn gets its own collider and rigidbody.
+#[derive(Clone)]
+pub struct Prefab {
+    pub model: Rc<Model<GlobalDebugVertex>>,
+    /// path of the model, remembered on spawned entities so scenes can be saved.
+    pub model_source: Option<String>,
+    pub collider: Option<ColliderDescription>,
+    pub rigidbody: Option<RigidBodyDescription>,
+    /// transform of the entity relative to where it gets spawned.
+    pub transform: TransformQuaternion3D,
+}
+
+impl Prefab {
+    pub fn new(model: Rc<Model<GlobalDebugVertex>>) -> Self {
+        Self { model, model_source: None, collider: None, rigidbody: None, transform: TransformQuaternion3D::default() }
+    }
+    /// # with_collider
+    /// gives the prefab a collider, building hulls from the vertices of the model.
+    pub fn with_collider(mut self, collider: PrefabCollider) -> Self {
+        let points = || self.model.vertices.iter().map(|vertex| [vertex.pos.x, vertex.pos.y, vertex.pos.z]).collect();
+        self.collider = Some(match collider {
+            PrefabCollider::Gjk { max_vertices } => ColliderDescription::Gjk { points: points(), max_vertices },
+            PrefabCollider::Sat { max_vertices } => ColliderDescription::Sat { points: points(), max_vertices },
+            PrefabCollider::Cuboid { half_extents } => ColliderDescription::Cuboid { half_extents },
+        });
+        self
+    }
+    pub fn with_rigidbody(mut self, rigidbody: &RigidBody3D) -> Self {
+        self.rigidbody = Some(RigidBodyDescription::from(rigidbody));
+        self
+    }
+    pub fn with_transform(mut self, transform: TransformQuaternion3D) -> Self {
+        self.transform = transform;
+        self
+    }
+    pub fn from_description(description: &PrefabDescription) -> Result<Self, SceneFormatError> {
+        let models = Model::<GlobalDebugVertex>::try_from_fbx(&description.model)
+            .map_err(|error| SceneFormatError::Model { path: description.model.clone(), error })?;
+        let Some(model) = models.into_iter().next() else {
+            return Err(SceneFormatError::MissingModel(description.model.clone()));
+        };
+        let mut prefab = Self::new(Rc::new(model)).with_transform(TransformQuaternion3D::from(&description.transform));
+        if let Some(collider) = &description.collider {
+            prefab = prefab.with_collider(collider.clone());
+        }
+        prefab.model_source = Some(description.model.clone());
+        prefab.rigidbody = description.rigidbody.clone();
+        Ok(prefab)
+    }
+    pub fn load_from_file(path: impl AsRef<Path>) -> Result<Self, SceneFormatError> {
+        Self::from_description(&PrefabDescription::from_ron(&std::fs::read_to_string(path)?)?)
+    }
+}
+
+impl SystemManager<GlobalDebugVertex, FVec3, u32, TransformQuaternion3D, RigidBody3D> {
+    /// # spawn_prefab
+    /// spawns the prefab at `transform` in the selected scene and gives it the
+    /// model, collider and rigidbody components of the prefab.
+    pub fn spawn_prefab(&mut self, device: Arc<ReplacingDevice>, prefab: &Prefab, transform: TransformQuaternion3D) -> Result<Entity, SceneFormatError> {
+        let id = self.spawn(BasicObject::new(transform.compose(&prefab.transform)));
+        if let Some(collider) = &prefab.collider {
+            if let Err(err) = self.attach_collider(id, collider.clone()) {
+                self.despawn(id);
+                return Err(err);
+            }
+        }
+        if let Some(bounds) = LocalBounds::of_model(&prefab.model) {
+            self.scene_manager.world.insert(id, bounds);
+        }
+        self.scene_manager.world.insert(id, RenderObject::<GlobalDebugVertex, u32>::new(device, prefab.model.clone()));
+        if let Some(source) = &prefab.model_source {
+            self.scene_manager.world.insert(id, ModelSource(source.clone()));
+        }
+        if let Some(rigidbody) = &prefab.rigidbody {
+            self.scene_manager.world.insert(id, RigidBody3D::from(rigidbody));
+        }
+        Ok(id)
+    }
+}
+
+impl SchonMotor<SolidMotor> {
+    /// # spawn
+    /// spawns the prefab at `transform`, see [`SystemManager::spawn_prefab`].
+    pub fn spawn(&mut self, prefab: &Prefab, transform: TransformQuaternion3D) -> Result<Entity, SceneFormatError> {
+        self.system_manager.spawn_prefab(self.device_manager.device.clone(), prefab, transform)
+    }
+}
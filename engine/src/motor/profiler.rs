@@ -0,0 +1,152 @@
+use std::{collections::VecDeque, fs::File, io::{self, BufWriter, Write}, path::Path, time::{Duration, Instant}};
+
+use yum_mocha::error::MochaError;
+
+use super::{SchonMotor, SchonMotorBase, schedule::Timed};
+
+/// thread ids of the trace, the gpu gets its own row.
+const CPU_THREAD: u32 = 1;
+const GPU_THREAD: u32 = 2;
+
+struct TraceEvent {
+    name: String,
+    category: &'static str,
+    thread: u32,
+    /// microseconds since the profiler was created.
+    start: f64,
+    duration: f64,
+}
+
+/// # Profiler
+/// Collects how long frames, schedule stages, systems and gpu work took while
+/// it is enabled, and writes them out as a Chrome trace, which can be opened in
+/// `chrome://tracing` or Perfetto. Only the last `capacity` events are kept.
+pub struct Profiler {
+    enabled: bool,
+    origin: Instant,
+    events: VecDeque<TraceEvent>,
+    pub capacity: usize,
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Self { enabled: false, origin: Instant::now(), events: VecDeque::new(), capacity: 1_000_000 }
+    }
+    pub fn enable(&mut self) {
+        self.enabled = true;
+    }
+    pub fn disable(&mut self) {
+        self.enabled = false;
+    }
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+    pub fn clear(&mut self) {
+        self.events.clear();
+    }
+    fn push(&mut self, name: String, category: &'static str, thread: u32, start: Instant, duration: Duration) {
+        if !self.enabled {
+            return;
+        }
+        if self.events.len() >= self.capacity {
+            self.events.pop_front();
+        }
+        self.events.push_back(TraceEvent {
+            name,
+            category,
+            thread,
+            start: start.saturating_duration_since(self.origin).as_secs_f64() * 1_000_000.0,
+            duration: duration.as_secs_f64() * 1_000_000.0,
+        });
+    }
+    /// records something that ran on the cpu from `start` to `end`.
+    pub fn record(&mut self, name: impl Into<String>, category: &'static str, start: Instant, end: Instant) {
+        self.push(name.into(), category, CPU_THREAD, start, end.saturating_duration_since(start));
+    }
+    pub fn record_gpu(&mut self, name: impl Into<String>, start: Instant, duration: Duration) {
+        self.push(name.into(), "gpu", GPU_THREAD, start, duration);
+    }
+    /// records a span passed on by [`super::schedule::Schedule::run_timed`].
+    pub fn record_timed(&mut self, timed: Timed, start: Instant, end: Instant) {
+        match timed {
+            Timed::Stage(stage) => self.record(format!("{stage:?}"), "stage", start, end),
+            Timed::System(name) => self.record(name, "system", start, end),
+        }
+    }
+    /// # scope
+    /// times everything until the returned guard is dropped.
+    pub fn scope(&mut self, name: impl Into<String>) -> ProfileScope<'_> {
+        ProfileScope { profiler: self, name: name.into(), start: Instant::now() }
+    }
+    /// # write_chrome_trace
+    /// writes the events in the Trace Event Format, as complete events.
+    pub fn write_chrome_trace(&self, mut writer: impl Write) -> io::Result<()> {
+        writeln!(writer, "{{\"displayTimeUnit\":\"ms\",\"traceEvents\":[")?;
+        write!(writer, "{{\"name\":\"thread_name\",\"ph\":\"M\",\"pid\":1,\"tid\":{CPU_THREAD},\"args\":{{\"name\":\"cpu\"}}}},\n")?;
+        write!(writer, "{{\"name\":\"thread_name\",\"ph\":\"M\",\"pid\":1,\"tid\":{GPU_THREAD},\"args\":{{\"name\":\"gpu\"}}}}")?;
+        for event in &self.events {
+            write!(
+                writer,
+                ",\n{{\"name\":\"{}\",\"cat\":\"{}\",\"ph\":\"X\",\"pid\":1,\"tid\":{},\"ts\":{:.3},\"dur\":{:.3}}}",
+                escape(&event.name), event.category, event.thread, event.start, event.duration
+            )?;
+        }
+        writeln!(writer, "\n]}}")?;
+        writer.flush()
+    }
+    pub fn save_chrome_trace(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.write_chrome_trace(BufWriter::new(File::create(path)?))
+    }
+}
+
+impl Default for Profiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// # ProfileScope
+/// records the time from [`Profiler::scope`] until it is dropped.
+pub struct ProfileScope<'a> {
+    profiler: &'a mut Profiler,
+    name: String,
+    start: Instant,
+}
+
+impl Drop for ProfileScope<'_> {
+    fn drop(&mut self) {
+        let name = std::mem::take(&mut self.name);
+        self.profiler.record(name, "scope", self.start, Instant::now());
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+impl<Base: SchonMotorBase> SchonMotor<Base> {
+    /// # enable_profiling
+    /// turns on the profiler of the system manager and the gpu timestamps of the
+    /// renderer. Without timestamp support only the cpu gets profiled. The gpu
+    /// timings come out of [`yum_mocha::vk_obj::rendering::Renderer::take_gpu_spans`]
+    /// and go into [`Profiler::record_gpu`].
+    pub fn enable_profiling(&mut self) -> Result<(), MochaError> {
+        self.system_manager.profiler.enable();
+        self.device_manager.renderer.enable_timestamps()
+    }
+}
@@ -1,27 +1,101 @@
 #![allow(unused)]
-use std::collections::HashMap;
+
+use std::cell::Ref;
 
 use drowsed_math::{Transform, TransformMatrix};
 
-use crate::components::{scene::Scene, object::BasicObject};
-/// The *SceneManager* contains the objects and scenes
-/// that compose the application. Objects are a global listing
-/// of all objects while scenes contain a grouping of certain objects
+use crate::{components::{scene::Scene, object::BasicObject, entity::{Entity, EntityAllocator}, hierarchy::Hierarchical}, ecs::{sparse_set::SparseSet, world::World}};
+/// The *SceneManager* contains the scenes that compose the application.
+/// Every object belongs to a single scene, except persistent objects which
+/// get carried over to whichever scene is switched to.
+/// The objects and every other component of an entity live in the [`World`],
+/// systems only look at the ones in the selected scene.
 pub struct SceneManager<T: TransformMatrix<f32>> {
-    pub(crate) objects: HashMap<i128, BasicObject<T>>,
+    pub(crate) persistent: SparseSet<()>,
     pub(crate) scenes: Vec<Scene<T>>,
+    pub(crate) entities: EntityAllocator,
+    pub world: World,
     pub selected_scene: usize,
 }
 
-impl<T: TransformMatrix<f32>> SceneManager<T> {
+impl<T: TransformMatrix<f32> + 'static> SceneManager<T> {
     pub fn new() -> Self {
-        Self { objects: HashMap::new(), scenes: vec![], selected_scene: 0 }
+        Self { persistent: SparseSet::new(), scenes: vec![], entities: EntityAllocator::new(), world: World::new(), selected_scene: 0 }
+    }
+    /// looks the object up in the selected scene.
+    pub fn get_object_by_id(&self, id: Entity) -> Option<Ref<BasicObject<T>>> {
+        if !self.scenes.get(self.selected_scene)?.contains(id) {
+            return None;
+        }
+        self.world.get::<BasicObject<T>>(id)
+    }
+    pub fn get_object_by_id_mut(&mut self, id: Entity) -> Option<&mut BasicObject<T>> {
+        if !self.scenes.get(self.selected_scene)?.contains(id) {
+            return None;
+        }
+        self.world.component_mut::<BasicObject<T>>(id)
+    }
+    /// # push_object
+    /// puts the object in the world as part of the scene at `scene`.
+    pub fn push_object(&mut self, scene: usize, id: Entity, object: BasicObject<T>) {
+        self.scenes[scene].insert(id);
+        self.world.insert(id, object);
+    }
+    /// # remove_object
+    /// takes the object out of its scene and the hierarchy, its children stay where
+    /// they last were in the world but don't have a parent anymore. Other components
+    /// of the entity stay in the world.
+    pub fn remove_object(&mut self, id: Entity) -> Option<BasicObject<T>> {
+        for scene in &mut self.scenes {
+            scene.remove(id);
+        }
+        let object = self.world.remove::<BasicObject<T>>(id)?;
+        if let Some(mut objects) = self.world.storage_mut::<BasicObject<T>>() {
+            if let Some(parent) = object.parent.and_then(|parent| objects.get_mut(parent)) {
+                parent.children.retain(|child| *child != id);
+            }
+            for child in &object.children {
+                if let Some(child) = objects.get_mut(*child) {
+                    child.set_transform(child.world);
+                    child.parent = None;
+                }
+            }
+        }
+        Some(object)
     }
-    pub fn get_object_by_id(&self, id: i128) -> Option<&BasicObject<T>> {
-        self.objects.get(&id)
+    /// # take_changes
+    /// the objects of the selected scene whose world transform might have changed
+    /// and the ones removed from it since the last call, for keeping things like the
+    /// spatial index in sync without looking at every object again. Removals are
+    /// only recorded once this was called, see [`Scene::stop_tracking`].
+    pub(crate) fn take_changes(&mut self) -> (Vec<Entity>, Vec<Entity>) {
+        let Some(scene) = self.scenes.get_mut(self.selected_scene) else {
+            return (vec![], vec![]);
+        };
+        let removed = scene.take_removed();
+        let Some(mut objects) = self.world.storage_mut::<BasicObject<T>>() else {
+            return (vec![], removed);
+        };
+        let moved = scene.objects().iter()
+            .copied()
+            .filter(|id| objects.get_mut(*id).is_some_and(|object| std::mem::take(&mut object.moved)))
+            .collect();
+        (moved, removed)
     }
-    pub fn get_object_by_id_mut(&mut self, id: i128) -> Option<&mut BasicObject<T>> {
-        self.objects.get_mut(&id)
+    pub fn scene_count(&self) -> usize {
+        self.scenes.len()
+    }
+    /// # set_persistent
+    /// persistent objects move along with the selection when switching scenes.
+    pub fn set_persistent(&mut self, id: Entity, persistent: bool) {
+        if persistent {
+            self.persistent.insert(id, ());
+        } else {
+            self.persistent.remove(id);
+        }
+    }
+    pub fn is_persistent(&self, id: Entity) -> bool {
+        self.persistent.contains(id)
     }
     pub fn get_selected_scene(&self) -> &Scene<T> {
         &self.scenes[self.selected_scene]
@@ -32,4 +106,159 @@ impl<T: TransformMatrix<f32>> SceneManager<T> {
     pub fn push(&mut self, scene: Scene<T>) {
         self.scenes.push(scene);
     }
-}
\ No newline at end of file
+    pub fn allocate(&mut self) -> Entity {
+        self.entities.allocate()
+    }
+    pub fn is_alive(&self, id: Entity) -> bool {
+        self.entities.is_alive(id)
+    }
+    /// # free
+    /// removes the entity from every scene and the world and frees it, systems holding on to
+    /// the entity have to be cleared separately (see `SystemManager::despawn`).
+    pub fn free(&mut self, id: Entity) -> bool {
+        self.persistent.remove(id);
+        self.remove_object(id);
+        self.world.despawn(id);
+        self.entities.free(id)
+    }
+}
+
+impl<T: TransformMatrix<f32> + Hierarchical + 'static> SceneManager<T> {
+    /// # set_parent
+    /// attaches `child` to `parent` in the selected scene keeping its current local
+    /// transform, or detaches it with `None`. Returns false if either object isn't in
+    /// the scene or if it would make the hierarchy loop.
+    pub fn set_parent(&mut self, child: Entity, parent: Option<Entity>) -> bool {
+        self.set_parent_in(self.selected_scene, child, parent)
+    }
+    fn set_parent_in(&mut self, scene: usize, child: Entity, parent: Option<Entity>) -> bool {
+        let Some(scene) = self.scenes.get(scene) else {
+            return false;
+        };
+        let Some(mut objects) = self.world.storage_mut::<BasicObject<T>>() else {
+            return false;
+        };
+        if !scene.contains(child) || !objects.contains(child) {
+            return false;
+        }
+        if let Some(parent) = parent {
+            // walking up from the new parent must never reach the child
+            let mut current = Some(parent);
+            while let Some(id) = current {
+                if id == child {
+                    return false;
+                }
+                let Some(object) = objects.get(id).filter(|_| scene.contains(id)) else {
+                    return false;
+                };
+                current = object.parent;
+            }
+        }
+        let old = objects.get(child).and_then(|object| object.parent);
+        if let Some(old) = old.and_then(|old| objects.get_mut(old)) {
+            old.children.retain(|id| *id != child);
+        }
+        if let Some(parent) = parent.and_then(|parent| objects.get_mut(parent)) {
+            parent.children.push(child);
+        }
+        let object = objects.get_mut(child).unwrap();
+        object.parent = parent;
+        object.dirty = true;
+        true
+    }
+    /// # set_world_transform
+    /// moves the object to `world`, working out the local transform from its parent.
+    pub fn set_world_transform(&mut self, id: Entity, world: T) {
+        let Some(mut objects) = self.world.storage_mut::<BasicObject<T>>() else {
+            return;
+        };
+        let parent = objects.get(id)
+            .and_then(|object| object.parent)
+            .and_then(|parent| objects.get(parent))
+            .map(|parent| parent.world_transform());
+        let Some(object) = objects.get_mut(id) else {
+            return;
+        };
+        match parent {
+            Some(parent) => object.set_transform(parent.relative(&world)),
+            None => object.set_transform(world),
+        }
+        object.world = world;
+    }
+    /// # propagate
+    /// recomputes the world transform of every object in the selected scene that is
+    /// dirty or has a dirty parent, walking down from the roots so parents are always done first.
+    pub fn propagate(&mut self) {
+        let Some(scene) = self.scenes.get(self.selected_scene) else {
+            return;
+        };
+        let Some(mut objects) = self.world.storage_mut::<BasicObject<T>>() else {
+            return;
+        };
+        let mut stack: Vec<(Entity, bool)> = scene.objects().iter()
+            .filter(|id| objects.get(**id).is_some_and(|object| object.parent.is_none()))
+            .map(|id| (*id, false))
+            .collect();
+        while let Some((id, parent_dirty)) = stack.pop() {
+            let parent = objects.get(id)
+                .and_then(|object| object.parent)
+                .and_then(|parent| objects.get(parent))
+                .map(|parent| parent.world_transform());
+            let Some(object) = objects.get_mut(id) else {
+                continue;
+            };
+            let dirty = object.dirty || parent_dirty;
+            if dirty {
+                object.world = match parent {
+                    Some(parent) => parent.compose(&object.transform()),
+                    None => object.transform(),
+                };
+                object.dirty = false;
+                object.moved = true;
+            }
+            stack.extend(object.children.iter().map(|child| (*child, dirty)));
+        }
+    }
+    /// # switch
+    /// selects the scene at `index`, moving every persistent object from the
+    /// previously selected scene into it. Persistent objects keep their parent
+    /// if it is persistent as well. Returns false if there is no such scene.
+    pub fn switch(&mut self, index: usize) -> bool {
+        if index >= self.scenes.len() {
+            return false;
+        }
+        if index == self.selected_scene || self.selected_scene >= self.scenes.len() {
+            self.selected_scene = index;
+            return true;
+        }
+        let old = self.selected_scene;
+        let moving: Vec<Entity> = self.persistent.entities().iter()
+            .copied()
+            .filter(|id| self.scenes[old].contains(*id))
+            .collect();
+        let parents: Vec<Option<Entity>> = moving.iter()
+            .map(|id| self.world.get::<BasicObject<T>>(*id).and_then(|object| object.parent()))
+            .map(|parent| parent.filter(|parent| moving.contains(parent)))
+            .collect();
+        let objects: Vec<BasicObject<T>> = moving.iter()
+            .filter_map(|id| self.remove_object(*id))
+            .collect();
+        for (id, object) in moving.iter().zip(objects) {
+            let transform = object.world_transform();
+            self.push_object(index, *id, BasicObject::new(transform));
+        }
+        for (id, parent) in moving.iter().zip(parents) {
+            if let Some(parent) = parent {
+                let Some(world) = self.world.get::<BasicObject<T>>(*id).map(|object| object.world_transform()) else {
+                    continue;
+                };
+                self.set_parent_in(index, *id, Some(parent));
+                self.set_world_transform(*id, world);
+            }
+        }
+        // the spatial index builds itself again for the new scene, nothing reads the old one's changes
+        self.scenes[old].stop_tracking();
+        self.selected_scene = index;
+        true
+    }
+}
@@ -0,0 +1,279 @@
+use std::{fmt, time::Instant};
+
+/// # Stage
+/// stages run in the order they are declared in, systems inside a stage are
+/// ordered by their `before`/`after` constraints and otherwise by when they were added.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Stage {
+    Input,
+    PreUpdate,
+    Physics,
+    PostPhysics,
+    RenderPrep,
+    Render,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 6] = [Stage::Input, Stage::PreUpdate, Stage::Physics, Stage::PostPhysics, Stage::RenderPrep, Stage::Render];
+}
+
+/// what a span passed on by [`Schedule::run_timed`] covers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Timed<'a> {
+    Stage(Stage),
+    System(&'a str),
+}
+
+/// # System
+/// Something that runs once a frame on the context `C` of a [`Schedule`],
+/// closures taking `&mut C` are systems as well.
+pub trait System<C> {
+    fn run(&mut self, context: &mut C);
+}
+
+impl<C, F: FnMut(&mut C)> System<C> for F {
+    fn run(&mut self, context: &mut C) {
+        self(context)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScheduleError {
+    /// the constraints of these systems depend on each other in a loop.
+    Cycle(Vec<String>),
+    /// a constraint names a system that was never added.
+    UnknownSystem { system: String, constraint: String },
+    /// two systems were added with the same name.
+    DuplicateName(String),
+    /// a constraint against a system of another stage that the order of the stages contradicts.
+    CrossStageConstraint { system: String, constraint: String },
+}
+
+impl fmt::Display for ScheduleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Cycle(systems) => write!(f, "systems {systems:?} have cyclic ordering constraints"),
+            Self::UnknownSystem { system, constraint } => write!(f, "system \"{system}\" is ordered against unknown system \"{constraint}\""),
+            Self::DuplicateName(name) => write!(f, "a system named \"{name}\" already exists"),
+            Self::CrossStageConstraint { system, constraint } => write!(f, "system \"{system}\" is ordered against \"{constraint}\" of a stage that runs the other way around"),
+        }
+    }
+}
+
+impl std::error::Error for ScheduleError {}
+
+struct ScheduledSystem<C> {
+    name: String,
+    stage: Stage,
+    before: Vec<String>,
+    after: Vec<String>,
+    system: Box<dyn System<C>>,
+}
+
+/// # Schedule
+/// Runs systems in stages. Ordering constraints only matter between systems of
+/// the same stage, a constraint against a system of another stage is already
+/// decided by the order of the stages and only has to agree with it.
+pub struct Schedule<C> {
+    systems: Vec<ScheduledSystem<C>>,
+    /// indices into `systems` in the order they run, `None` when it has to be rebuilt.
+    order: Option<Vec<usize>>,
+}
+
+/// returned by [`Schedule::add`] to declare where the system goes relative to others.
+pub struct SystemConfig<'a, C> {
+    system: &'a mut ScheduledSystem<C>,
+}
+
+impl<'a, C> SystemConfig<'a, C> {
+    pub fn before(self, name: &str) -> Self {
+        self.system.before.push(name.to_string());
+        self
+    }
+    pub fn after(self, name: &str) -> Self {
+        self.system.after.push(name.to_string());
+        self
+    }
+}
+
+impl<C> Schedule<C> {
+    pub fn new() -> Self {
+        Self { systems: vec![], order: None }
+    }
+    pub fn add(&mut self, stage: Stage, name: &str, system: impl System<C> + 'static) -> SystemConfig<'_, C> {
+        self.order = None;
+        self.systems.push(ScheduledSystem { name: name.to_string(), stage, before: vec![], after: vec![], system: Box::new(system) });
+        SystemConfig { system: self.systems.last_mut().unwrap() }
+    }
+    pub fn remove(&mut self, name: &str) -> bool {
+        let count = self.systems.len();
+        self.systems.retain(|system| system.name != name);
+        self.order = None;
+        count != self.systems.len()
+    }
+    /// moves the systems of `other` over to the end of this schedule.
+    pub fn absorb(&mut self, other: Schedule<C>) {
+        if !other.systems.is_empty() {
+            self.systems.extend(other.systems);
+            self.order = None;
+        }
+    }
+    pub fn contains(&self, name: &str) -> bool {
+        self.systems.iter().any(|system| system.name == name)
+    }
+    fn index_of(&self, name: &str) -> Option<usize> {
+        self.systems.iter().position(|system| system.name == name)
+    }
+    /// # build
+    /// works out the order systems run in, sorting every stage topologically.
+    /// Happens on its own the first time the schedule runs after a change.
+    pub fn build(&mut self) -> Result<(), ScheduleError> {
+        for (i, system) in self.systems.iter().enumerate() {
+            if self.systems[..i].iter().any(|other| other.name == system.name) {
+                return Err(ScheduleError::DuplicateName(system.name.clone()));
+            }
+        }
+        // edges[i] are the systems that have to run after system i
+        let mut edges = vec![Vec::<usize>::new(); self.systems.len()];
+        for (i, system) in self.systems.iter().enumerate() {
+            for (constraint, runs_first) in system.before.iter().map(|name| (name, true)).chain(system.after.iter().map(|name| (name, false))) {
+                let Some(j) = self.index_of(constraint) else {
+                    return Err(ScheduleError::UnknownSystem { system: system.name.clone(), constraint: constraint.clone() });
+                };
+                let other = self.systems[j].stage;
+                if other != system.stage {
+                    // the stages already order them, as long as it's the right way around
+                    if (other > system.stage) != runs_first {
+                        return Err(ScheduleError::CrossStageConstraint { system: system.name.clone(), constraint: constraint.clone() });
+                    }
+                    continue;
+                }
+                if runs_first {
+                    edges[i].push(j);
+                } else {
+                    edges[j].push(i);
+                }
+            }
+        }
+        let mut incoming = vec![0usize; self.systems.len()];
+        for targets in &edges {
+            for target in targets {
+                incoming[*target] += 1;
+            }
+        }
+
+        let mut order = Vec::with_capacity(self.systems.len());
+        for stage in Stage::ALL {
+            let mut remaining: Vec<usize> = (0..self.systems.len()).filter(|i| self.systems[*i].stage == stage).collect();
+            while !remaining.is_empty() {
+                // the earliest added system without anything left to wait on
+                let Some(position) = remaining.iter().position(|i| incoming[*i] == 0) else {
+                    return Err(ScheduleError::Cycle(remaining.iter().map(|i| self.systems[*i].name.clone()).collect()));
+                };
+                let next = remaining.remove(position);
+                for target in &edges[next] {
+                    incoming[*target] -= 1;
+                }
+                order.push(next);
+            }
+        }
+        self.order = Some(order);
+        Ok(())
+    }
+    /// names of the systems in the order they run.
+    pub fn order(&mut self) -> Result<Vec<&str>, ScheduleError> {
+        if self.order.is_none() {
+            self.build()?;
+        }
+        Ok(self.order.as_ref().unwrap().iter().map(|i| self.systems[*i].name.as_str()).collect())
+    }
+    pub fn run(&mut self, context: &mut C) -> Result<(), ScheduleError> {
+        if self.order.is_none() {
+            self.build()?;
+        }
+        for i in self.order.as_ref().unwrap() {
+            self.systems[*i].system.run(context);
+        }
+        Ok(())
+    }
+    /// # run_timed
+    /// [`Schedule::run`], handing `record` when every system and every stage
+    /// with systems in it started and finished.
+    pub fn run_timed(&mut self, context: &mut C, mut record: impl FnMut(Timed, Instant, Instant)) -> Result<(), ScheduleError> {
+        if self.order.is_none() {
+            self.build()?;
+        }
+        let mut stage: Option<(Stage, Instant)> = None;
+        for i in self.order.as_ref().unwrap() {
+            let system = &mut self.systems[*i];
+            let start = Instant::now();
+            match stage {
+                Some((current, _)) if current == system.stage => {}
+                Some((current, began)) => {
+                    record(Timed::Stage(current), began, start);
+                    stage = Some((system.stage, start));
+                }
+                None => stage = Some((system.stage, start)),
+            }
+            system.system.run(context);
+            record(Timed::System(&system.name), start, Instant::now());
+        }
+        if let Some((current, began)) = stage {
+            record(Timed::Stage(current), began, Instant::now());
+        }
+        Ok(())
+    }
+}
+
+impl<C> Default for Schedule<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// a schedule over a log of the systems that ran.
+    fn recording(systems: &[(Stage, &'static str)]) -> Schedule<Vec<&'static str>> {
+        let mut schedule = Schedule::new();
+        for (stage, name) in systems {
+            let name = *name;
+            schedule.add(*stage, name, move |log: &mut Vec<&'static str>| log.push(name));
+        }
+        schedule
+    }
+
+    #[test]
+    fn systems_run_in_stage_and_constraint_order() {
+        let mut schedule = recording(&[(Stage::Render, "draw"), (Stage::Input, "c"), (Stage::Input, "b"), (Stage::Input, "a")]);
+        schedule.add(Stage::Input, "first", |log: &mut Vec<&'static str>| log.push("first")).before("c");
+        schedule.add(Stage::Input, "last", |log: &mut Vec<&'static str>| log.push("last")).after("a").before("draw");
+        // free systems keep the order they were added in, the rest waits for what they are after
+        let expected = vec!["b", "a", "first", "c", "last", "draw"];
+        assert_eq!(schedule.order().unwrap(), expected);
+        let mut log = vec![];
+        schedule.run(&mut log).unwrap();
+        assert_eq!(log, expected);
+    }
+
+    #[test]
+    fn constraints_in_a_loop_are_a_cycle() {
+        let mut schedule = recording(&[(Stage::Physics, "free")]);
+        schedule.add(Stage::Physics, "a", |_: &mut Vec<&'static str>| {}).after("b");
+        schedule.add(Stage::Physics, "b", |_: &mut Vec<&'static str>| {}).after("a");
+        assert_eq!(schedule.build(), Err(ScheduleError::Cycle(vec!["a".to_string(), "b".to_string()])));
+        assert_eq!(schedule.run(&mut vec![]), Err(ScheduleError::Cycle(vec!["a".to_string(), "b".to_string()])));
+    }
+
+    #[test]
+    fn constraints_have_to_name_systems_and_agree_with_stages() {
+        let mut schedule = recording(&[(Stage::Render, "draw")]);
+        schedule.add(Stage::Input, "read", |_: &mut Vec<&'static str>| {}).after("draw");
+        assert_eq!(schedule.build(), Err(ScheduleError::CrossStageConstraint { system: "read".to_string(), constraint: "draw".to_string() }));
+        schedule.remove("read");
+        schedule.add(Stage::Input, "read", |_: &mut Vec<&'static str>| {}).before("missing");
+        assert_eq!(schedule.build(), Err(ScheduleError::UnknownSystem { system: "read".to_string(), constraint: "missing".to_string() }));
+    }
+}
@@ -0,0 +1,386 @@
+use std::{cell::RefCell, collections::HashMap, fmt, path::Path, rc::Rc, sync::Arc};
+
+use drowsed_math::{FVec3, FMat3, FMat4, TransformQuaternion3D, complex::quaternion::Quaternion};
+use mofongo::{collider::Collider, error::MofongoError, solid::{collisions::{gjk::GJKColliderSolid, sat::SATColliderSolid}, physics::rigidbody::RigidBody3D}};
+use serde::{Deserialize, Serialize};
+use yum_mocha::{camera::Camera, error::MochaError, model::vertex::GlobalDebugVertex, vk_obj::device::ReplacingDevice};
+
+use crate::components::{entity::Entity, object::BasicObject, rendering::{models::{FromFBX, Model}, rendersystem::RenderObject}, collisions::collision_system::AttachedCollider, scene::Scene, spatial::LocalBounds};
+
+use super::system_manager::SystemManager;
+
+/// version written into every scene file, bump it whenever the layout of
+/// the descriptions below changes in a way older files can't be read with.
+pub const SCENE_FORMAT_VERSION: u32 = 2;
+
+#[derive(Debug)]
+pub enum SceneFormatError {
+    Io(std::io::Error),
+    Serialize(String),
+    Deserialize(String),
+    /// the file was written by a newer version of the engine.
+    UnsupportedVersion(u32),
+    /// a collider description that can't be turned into a collider, like a hull from no points.
+    InvalidCollider(MofongoError),
+    /// the fbx file a model is loaded from has no model in it.
+    MissingModel(String),
+    /// the fbx file a model is loaded from couldn't be read.
+    Model { path: String, error: MochaError },
+}
+
+impl fmt::Display for SceneFormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "couldn't access scene file: {err}"),
+            Self::Serialize(err) => write!(f, "couldn't write scene: {err}"),
+            Self::Deserialize(err) => write!(f, "couldn't read scene: {err}"),
+            Self::UnsupportedVersion(version) => write!(f, "scene format version {version} is newer than {SCENE_FORMAT_VERSION}"),
+            Self::InvalidCollider(err) => write!(f, "collider description doesn't describe a valid shape: {err}"),
+            Self::MissingModel(path) => write!(f, "there is no model in \"{path}\""),
+            Self::Model { path, error } => write!(f, "couldn't load a model from \"{path}\": {error}"),
+        }
+    }
+}
+
+impl std::error::Error for SceneFormatError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::InvalidCollider(err) => Some(err),
+            Self::Model { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for SceneFormatError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+/// # ModelSource
+/// component recording which file the model of an entity was loaded from.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelSource(pub String);
+
+/// # ColliderDescription
+/// how a collider was built, kept as a component so the collider can be saved.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ColliderDescription {
+    Gjk { points: Vec<[f32; 3]>, max_vertices: Option<usize> },
+    Sat { points: Vec<[f32; 3]>, max_vertices: Option<usize> },
+    Cuboid { half_extents: [f32; 3] },
+}
+
+type SolidColliderRef = Rc<RefCell<dyn Collider<TransformComponent = TransformQuaternion3D, ColliderLayoutVertex = FVec3>>>;
+
+impl ColliderDescription {
+    pub fn build(&self) -> Result<SolidColliderRef, MofongoError> {
+        let points = |points: &Vec<[f32; 3]>| points.iter().map(|point| vec3(*point)).collect::<Vec<_>>();
+        Ok(match self {
+            Self::Gjk { points: p, max_vertices } => Rc::new(RefCell::new(GJKColliderSolid::from_points(&points(p), *max_vertices))),
+            Self::Sat { points: p, max_vertices } => Rc::new(RefCell::new(SATColliderSolid::try_from_points(&points(p), *max_vertices)?)),
+            Self::Cuboid { half_extents } => Rc::new(RefCell::new(SATColliderSolid::cuboid(vec3(*half_extents)))),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransformDescription {
+    pub translation: [f32; 3],
+    pub rotation: RotationDescription,
+    pub scale: [f32; 3],
+}
+
+/// # RotationDescription
+/// rotations are written as quaternions, euler angles written by version 1 are still read.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum RotationDescription {
+    /// `[x, y, z, w]`
+    Quaternion([f32; 4]),
+    /// euler angles in radians.
+    Euler([f32; 3]),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RigidBodyDescription {
+    pub mass: f32,
+    pub velocity: [f32; 3],
+    pub angular_velocity: [f32; 3],
+    pub inverse_inertia: [[f32; 3]; 3],
+    #[serde(default)]
+    pub center_of_mass: [f32; 3],
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CameraDescription {
+    pub projection: [[f32; 4]; 4],
+    pub view: [[f32; 4]; 4],
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObjectDescription {
+    pub transform: TransformDescription,
+    /// index of the parent in the objects of the same scene.
+    #[serde(default)]
+    pub parent: Option<usize>,
+    /// whether the object moves along when switching scenes.
+    #[serde(default)]
+    pub persistent: bool,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub rigidbody: Option<RigidBodyDescription>,
+    #[serde(default)]
+    pub collider: Option<ColliderDescription>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SceneDescription {
+    pub current_camera: usize,
+    pub cameras: Vec<CameraDescription>,
+    pub objects: Vec<ObjectDescription>,
+}
+
+/// # SceneManagerDescription
+/// Everything needed to rebuild the scenes of a [`SystemManager`], this is
+/// what gets written to scene files.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SceneManagerDescription {
+    pub version: u32,
+    pub selected_scene: usize,
+    pub scenes: Vec<SceneDescription>,
+}
+
+impl SceneManagerDescription {
+    pub fn to_ron(&self) -> Result<String, SceneFormatError> {
+        ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()).map_err(|err| SceneFormatError::Serialize(err.to_string()))
+    }
+    pub fn from_ron(text: &str) -> Result<Self, SceneFormatError> {
+        let description: Self = ron::from_str(text).map_err(|err| SceneFormatError::Deserialize(err.to_string()))?;
+        if description.version > SCENE_FORMAT_VERSION {
+            return Err(SceneFormatError::UnsupportedVersion(description.version));
+        }
+        Ok(description)
+    }
+}
+
+fn vec3(v: [f32; 3]) -> FVec3 {
+    FVec3::new(v[0], v[1], v[2])
+}
+fn array3(v: FVec3) -> [f32; 3] {
+    [v.x, v.y, v.z]
+}
+fn mat4_array(m: &FMat4) -> [[f32; 4]; 4] {
+    [
+        [m.x.x, m.x.y, m.x.z, m.x.w],
+        [m.y.x, m.y.y, m.y.z, m.y.w],
+        [m.z.x, m.z.y, m.z.z, m.z.w],
+        [m.w.x, m.w.y, m.w.z, m.w.w],
+    ]
+}
+fn array_mat4(m: &[[f32; 4]; 4]) -> FMat4 {
+    FMat4::new(
+        m[0][0], m[0][1], m[0][2], m[0][3],
+        m[1][0], m[1][1], m[1][2], m[1][3],
+        m[2][0], m[2][1], m[2][2], m[2][3],
+        m[3][0], m[3][1], m[3][2], m[3][3],
+    )
+}
+fn mat3_array(m: &FMat3) -> [[f32; 3]; 3] {
+    [array3(m.x), array3(m.y), array3(m.z)]
+}
+fn array_mat3(m: &[[f32; 3]; 3]) -> FMat3 {
+    FMat3::new(
+        m[0][0], m[0][1], m[0][2],
+        m[1][0], m[1][1], m[1][2],
+        m[2][0], m[2][1], m[2][2],
+    )
+}
+
+impl From<&TransformQuaternion3D> for TransformDescription {
+    fn from(transform: &TransformQuaternion3D) -> Self {
+        Self {
+            translation: array3(transform.translation),
+            rotation: RotationDescription::Quaternion([transform.rotation.x, transform.rotation.y, transform.rotation.z, transform.rotation.w]),
+            scale: array3(transform.scale),
+        }
+    }
+}
+
+impl From<&TransformDescription> for TransformQuaternion3D {
+    fn from(description: &TransformDescription) -> Self {
+        TransformQuaternion3D {
+            translation: vec3(description.translation),
+            rotation: match description.rotation {
+                RotationDescription::Quaternion([x, y, z, w]) => Quaternion::<f32> { x, y, z, w },
+                RotationDescription::Euler(euler) => Quaternion::<f32>::from_euler(vec3(euler)),
+            },
+            scale: vec3(description.scale),
+        }
+    }
+}
+
+impl From<&RigidBody3D> for RigidBodyDescription {
+    fn from(body: &RigidBody3D) -> Self {
+        Self {
+            mass: body.mass,
+            velocity: array3(body.velocity),
+            angular_velocity: array3(body.angular_velocity),
+            inverse_inertia: mat3_array(&body.inertia),
+            center_of_mass: array3(body.center_of_mass),
+        }
+    }
+}
+
+impl From<&RigidBodyDescription> for RigidBody3D {
+    fn from(description: &RigidBodyDescription) -> Self {
+        let mut body = RigidBody3D::new(description.mass);
+        body.velocity = vec3(description.velocity);
+        body.angular_velocity = vec3(description.angular_velocity);
+        body.inertia = array_mat3(&description.inverse_inertia);
+        body.center_of_mass = vec3(description.center_of_mass);
+        body
+    }
+}
+
+/// Saving and loading only exists for the [`super::SolidMotor`] setup,
+/// since the descriptions are written in terms of its concrete types.
+impl SystemManager<GlobalDebugVertex, FVec3, u32, TransformQuaternion3D, RigidBody3D> {
+    /// # attach_model
+    /// loads the first model of an fbx file and renders it for `id`, remembering
+    /// the path so the scene can be saved.
+    pub fn attach_model(&mut self, device: Arc<ReplacingDevice>, id: Entity, path: &str) -> Result<(), SceneFormatError> {
+        let model = load_model(path)?;
+        self.attach_loaded_model(device, id, path, model);
+        Ok(())
+    }
+    fn attach_loaded_model(&mut self, device: Arc<ReplacingDevice>, id: Entity, path: &str, model: Model<GlobalDebugVertex>) {
+        if let Some(bounds) = LocalBounds::of_model(&model) {
+            self.scene_manager.world.insert(id, bounds);
+        }
+        self.scene_manager.world.insert(id, RenderObject::<GlobalDebugVertex, u32>::new(device, Rc::new(model)));
+        self.scene_manager.world.insert(id, ModelSource(path.to_string()));
+    }
+    /// # attach_collider
+    /// builds the collider from its description and registers it for `id`.
+    pub fn attach_collider(&mut self, id: Entity, description: ColliderDescription) -> Result<(), SceneFormatError> {
+        let collider = description.build().map_err(SceneFormatError::InvalidCollider)?;
+        self.attach_built_collider(id, description, collider);
+        Ok(())
+    }
+    fn attach_built_collider(&mut self, id: Entity, description: ColliderDescription, collider: SolidColliderRef) {
+        self.scene_manager.world.insert(id, AttachedCollider::new(collider));
+        self.scene_manager.world.insert(id, description);
+    }
+    pub fn describe(&self) -> SceneManagerDescription {
+        let world = &self.scene_manager.world;
+        let stored = world.storage::<BasicObject<TransformQuaternion3D>>();
+        let scenes = self.scene_manager.scenes.iter().map(|scene| {
+            let scene_objects: Vec<(Entity, &BasicObject<TransformQuaternion3D>)> = scene.objects().iter()
+                .filter_map(|id| Some((*id, stored.as_ref()?.get(*id)?)))
+                .collect();
+            let indices: HashMap<Entity, usize> = scene_objects.iter().enumerate().map(|(i, (id, _))| (*id, i)).collect();
+            let objects = scene_objects.iter().map(|(id, object)| ObjectDescription {
+                transform: TransformDescription::from(&object.transform()),
+                parent: object.parent().and_then(|parent| indices.get(&parent).copied()),
+                persistent: self.scene_manager.is_persistent(*id),
+                model: world.get::<ModelSource>(*id).map(|source| source.0.clone()),
+                rigidbody: world.get::<RigidBody3D>(*id).map(|rigidbody| RigidBodyDescription::from(&*rigidbody)),
+                collider: world.get::<ColliderDescription>(*id).map(|description| description.clone()),
+            }).collect();
+            SceneDescription {
+                current_camera: scene.current_camera,
+                cameras: scene.cameras().iter().map(|camera| CameraDescription {
+                    projection: mat4_array(&camera.projection),
+                    view: mat4_array(&camera.view),
+                }).collect(),
+                objects,
+            }
+        }).collect();
+        SceneManagerDescription { version: SCENE_FORMAT_VERSION, selected_scene: self.scene_manager.selected_scene, scenes }
+    }
+    /// # load
+    /// despawns everything and rebuilds the scenes from `description`, giving the
+    /// objects their models, rigidbodies and colliders again. Models and
+    /// colliders are all loaded before anything is despawned, so on an error the
+    /// current scenes are left as they were.
+    pub fn load(&mut self, device: Arc<ReplacingDevice>, description: &SceneManagerDescription) -> Result<(), SceneFormatError> {
+        if description.version > SCENE_FORMAT_VERSION {
+            return Err(SceneFormatError::UnsupportedVersion(description.version));
+        }
+        // the same file is only loaded once, however many objects use it
+        let mut models = HashMap::<&str, Model<GlobalDebugVertex>>::new();
+        let mut colliders = Vec::<Vec<Option<SolidColliderRef>>>::with_capacity(description.scenes.len());
+        for scene_description in &description.scenes {
+            let mut scene_colliders = Vec::with_capacity(scene_description.objects.len());
+            for object in &scene_description.objects {
+                if let Some(path) = &object.model {
+                    if !models.contains_key(path.as_str()) {
+                        models.insert(path, load_model(path)?);
+                    }
+                }
+                let collider = match &object.collider {
+                    Some(collider) => Some(collider.build().map_err(SceneFormatError::InvalidCollider)?),
+                    None => None,
+                };
+                scene_colliders.push(collider);
+            }
+            colliders.push(scene_colliders);
+        }
+
+        let existing: Vec<Entity> = self.scene_manager.scenes.iter().flat_map(|scene| scene.objects().iter().copied()).collect();
+        for id in existing {
+            self.despawn(id);
+        }
+        self.scene_manager.scenes.clear();
+
+        for ((index, scene_description), scene_colliders) in description.scenes.iter().enumerate().zip(colliders) {
+            let cameras = scene_description.cameras.iter()
+                .map(|camera| Camera::new(array_mat4(&camera.projection), array_mat4(&camera.view)))
+                .collect();
+            let mut scene = Scene::new(cameras);
+            scene.current_camera = scene_description.current_camera;
+            self.scene_manager.push(scene);
+            self.scene_manager.selected_scene = index;
+
+            let ids: Vec<Entity> = scene_description.objects.iter()
+                .map(|object| self.spawn(BasicObject::new(TransformQuaternion3D::from(&object.transform))))
+                .collect();
+            for ((object, id), collider) in scene_description.objects.iter().zip(ids.iter().copied()).zip(scene_colliders) {
+                if let Some(parent) = object.parent.and_then(|parent| ids.get(parent)) {
+                    self.set_parent(id, Some(*parent));
+                }
+                self.scene_manager.set_persistent(id, object.persistent);
+                if let Some(path) = &object.model {
+                    self.attach_loaded_model(device.clone(), id, path, models[path.as_str()].clone());
+                }
+                if let Some(rigidbody) = &object.rigidbody {
+                    self.scene_manager.world.insert(id, RigidBody3D::from(rigidbody));
+                }
+                if let (Some(description), Some(collider)) = (&object.collider, collider) {
+                    self.attach_built_collider(id, description.clone(), collider);
+                }
+            }
+        }
+        self.scene_manager.selected_scene = description.selected_scene.min(description.scenes.len().saturating_sub(1));
+        Ok(())
+    }
+    pub fn save_to_file(&self, path: impl AsRef<Path>) -> Result<(), SceneFormatError> {
+        std::fs::write(path, self.describe().to_ron()?)?;
+        Ok(())
+    }
+    pub fn load_from_file(&mut self, device: Arc<ReplacingDevice>, path: impl AsRef<Path>) -> Result<(), SceneFormatError> {
+        let description = SceneManagerDescription::from_ron(&std::fs::read_to_string(path)?)?;
+        self.load(device, &description)
+    }
+}
+
+/// the first model of an fbx file.
+fn load_model(path: &str) -> Result<Model<GlobalDebugVertex>, SceneFormatError> {
+    let models = Model::<GlobalDebugVertex>::try_from_fbx(path)
+        .map_err(|error| SceneFormatError::Model { path: path.to_string(), error })?;
+    models.into_iter().next().ok_or_else(|| SceneFormatError::MissingModel(path.to_string()))
+}
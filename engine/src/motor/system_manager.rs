@@ -1,61 +1,403 @@
 #![allow(unused)]
-use std::{cell::{Cell, RefCell}, rc::Rc};
+use std::{cell::{Cell, Ref, RefCell}, rc::Rc, sync::{Arc, Mutex}};
 
 use ash::vk;
-use drowsed_math::{Transform, Vector, TransformMatrix};
-use mofongo::collider::{Collider, CollisionInfo};
-use yum_mocha::vk_obj::{rendering::mesh::{VulkanIndexable, Vertex}, device::LogicalDevice};
+use drowsed_math::{Transform, Vector, TransformMatrix, TransformQuaternion3D, FVec3};
+use mofongo::{collider::{Collider, CollisionInfo}, solid::physics::fluid::FluidVolume};
+use yum_mocha::{vk_obj::{rendering::mesh::{VulkanIndexable, Vertex}, device::LogicalDevice}, input::input_state::GlobalInputState};
 
-use crate::components::{rendering::rendersystem::RenderSystem, collisions::collision_system::CollisionSystem, physics::{physics_system::PhysicsSystem, self}, self, object::BasicObject};
+use crate::{error::EngineError, components::{rendering::rendersystem::RenderSystem, collisions::collision_system::{CollisionSystem, CollisionEvent, AttachedCollider}, physics::{physics_system::PhysicsSystem, fluid_system::FluidSystem, self}, self, object::BasicObject, entity::Entity, hierarchy::Hierarchical, behaviour::BehaviourSystem, animation::animation_system::AnimationSystem, rendering::skinning::SkinningSystem, spatial::spatial_index::SpatialIndex}};
 
-use super::scene_manager::SceneManager;
+use super::{scene_manager::SceneManager, schedule::{Schedule, ScheduleError, Stage}, events::EventBus, time::{Time, Timers}, assets::AssetServer, profiler::Profiler};
+/// the most fixed physics steps run in one frame, the time left over after
+/// them is dropped so a slow frame doesn't make the next one slower.
+const MAX_PHYSICS_STEPS: u32 = 8;
 pub struct SystemManagerInfo<V: Vector> {
     pub global_gravity: V,
 }
+/// # RenderTarget
+/// where the rendering systems record their draw calls to.
+#[derive(Clone)]
+pub struct RenderTarget {
+    pub device: std::sync::Arc<LogicalDevice>,
+    pub command_buffer: vk::CommandBuffer,
+    pub layout: vk::PipelineLayout,
+    /// what skinned models are drawn with in [`components::rendering::skinning::SkinningMode::Gpu`].
+    pub skinned: Option<SkinnedTarget>,
+}
+/// # SkinnedTarget
+/// the pipeline for skinned models and where the descriptor sets of their
+/// joint matrices come from, set 1 of `layout`.
+#[derive(Clone, Copy)]
+pub struct SkinnedTarget {
+    pub pipeline: vk::Pipeline,
+    pub layout: vk::PipelineLayout,
+    pub palette_pool: vk::DescriptorPool,
+    pub palette_layout: vk::DescriptorSetLayout,
+}
+/// # FrameInfo
+/// what systems in the schedule need to know about the frame being run.
+/// `target` is `None` when running headless, systems that draw skip the frame then.
+#[derive(Clone)]
+pub struct FrameInfo<D> {
+    pub delta_time: D,
+    pub target: Option<RenderTarget>,
+}
+/// called with the [`SystemManager`] when the scene it was registered for gets
+/// switched to or away from.
+pub type SceneHook<V, E, I, T, R> = Box<dyn FnMut(&mut SystemManager<V, E, I, T, R>)>;
+/// # SceneSystemState
+/// system state that belongs to a scene instead of to the whole application,
+/// stored away while the scene isn't selected.
+pub struct SceneSystemState<G> {
+    pub gravity: G,
+    pub fluid_volumes: Vec<FluidVolume>,
+}
 pub struct SystemManager<V: Vertex, E: Vector, I: VulkanIndexable, T: TransformMatrix<f32>, R: mofongo::bodies::RigidBody<Transformation = T>> {
     pub scene_manager: SceneManager<T>,
     pub rendering: RenderSystem<V, I, T>,
     pub collisions: CollisionSystem<T, E>,
     pub physics: PhysicsSystem<R>,
+    pub fluids: FluidSystem,
+    pub behaviours: BehaviourSystem<V, E, I, T, R>,
+    /// only driven for 3D objects, see [`SystemManager::add_solid_systems`].
+    pub animations: AnimationSystem,
+    /// only drives models of [`yum_mocha::model::vertex::GlobalDebugVertex`], see [`SystemManager::add_skinning_system`].
+    pub skinning: SkinningSystem,
+    /// world bounds of the objects in the selected scene, only kept up to date
+    /// for 3D objects, see [`SystemManager::add_solid_systems`].
+    pub spatial: SpatialIndex,
+    /// input of the window, `None` when running headless.
+    pub input: Option<Arc<Mutex<GlobalInputState>>>,
+    /// events systems send to each other, like [`CollisionEvent`]s.
+    pub events: EventBus,
+    /// systems run every frame by [`SystemManager::run_schedule`].
+    pub schedule: Schedule<SystemManager<V, E, I, T, R>>,
+    /// the frame currently being run, only set while the schedule runs.
+    pub frame: Option<FrameInfo<R::DeltaTime>>,
+    /// the game clock, ticked by [`SystemManager::run_frame`].
+    pub time: Time,
+    /// timers counting down in the scaled time of [`SystemManager::time`].
+    pub timers: Timers<SystemManager<V, E, I, T, R>>,
+    /// models and textures loaded in the background, finished by the `assets` system.
+    pub assets: AssetServer,
+    /// times the stages and systems of the schedule while it is enabled.
+    pub profiler: Profiler,
+    /// seconds of scaled time every physics step covers, `None` steps physics once
+    /// per frame with its delta. Only used by the `physics` system of [`SystemManager::add_solid_systems`].
+    pub physics_step: Option<f32>,
+    physics_accumulator: f32,
+    default_gravity: R::SpatialVector,
+    scene_states: Vec<Option<SceneSystemState<R::SpatialVector>>>,
+    load_hooks: Vec<(usize, SceneHook<V, E, I, T, R>)>,
+    unload_hooks: Vec<(usize, SceneHook<V, E, I, T, R>)>,
 }
 
-impl<V: Vertex, E: Vector, I: VulkanIndexable, T: TransformMatrix<f32>, R: mofongo::bodies::RigidBody<Transformation = T>> SystemManager<V, E, I, T, R> {
+impl<V: Vertex + 'static, E: Vector + 'static, I: VulkanIndexable + 'static, T: TransformMatrix<f32> + 'static, R: mofongo::bodies::RigidBody<Transformation = T> + 'static> SystemManager<V, E, I, T, R> {
     pub fn new(info: &SystemManagerInfo<R::SpatialVector>) -> Self {
         let collisions = components::collisions::collision_system::CollisionSystem::new();
         let rendering = RenderSystem::<V, I, T>::default();
         let physics = physics::physics_system::PhysicsSystem::new(info.global_gravity);
-        Self { rendering, collisions, physics, scene_manager: SceneManager::new() }
+        Self {
+            rendering,
+            collisions,
+            physics,
+            fluids: FluidSystem::new(),
+            scene_manager: SceneManager::new(),
+            behaviours: BehaviourSystem::new(),
+            animations: AnimationSystem::new(),
+            skinning: SkinningSystem::new(),
+            spatial: SpatialIndex::new(),
+            input: None,
+            events: EventBus::new(),
+            schedule: Schedule::new(),
+            frame: None,
+            time: Time::new(),
+            timers: Timers::new(),
+            assets: AssetServer::new(),
+            profiler: Profiler::new(),
+            physics_step: None,
+            physics_accumulator: 0.0,
+            default_gravity: info.global_gravity,
+            scene_states: vec![],
+            load_hooks: vec![],
+            unload_hooks: vec![],
+        }
+    }
+    /// # spawn
+    /// allocates a new entity and puts the object in the selected scene.
+    pub fn spawn(&mut self, object: BasicObject<T>) -> Entity {
+        let id = self.scene_manager.allocate();
+        self.scene_manager.push_object(self.scene_manager.selected_scene, id, object);
+        id
+    }
+    /// # despawn
+    /// removes the entity from every scene and system, drops its components and frees it.
+    /// Returns false if the entity was already dead.
+    pub fn despawn(&mut self, id: Entity) -> bool {
+        if !self.scene_manager.is_alive(id) {
+            return false;
+        }
+        self.detach_behaviours(id);
+        self.animations.remove(id);
+        self.skinning.remove(id);
+        self.spatial.remove(id);
+        self.scene_manager.free(id)
+    }
+    /// # set_parent
+    /// attaches `child` to `parent` in the selected scene, see [`SceneManager::set_parent`].
+    pub fn set_parent(&mut self, child: Entity, parent: Option<Entity>) -> bool
+    where T: Hierarchical {
+        self.scene_manager.set_parent(child, parent)
     }
-    pub fn is_object_present(&self, id: &i128) -> bool {
-        self.scene_manager.objects.contains_key(id)
+    /// updates the world transforms of the selected scene, should run after
+    /// anything that moves objects and before anything that reads them.
+    pub fn propagate(&mut self)
+    where T: Hierarchical {
+        self.scene_manager.propagate()
+    }
+    /// # on_load
+    /// runs `hook` every time the scene at `scene` gets switched to.
+    pub fn on_load(&mut self, scene: usize, hook: impl FnMut(&mut Self) + 'static) {
+        self.load_hooks.push((scene, Box::new(hook)));
+    }
+    /// # on_unload
+    /// runs `hook` every time the scene at `scene` gets switched away from,
+    /// while it is still the selected scene.
+    pub fn on_unload(&mut self, scene: usize, hook: impl FnMut(&mut Self) + 'static) {
+        self.unload_hooks.push((scene, Box::new(hook)));
+    }
+    fn run_hooks(&mut self, scene: usize, load: bool) {
+        let hooks = if load { &mut self.load_hooks } else { &mut self.unload_hooks };
+        let mut taken = std::mem::take(hooks);
+        for (hook_scene, hook) in &mut taken {
+            if *hook_scene == scene {
+                hook(self);
+            }
+        }
+        // hooks might have registered more hooks while they ran
+        let hooks = if load { &mut self.load_hooks } else { &mut self.unload_hooks };
+        taken.append(hooks);
+        *hooks = taken;
+    }
+    /// # switch_scene
+    /// Unloads the selected scene and loads the one at `index`. Persistent objects
+    /// move along (see [`SceneManager::set_persistent`]), while the gravity and fluid
+    /// volumes of the old scene are stored away until it gets selected again.
+    /// Registrations of objects left behind stay in the systems, they just get
+    /// skipped while their scene isn't selected.
+    pub fn switch_scene(&mut self, index: usize) -> bool
+    where T: Hierarchical {
+        let old = self.scene_manager.selected_scene;
+        if index >= self.scene_manager.scene_count() {
+            return false;
+        }
+        if index == old {
+            return true;
+        }
+        self.run_hooks(old, false);
+
+        let state = SceneSystemState { gravity: self.physics.gravity(), fluid_volumes: self.fluids.replace_volumes(vec![]) };
+        if self.scene_states.len() <= old.max(index) {
+            self.scene_states.resize_with(old.max(index) + 1, || None);
+        }
+        self.scene_states[old] = Some(state);
+        self.scene_manager.switch(index);
+        let state = self.scene_states[index].take().unwrap_or(SceneSystemState { gravity: self.default_gravity, fluid_volumes: vec![] });
+        self.physics.set_gravity(state.gravity);
+        self.fluids.replace_volumes(state.fluid_volumes);
+
+        self.run_hooks(index, true);
+        true
     }
-    pub fn get_object(&self, id: &i128) -> Option<&BasicObject<T>> {
-        self.scene_manager.objects.get(id)
+    pub fn is_object_present(&self, id: &Entity) -> bool {
+        self.scene_manager.is_alive(*id)
     }
-    pub fn get_object_mut(&mut self, id: &i128) -> Option<&mut BasicObject<T>> {
-        self.scene_manager.objects.get_mut(id)
+    pub fn get_object(&self, id: &Entity) -> Option<Ref<BasicObject<T>>> {
+        self.scene_manager.get_object_by_id(*id)
     }
-    pub fn get_rigidbody(&self, id: &i128) -> Option<&R> {
-        self.physics.get_rigidbody_by_id(*id)
+    pub fn get_object_mut(&mut self, id: &Entity) -> Option<&mut BasicObject<T>> {
+        self.scene_manager.get_object_by_id_mut(*id)
     }
-    pub fn get_rigidbody_mut(&mut self, id: &i128) -> Option<&mut R> {
-        self.physics.get_rigidbody_by_id_mut(*id)
+    /// [`SystemManager::get_object`] for code returning an [`EngineError`].
+    pub fn object(&self, id: Entity) -> Result<Ref<BasicObject<T>>, EngineError> {
+        self.scene_manager.get_object_by_id(id).ok_or(EngineError::MissingEntity(id))
     }
-    pub fn get_collider(&self, id: &i128) -> Option<&(Rc<RefCell<dyn Collider<ColliderLayoutVertex = E, TransformComponent = T>>>, Cell<Option<CollisionInfo<E>>>)> {
-        self.collisions.get_collider_by_id(*id)
+    pub fn object_mut(&mut self, id: Entity) -> Result<&mut BasicObject<T>, EngineError> {
+        self.scene_manager.get_object_by_id_mut(id).ok_or(EngineError::MissingEntity(id))
     }
-    pub fn render_collisions(&mut self, device: std::sync::Arc<LogicalDevice>, scenemanager: &SceneManager<T>) {
-        self.collisions.render(device, scenemanager)
+    pub fn get_rigidbody(&self, id: &Entity) -> Option<Ref<R>> {
+        self.scene_manager.world.get::<R>(*id)
+    }
+    pub fn get_rigidbody_mut(&mut self, id: &Entity) -> Option<&mut R> {
+        self.scene_manager.world.component_mut::<R>(*id)
+    }
+    pub fn get_collider(&self, id: &Entity) -> Option<Ref<AttachedCollider<T, E>>> {
+        self.scene_manager.world.get::<AttachedCollider<T, E>>(*id)
+    }
+    pub fn render_collisions(&mut self, scenemanager: &SceneManager<T>) {
+        let events = self.collisions.render(scenemanager);
+        self.events.send_batch(events);
     }
     pub fn render_graphics(&mut self, device: std::sync::Arc<LogicalDevice>, command_buffer: vk::CommandBuffer, layout: vk::PipelineLayout, scenemanager: &SceneManager<T>) {
         self.rendering.render(device, command_buffer, layout, scenemanager)
     }
-    pub fn render_physics(&mut self, device: std::sync::Arc<LogicalDevice>, deltatime: R::DeltaTime, scenemanager: &mut SceneManager<T>) {
-        self.physics.render(device, deltatime, scenemanager)
+    pub fn render_physics(&mut self, deltatime: R::DeltaTime, scenemanager: &mut SceneManager<T>)
+    where T: Hierarchical {
+        self.physics.render(deltatime, scenemanager)
+    }
+    /// # add_default_systems
+    /// puts the engines own systems in the schedule:
+    /// - `update_events` then `timers` and `assets` in [`Stage::Input`], so events of the last frame can still be read this one
+    /// - `behaviours` then `propagate` in [`Stage::PreUpdate`] and `propagate_physics` in [`Stage::PostPhysics`]
+    /// - `collisions`, sending [`CollisionEvent`]s, then `physics` in [`Stage::Physics`]
+    /// - `rendering` then `advance_tick` in [`Stage::Render`]
+    pub fn add_default_systems(&mut self)
+    where T: Hierarchical, Self: 'static {
+        self.schedule.add(Stage::Input, "update_events", |systems: &mut Self| systems.events.update());
+        self.schedule.add(Stage::Input, "timers", |systems: &mut Self| systems.run_timers())
+            .after("update_events");
+        self.schedule.add(Stage::Input, "assets", |systems: &mut Self| {
+            let device = systems.frame.as_ref().and_then(|frame| frame.target.as_ref()).map(|target| target.device.clone());
+            let events = systems.assets.update(device.as_ref());
+            systems.events.send_batch(events);
+        })
+            .after("update_events");
+        self.schedule.add(Stage::PreUpdate, "behaviours", |systems: &mut Self| {
+            let Some(frame) = &systems.frame else {
+                return;
+            };
+            let deltatime = frame.delta_time;
+            systems.run_behaviours(deltatime);
+        }).before("propagate");
+        self.schedule.add(Stage::PreUpdate, "propagate", |systems: &mut Self| systems.propagate());
+        self.schedule.add(Stage::Physics, "collisions", |systems: &mut Self| {
+            let events = systems.collisions.render(&systems.scene_manager);
+            systems.events.send_batch(events);
+        });
+        self.schedule.add(Stage::Physics, "physics", |systems: &mut Self| {
+            let Some(frame) = &systems.frame else {
+                return;
+            };
+            systems.physics.render(frame.delta_time, &mut systems.scene_manager);
+        }).after("collisions");
+        self.schedule.add(Stage::PostPhysics, "propagate_physics", |systems: &mut Self| systems.propagate());
+        self.schedule.add(Stage::Render, "rendering", |systems: &mut Self| {
+            let Some(target) = systems.frame.as_ref().and_then(|frame| frame.target.clone()) else {
+                return;
+            };
+            systems.rendering.render(target.device, target.command_buffer, target.layout, &systems.scene_manager);
+        });
+        self.schedule.add(Stage::Render, "advance_tick", |systems: &mut Self| systems.scene_manager.world.advance_tick())
+            .after("rendering");
+    }
+    /// # run_timers
+    /// counts the timers down by the scaled delta of the frame, running the ones
+    /// that ran out. Timers started by callbacks wait for the next frame, and
+    /// timers can't cancel each other while they run.
+    pub fn run_timers(&mut self) {
+        let delta = self.time.delta();
+        let mut timers = self.timers.take();
+        let fired = timers.update(delta, self);
+        timers.absorb(self.timers.take());
+        self.timers = timers;
+        self.events.send_batch(fired);
+    }
+    /// # run_schedule
+    /// runs every system in the schedule for one frame.
+    pub fn run_schedule(&mut self, frame: FrameInfo<R::DeltaTime>) -> Result<(), ScheduleError> {
+        self.frame = Some(frame);
+        let mut schedule = std::mem::take(&mut self.schedule);
+        let result = if self.profiler.is_enabled() {
+            // systems see an empty profiler while the schedule runs
+            let mut profiler = std::mem::take(&mut self.profiler);
+            let result = schedule.run_timed(self, |timed, start, end| profiler.record_timed(timed, start, end));
+            self.profiler = profiler;
+            result
+        } else {
+            schedule.run(self)
+        };
+        // systems added while the schedule ran ended up in the empty one
+        schedule.absorb(std::mem::take(&mut self.schedule));
+        self.schedule = schedule;
+        self.frame = None;
+        result
+    }
+    /// # run_frame
+    /// ticks [`SystemManager::time`] and runs the schedule with its scaled delta.
+    pub fn run_frame(&mut self, target: Option<RenderTarget>) -> Result<(), ScheduleError>
+    where R: mofongo::bodies::RigidBody<DeltaTime = f32> {
+        self.time.tick();
+        self.run_schedule(FrameInfo { delta_time: self.time.delta(), target })
+    }
+}
+
+impl<V: Vertex + 'static, I: VulkanIndexable + 'static, R: mofongo::bodies::RigidBody<Transformation = TransformQuaternion3D, SpatialVector = FVec3> + 'static> SystemManager<V, FVec3, I, TransformQuaternion3D, R> {
+    /// buoyancy and drag only exist for 3D bodies, this has to run before [`SystemManager::render_physics`].
+    pub fn render_fluids(&mut self, scenemanager: &SceneManager<TransformQuaternion3D>) {
+        self.fluids.render(&self.physics, scenemanager)
+    }
+    /// # step_physics
+    /// moves the physics `delta` seconds forward, in steps of [`SystemManager::physics_step`]
+    /// when it is set, carrying what is left over to the next frame. Every step
+    /// checks the colliders, sending their [`CollisionEvent`]s, pushes bodies
+    /// around in fluids and then moves them, so nothing tunnels between steps.
+    pub fn step_physics(&mut self, delta: f32)
+    where R: mofongo::bodies::RigidBody<DeltaTime = f32> {
+        let Some(step) = self.physics_step.filter(|step| *step > 0.0) else {
+            self.physics_substep(delta);
+            return;
+        };
+        self.physics_accumulator += delta;
+        let mut steps = 0;
+        while self.physics_accumulator >= step && steps < MAX_PHYSICS_STEPS {
+            self.physics_substep(step);
+            self.physics_accumulator -= step;
+            steps += 1;
+        }
+        if steps == MAX_PHYSICS_STEPS {
+            self.physics_accumulator = self.physics_accumulator.min(step);
+        }
+        self.time.set_fixed(Some(step), self.physics_accumulator / step);
+    }
+    /// one step of [`SystemManager::step_physics`], `delta` seconds long. World
+    /// transforms are propagated after it, so the next step collides children where
+    /// their parents moved them.
+    fn physics_substep(&mut self, delta: f32)
+    where R: mofongo::bodies::RigidBody<DeltaTime = f32> {
+        let events = self.collisions.render(&self.scene_manager);
+        self.events.send_batch(events);
+        self.fluids.render(&self.physics, &self.scene_manager);
+        self.physics.render(delta, &mut self.scene_manager);
+        self.propagate();
     }
-    pub fn render_all(&mut self, device: std::sync::Arc<LogicalDevice>, deltatime: R::DeltaTime, command_buffer: vk::CommandBuffer, layout: vk::PipelineLayout, scenemanager: &mut SceneManager<T>) {
-        self.render_collisions(device.clone(), scenemanager);
-        self.render_graphics(device.clone(), command_buffer, layout, scenemanager);
-        self.render_physics(device.clone(), deltatime, scenemanager);
+    /// [`SystemManager::add_default_systems`] with `collisions` folded into `physics`,
+    /// which runs [`SystemManager::step_physics`] so collisions and fluids happen in
+    /// every fixed step of [`SystemManager::physics_step`] when it is set. Also adds
+    /// `animation`, which runs between `behaviours` and `propagate`, and `spatial_index`,
+    /// which runs after `propagate_physics`.
+    pub fn add_solid_systems(&mut self)
+    where Self: 'static, R: mofongo::bodies::RigidBody<DeltaTime = f32> {
+        self.add_default_systems();
+        // the default ones can't step at a fixed rate without knowing the delta is an f32
+        self.schedule.remove("collisions");
+        self.schedule.remove("physics");
+        self.schedule.add(Stage::Physics, "physics", |systems: &mut Self| {
+            let Some(frame) = &systems.frame else {
+                return;
+            };
+            let delta = frame.delta_time;
+            systems.step_physics(delta);
+        });
+        self.schedule.add(Stage::PreUpdate, "animation", |systems: &mut Self| {
+            let Some(frame) = &systems.frame else {
+                return;
+            };
+            systems.animations.render(frame.delta_time, &mut systems.scene_manager);
+        })
+            .after("behaviours")
+            .before("propagate");
+        self.schedule.add(Stage::PostPhysics, "spatial_index", |systems: &mut Self| systems.spatial.update(&mut systems.scene_manager))
+            .after("propagate_physics");
     }
 }
\ No newline at end of file
@@ -0,0 +1,281 @@
+use std::time::{Duration, Instant};
+
+/// # Time
+/// The game clock. `real` values are wall clock time, the others are scaled by
+/// the time scale and stop while paused, which is what gameplay should use.
+pub struct Time {
+    real_delta: f32,
+    delta: f32,
+    real_elapsed: Duration,
+    elapsed: Duration,
+    frame: u64,
+    scale: f32,
+    paused: bool,
+    /// the longest a single frame can be, so a breakpoint or a dragged window
+    /// doesn't throw everything across the map.
+    pub max_delta: f32,
+    fixed_step: Option<f32>,
+    overstep: f32,
+    last: Option<Instant>,
+}
+
+impl Time {
+    pub fn new() -> Self {
+        Self {
+            real_delta: 0.0,
+            delta: 0.0,
+            real_elapsed: Duration::ZERO,
+            elapsed: Duration::ZERO,
+            frame: 0,
+            scale: 1.0,
+            paused: false,
+            max_delta: 0.25,
+            fixed_step: None,
+            overstep: 0.0,
+            last: None,
+        }
+    }
+    /// # tick
+    /// starts a new frame, measuring the time since the last one.
+    /// The first frame has a delta of 0.
+    pub fn tick(&mut self) {
+        let now = Instant::now();
+        let real = self.last.map_or(0.0, |last| (now - last).as_secs_f32());
+        self.last = Some(now);
+        self.advance(real);
+    }
+    /// # advance
+    /// starts a new frame `real_delta` seconds after the last one, for when the
+    /// frame time comes from somewhere else than the wall clock.
+    pub fn advance(&mut self, real_delta: f32) {
+        let real_delta = real_delta.clamp(0.0, self.max_delta);
+        self.real_delta = real_delta;
+        self.real_elapsed += Duration::from_secs_f32(real_delta);
+        self.delta = if self.paused { 0.0 } else { real_delta * self.scale };
+        self.elapsed += Duration::from_secs_f32(self.delta);
+        self.frame += 1;
+    }
+    /// scaled seconds since the last frame, 0 while paused.
+    pub fn delta(&self) -> f32 {
+        self.delta
+    }
+    pub fn real_delta(&self) -> f32 {
+        self.real_delta
+    }
+    /// scaled time since the start, doesn't move while paused.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+    pub fn real_elapsed(&self) -> Duration {
+        self.real_elapsed
+    }
+    /// frames started so far.
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+    /// how fast the game runs, 0.5 is half speed. Negative scales are treated as 0.
+    pub fn set_scale(&mut self, scale: f32) {
+        self.scale = scale.max(0.0);
+    }
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+    /// the step of the fixed update loop, `None` when frames run with a variable delta.
+    pub fn fixed_step(&self) -> Option<f32> {
+        self.fixed_step
+    }
+    /// how far into the next fixed step the clock is, from 0 to 1,
+    /// for interpolating between the last two steps when drawing.
+    pub fn overstep(&self) -> f32 {
+        self.overstep
+    }
+    pub(crate) fn set_fixed(&mut self, fixed_step: Option<f32>, overstep: f32) {
+        self.fixed_step = fixed_step;
+        self.overstep = overstep;
+    }
+}
+
+impl Default for Time {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TimerId(u64);
+
+/// # TimerFired
+/// sent for timers started without a callback, see [`Timers::after_event`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimerFired {
+    pub timer: TimerId,
+}
+
+/// what a timer does when it runs out.
+pub enum TimerAction<C> {
+    Callback(Box<dyn FnMut(&mut C)>),
+    /// sends a [`TimerFired`].
+    Event,
+}
+
+struct Timer<C> {
+    id: TimerId,
+    remaining: f32,
+    /// how often it fires again, `None` fires only once.
+    interval: Option<f32>,
+    action: TimerAction<C>,
+}
+
+/// # Timers
+/// One shot and repeating timers running in game time, so they stop with the
+/// game and follow its time scale. Timers only fire once a frame at most, a
+/// repeating timer shorter than a frame falls behind instead of firing twice.
+pub struct Timers<C> {
+    timers: Vec<Timer<C>>,
+    next_id: u64,
+}
+
+impl<C> Timers<C> {
+    pub fn new() -> Self {
+        Self { timers: vec![], next_id: 0 }
+    }
+    fn start(&mut self, seconds: f32, interval: Option<f32>, action: TimerAction<C>) -> TimerId {
+        let id = TimerId(self.next_id);
+        self.next_id += 1;
+        self.timers.push(Timer { id, remaining: seconds, interval, action });
+        id
+    }
+    /// runs `callback` once, `seconds` from now.
+    pub fn after(&mut self, seconds: f32, callback: impl FnMut(&mut C) + 'static) -> TimerId {
+        self.start(seconds, None, TimerAction::Callback(Box::new(callback)))
+    }
+    /// runs `callback` every `seconds`, starting `seconds` from now. `None` when
+    /// `seconds` isn't positive, since the timer would have to fire every frame.
+    pub fn every(&mut self, seconds: f32, callback: impl FnMut(&mut C) + 'static) -> Option<TimerId> {
+        (seconds > 0.0).then(|| self.start(seconds, Some(seconds), TimerAction::Callback(Box::new(callback))))
+    }
+    /// sends a [`TimerFired`] once, `seconds` from now.
+    pub fn after_event(&mut self, seconds: f32) -> TimerId {
+        self.start(seconds, None, TimerAction::Event)
+    }
+    /// sends a [`TimerFired`] every `seconds`, `None` when `seconds` isn't positive.
+    pub fn every_event(&mut self, seconds: f32) -> Option<TimerId> {
+        (seconds > 0.0).then(|| self.start(seconds, Some(seconds), TimerAction::Event))
+    }
+    pub fn cancel(&mut self, id: TimerId) -> bool {
+        let count = self.timers.len();
+        self.timers.retain(|timer| timer.id != id);
+        count != self.timers.len()
+    }
+    pub fn is_active(&self, id: TimerId) -> bool {
+        self.timers.iter().any(|timer| timer.id == id)
+    }
+    /// seconds until the timer fires next.
+    pub fn remaining(&self, id: TimerId) -> Option<f32> {
+        self.timers.iter().find(|timer| timer.id == id).map(|timer| timer.remaining)
+    }
+    /// # update
+    /// counts every timer down by `delta`, running the callbacks of the ones
+    /// that ran out on `context` and returning the events of the rest. Nothing
+    /// runs while time stands still, so a paused game doesn't fire due timers.
+    pub fn update(&mut self, delta: f32, context: &mut C) -> Vec<TimerFired> {
+        if delta.is_nan() || delta <= 0.0 {
+            return vec![];
+        }
+        let mut fired = vec![];
+        let mut index = 0;
+        while index < self.timers.len() {
+            let timer = &mut self.timers[index];
+            timer.remaining -= delta;
+            if timer.remaining > 0.0 {
+                index += 1;
+                continue;
+            }
+            match &mut timer.action {
+                TimerAction::Callback(callback) => callback(context),
+                TimerAction::Event => fired.push(TimerFired { timer: timer.id }),
+            }
+            match timer.interval {
+                Some(interval) => {
+                    timer.remaining = (timer.remaining + interval).max(0.0);
+                    index += 1;
+                }
+                None => {
+                    self.timers.remove(index);
+                }
+            }
+        }
+        fired
+    }
+    /// takes every timer out, leaving none behind but still handing out new ids
+    /// after the ones taken, so the two sets can be merged again with [`Timers::absorb`].
+    pub(crate) fn take(&mut self) -> Timers<C> {
+        std::mem::replace(self, Timers { timers: vec![], next_id: self.next_id })
+    }
+    /// moves the timers of `other` over, keeping their ids.
+    pub(crate) fn absorb(&mut self, other: Timers<C>) {
+        self.timers.extend(other.timers);
+        self.next_id = self.next_id.max(other.next_id);
+    }
+}
+
+impl<C> Default for Timers<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeating_timer_fires_every_interval() {
+        let mut timers = Timers::<u32>::new();
+        let mut count = 0;
+        let id = timers.every(1.0, |count: &mut u32| *count += 1).unwrap();
+        timers.update(0.5, &mut count);
+        assert_eq!(count, 0);
+        timers.update(0.5, &mut count);
+        assert_eq!(count, 1);
+        timers.update(1.0, &mut count);
+        assert_eq!(count, 2);
+        assert!(timers.is_active(id));
+        assert_eq!(timers.remaining(id), Some(1.0));
+    }
+
+    #[test]
+    fn paused_time_fires_nothing() {
+        let mut timers = Timers::<u32>::new();
+        let mut count = 0;
+        let id = timers.after(0.0, |count: &mut u32| *count += 1);
+        let event = timers.after_event(1.0);
+        assert!(timers.update(0.0, &mut count).is_empty());
+        timers.update(-1.0, &mut count);
+        assert_eq!(count, 0);
+        assert_eq!(timers.remaining(event), Some(1.0));
+
+        let fired = timers.update(1.0, &mut count);
+        assert_eq!(count, 1);
+        assert_eq!(fired, vec![TimerFired { timer: event }]);
+        assert!(!timers.is_active(id));
+        assert!(!timers.is_active(event));
+    }
+
+    #[test]
+    fn repeating_timer_needs_a_positive_interval() {
+        let mut timers = Timers::<u32>::new();
+        assert!(timers.every(0.0, |_: &mut u32| {}).is_none());
+        assert!(timers.every_event(-1.0).is_none());
+        assert!(timers.every_event(f32::NAN).is_none());
+    }
+}
@@ -0,0 +1,42 @@
+#version 460
+
+layout( push_constant ) uniform constants
+{
+    mat4 transform;
+    mat4 normal;
+} pc;
+
+layout(location = 0) in vec3 pos;
+layout(location = 1) in vec3 normal;
+layout(location = 2) in vec2 uv;
+layout(location = 3) in uvec4 joints;
+layout(location = 4) in vec4 weights;
+
+layout(location = 0) out vec2 uv_o;
+layout(location = 1) out vec3 color_o;
+layout(set = 0, binding = 0) uniform sampler2D textures[];
+layout(std430, set = 1, binding = 0) readonly buffer Palette {
+    mat4 matrices[];
+} palette;
+
+const vec3 DIRECTION = normalize(vec3(1.0, -3.0, -1.0));
+const float AMBIENT = 0.02;
+
+void main() {
+    mat4 skin = weights.x * palette.matrices[joints.x]
+              + weights.y * palette.matrices[joints.y]
+              + weights.z * palette.matrices[joints.z]
+              + weights.w * palette.matrices[joints.w];
+    // vertices without joints keep their bind pose
+    if (weights.x + weights.y + weights.z + weights.w == 0.0) {
+        skin = mat4(1.0);
+    }
+    gl_Position = pc.transform * skin * vec4(pos, 1.0);
+
+    vec3 normal_ws = normalize(mat3(pc.normal) * mat3(skin) * normal);
+
+    float intensity = AMBIENT + max(dot(normal_ws, DIRECTION), 0);
+
+    uv_o = uv;
+    color_o = intensity * vec3(0.5, 0.0, 0.5);
+}
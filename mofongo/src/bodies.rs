@@ -10,6 +10,8 @@ pub trait RigidBody {
     fn angular_velocity(&self) -> Self::SpatialVector;
     fn mass(&self) -> Self::Mass;
     fn apply_force(&mut self, force: Self::SpatialVector, pos: Self::SpatialVector);
+    /// a force through the center of mass, which moves the body without turning it.
+    fn apply_central_force(&mut self, force: Self::SpatialVector);
     fn apply_torque(&mut self, torque: <Self::SpatialVector as EuclideanGeometry>::CrossProduct);
     fn step(&mut self, deltatime: Self::DeltaTime, gravity: Self::SpatialVector, transform: &Self::Transformation) -> Self::Transformation;
 }
\ No newline at end of file
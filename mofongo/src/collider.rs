@@ -1,16 +1,32 @@
+use std::rc::Rc;
+
 use drowsed_math::{Transform, Triangle, Vector};
 
+use crate::solid::hull::ConvexHull;
+
 pub enum ColliderLayout<'a, V: Vector> {
     Vertices(&'a Vec<V>),
     IndexedVertices(&'a Vec<V>, &'a Vec<u32>),
     Triangles(&'a Vec<Triangle<V>>),
     IndexedTriangles(&'a Vec<Triangle<V>>, &'a Vec<u32>),
+    Hull(&'a ConvexHull<V>),
+    /// made out of several child colliders, which can't be read from the layout.
+    /// Colliders that get this should call `collision` on the compound instead.
+    Compound,
 }
 
 #[derive(Debug, Default, Clone, Copy)]
 pub struct CollisionInfo<V: Vector> {
     pub normal: V,
     pub depth: f32,
+    /// world space contact points, only the first `contact_count` are valid.
+    /// colliders that can't build a manifold leave this empty.
+    pub contacts: [V; 4],
+    pub contact_count: usize,
+    /// which child of a compound collider was hit, `None` for every other collider.
+    pub child: Option<usize>,
+    /// same as `child` but for the other collider taking part in the collision.
+    pub other_child: Option<usize>,
 }
 
 pub trait Collider {
@@ -21,6 +37,11 @@ pub trait Collider {
         transform2: &Self::TransformComponent
     ) -> Option<CollisionInfo<Self::ColliderLayoutVertex>>;
     fn layout(&self) -> ColliderLayout<Self::ColliderLayoutVertex>;
+    /// the [`ColliderLayout::Hull`] of the collider moved by `transform`, for colliders
+    /// that keep it around between collisions. `None` leaves moving it to the caller.
+    fn world_hull(&self, _transform: &Self::TransformComponent) -> Option<Rc<ConvexHull<Self::ColliderLayoutVertex>>> {
+        None
+    }
     type ColliderLayoutVertex: Vector;
     type TransformComponent: Transform;
 }
\ No newline at end of file
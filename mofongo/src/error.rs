@@ -0,0 +1,22 @@
+use std::fmt;
+
+/// # MofongoError
+/// why a shape couldn't be built.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MofongoError {
+    /// a hull needs at least 4 points, this is how many there were.
+    TooFewPoints(usize),
+    /// the points are all on a line or a plane so they don't enclose anything.
+    Flat,
+}
+
+impl fmt::Display for MofongoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MofongoError::TooFewPoints(count) => write!(f, "a hull needs at least 4 points, got {count}"),
+            MofongoError::Flat => write!(f, "the points are flat and don't enclose a volume"),
+        }
+    }
+}
+
+impl std::error::Error for MofongoError {}
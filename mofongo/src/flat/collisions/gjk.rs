@@ -111,6 +111,7 @@ fn epa(vertices1: &Vec<Vector2<f32>>, vertices2: &Vec<Vector2<f32>>, transform1:
     let collision_info = CollisionInfo {
         normal: min_normal * (min_distance + 0.001),
         depth: min_distance,
+        ..Default::default()
     };
     return Some(collision_info);
 }
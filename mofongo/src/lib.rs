@@ -2,3 +2,4 @@ pub mod solid;
 pub mod collider;
 pub mod bodies;
 pub mod flat;
+pub mod error;
\ No newline at end of file
@@ -0,0 +1,144 @@
+use drowsed_math::{FVec3, FMat4, TransformQuaternion3D, SquareMatrix, TransformMatrix};
+
+use crate::collider::{CollisionInfo, Collider, ColliderLayout};
+use crate::solid::hull::decomposition::{decompose, DecompositionParameters};
+use crate::solid::hull::{quickhull::quickhull, transform_point};
+use crate::solid::physics::mass::MassProperties;
+use crate::solid::transform::compose;
+
+use super::sat::SATColliderSolid;
+
+pub type SolidCollider = dyn Collider<TransformComponent = TransformQuaternion3D, ColliderLayoutVertex = FVec3>;
+
+/// swaps which collider the info is relative to, the normal then points the other way.
+pub(crate) fn flip(info: CollisionInfo<FVec3>) -> CollisionInfo<FVec3> {
+    CollisionInfo { normal: -info.normal, child: info.other_child, other_child: info.child, ..info }
+}
+
+/// local bounding box of a collider, if its layout exposes its vertices.
+fn layout_bounds(layout: ColliderLayout<FVec3>) -> Option<(FVec3, FVec3)> {
+    let vertices = match layout {
+        ColliderLayout::Vertices(vertices) => vertices,
+        ColliderLayout::IndexedVertices(vertices, _) => vertices,
+        ColliderLayout::Hull(hull) => &hull.vertices,
+        _ => return None,
+    };
+    let mut min = *vertices.first()?;
+    let mut max = min;
+    for vertex in vertices {
+        min.x = min.x.min(vertex.x); min.y = min.y.min(vertex.y); min.z = min.z.min(vertex.z);
+        max.x = max.x.max(vertex.x); max.y = max.y.max(vertex.y); max.z = max.z.max(vertex.z);
+    }
+    Some((min, max))
+}
+
+pub struct CompoundChild {
+    /// offset of the child relative to the object owning the compound collider.
+    pub transform: TransformQuaternion3D,
+    pub collider: Box<SolidCollider>,
+    pub mass: f32,
+}
+
+impl CompoundChild {
+    /// # mass_properties
+    /// the child is treated as the solid hull of its collider, see [`MassProperties::hull`].
+    /// Flat colliders count as a box filling their bounds, and colliders whose
+    /// layout doesn't expose vertices as a point mass.
+    pub fn mass_properties(&self) -> MassProperties {
+        let mut matrix = FMat4::identity();
+        self.transform.apply_matrix4(&mut matrix);
+        let hull = match self.collider.layout() {
+            ColliderLayout::Hull(hull) => Some(hull.transformed(&matrix)),
+            ColliderLayout::Vertices(vertices) | ColliderLayout::IndexedVertices(vertices, _) => {
+                quickhull(&vertices.iter().map(|vertex| transform_point(*vertex, &matrix)).collect(), None)
+            }
+            _ => None,
+        };
+        if let Some(hull) = hull {
+            return MassProperties::hull(self.mass, &hull);
+        }
+        let scale = self.transform.scale;
+        let (center, size) = match layout_bounds(self.collider.layout()) {
+            Some((min, max)) => ((min + max) * 0.5, max - min),
+            None => (FVec3::from(0.0), FVec3::from(0.0)),
+        };
+        let center = FVec3::new(center.x * scale.x, center.y * scale.y, center.z * scale.z);
+        let size = FVec3::new(size.x * scale.x, size.y * scale.y, size.z * scale.z);
+        let rotation = self.transform.rotation;
+        let axes = [
+            rotation * FVec3::new(1.0, 0.0, 0.0),
+            rotation * FVec3::new(0.0, 1.0, 0.0),
+            rotation * FVec3::new(0.0, 0.0, 1.0),
+        ];
+        MassProperties::cuboid(self.mass, self.transform.translation + rotation * center, size).rotated(axes)
+    }
+}
+
+/// # CompoundCollider
+/// Several child shapes acting as one collider, each with its own offset from the
+/// owning object, e.g. a car body and its wheels. Concave objects also collide
+/// this way, by splitting them up into convex pieces (see [`CompoundCollider::from_mesh`]).
+///
+/// When colliding, the deepest contact out of all the children is reported and
+/// [`CollisionInfo::child`] says which child it came from.
+pub struct CompoundCollider {
+    pub children: Vec<CompoundChild>,
+}
+impl CompoundCollider {
+    pub fn new() -> Self {
+        CompoundCollider { children: vec![] }
+    }
+    /// adds a child and returns its index, which is what collisions report back.
+    pub fn push(&mut self, transform: TransformQuaternion3D, collider: Box<SolidCollider>, mass: f32) -> usize {
+        self.children.push(CompoundChild { transform, collider, mass });
+        self.children.len() - 1
+    }
+    /// # from_mesh
+    /// runs an approximate convex decomposition on a triangle mesh and makes a
+    /// [`SATColliderSolid`] child out of every hull. `mass` is split between the
+    /// children by the volume of their hulls.
+    pub fn from_mesh(vertices: &Vec<FVec3>, indices: &Vec<u32>, parameters: &DecompositionParameters, mass: f32) -> Self {
+        let hulls = decompose(vertices, indices, parameters);
+        let total_volume: f32 = hulls.iter().map(|hull| hull.volume()).sum();
+        let mut compound = Self::new();
+        for hull in hulls {
+            let child_mass = if total_volume > 0.0 { mass * hull.volume() / total_volume } else { 0.0 };
+            compound.push(TransformQuaternion3D::default(), Box::new(SATColliderSolid::new(hull)), child_mass);
+        }
+        compound
+    }
+    /// transform of a child in world space, given the transform of the owning object.
+    pub fn child_transform(&self, child: usize, transform: &TransformQuaternion3D) -> TransformQuaternion3D {
+        compose(transform, &self.children[child].transform)
+    }
+    pub fn mass(&self) -> f32 {
+        self.children.iter().map(|child| child.mass).sum()
+    }
+    /// # mass_properties
+    /// combined mass, center of mass and inertia of every child, relative to
+    /// the owning object. Pass it to [`crate::solid::physics::rigidbody::RigidBody3D::from_mass_properties`].
+    pub fn mass_properties(&self) -> MassProperties {
+        let parts = self.children.iter().map(|child| child.mass_properties()).collect();
+        MassProperties::combine(&parts)
+    }
+}
+
+impl Collider for CompoundCollider {
+    fn collision(&self, transform1: &TransformQuaternion3D, collider: &SolidCollider, transform2: &TransformQuaternion3D) -> Option<CollisionInfo<FVec3>> {
+        let mut deepest: Option<CollisionInfo<FVec3>> = None;
+        for (i, child) in self.children.iter().enumerate() {
+            let transform = compose(transform1, &child.transform);
+            if let Some(info) = child.collider.collision(&transform, collider, transform2) {
+                if deepest.map_or(true, |deepest| info.depth > deepest.depth) {
+                    deepest = Some(CollisionInfo { child: Some(i), ..info });
+                }
+            }
+        }
+        deepest
+    }
+    fn layout(&self) -> ColliderLayout<FVec3> {
+        ColliderLayout::Compound
+    }
+    type ColliderLayoutVertex = FVec3;
+    type TransformComponent = TransformQuaternion3D;
+}
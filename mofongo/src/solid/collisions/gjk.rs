@@ -1,6 +1,9 @@
 use drowsed_math::{Vector3, Vector4, Number, SignedNumber, FVec3, FVec4, FMat4, Matrix4, Simplex, Transform, TransformQuaternion3D, Vector, SquareMatrix, TransformMatrix};
 
 use crate::collider::{CollisionInfo, Collider, ColliderLayout};
+use crate::solid::hull::ConvexHull;
+
+use super::compound::flip;
 use drowsed_math::EuclideanGeometry;
 /// # furthest_point
 /// *this function only works if the object is a convext polygon*
@@ -257,7 +260,7 @@ fn epa(simplex: Simplex<FVec3, 4>, vertices1: &Vec<Vector3<f32>>, vertices2: &Ve
             }
         }
     }
-    Some(CollisionInfo { normal: min_normal, depth: min_distance + 0.001 })
+    Some(CollisionInfo { normal: min_normal, depth: min_distance + 0.001, ..Default::default() })
 }
 
 pub fn gjk(vertices1: &Vec<Vector3<f32>>, vertices2: &Vec<Vector3<f32>>, transform1: &FMat4, transform2: &FMat4) -> Option<CollisionInfo<FVec3>> {
@@ -289,6 +292,21 @@ impl GJKColliderSolid {
     pub fn new(vertices: Vec<FVec3>) -> Self {
         GJKColliderSolid { vertices }
     }
+    /// # from_points
+    /// only keeps the vertices on the convex hull of `points`, since models carry
+    /// a lot of interior and duplicate vertices which just slow down support queries.
+    /// `max_vertices` simplifies the hull further. Falls back to every point if the
+    /// points are flat and no hull can be built.
+    pub fn from_points(points: &Vec<FVec3>, max_vertices: Option<usize>) -> Self {
+        let hull = match max_vertices {
+            Some(max_vertices) => ConvexHull::from_points_simplified(points, max_vertices),
+            None => ConvexHull::from_points(points),
+        };
+        match hull {
+            Some(hull) => GJKColliderSolid { vertices: hull.vertices },
+            None => GJKColliderSolid { vertices: points.clone() },
+        }
+    }
 }
 
 impl Collider for GJKColliderSolid {
@@ -301,6 +319,12 @@ impl Collider for GJKColliderSolid {
             ColliderLayout::Vertices(vertices) => {
                 gjk(&self.vertices, vertices, &mat1, &mat2)
             }
+            ColliderLayout::Hull(hull) => {
+                gjk(&self.vertices, &hull.vertices, &mat1, &mat2)
+            }
+            ColliderLayout::Compound => {
+                collider.collision(transform2, self, transform1).map(flip)
+            }
             _ => {
                 panic!("Layout not supported for GJKCollider")
             }
@@ -1 +1,3 @@
-pub mod gjk;
\ No newline at end of file
+pub mod gjk;
+pub mod sat;
+pub mod compound;
\ No newline at end of file
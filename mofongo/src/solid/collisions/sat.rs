@@ -0,0 +1,415 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use drowsed_math::{FVec3, FMat4, TransformQuaternion3D, Vector, SquareMatrix, TransformMatrix, EuclideanGeometry};
+
+use crate::collider::{CollisionInfo, Collider, ColliderLayout};
+use crate::error::MofongoError;
+use crate::solid::hull::{ConvexHull, transform_point};
+
+use super::compound::flip;
+use super::gjk::gjk;
+
+/// edge contacts only win over face contacts if they are clearly better,
+/// otherwise tiny float differences make the manifold flip between the two every frame.
+const EDGE_RELATIVE_TOLERANCE: f32 = 0.95;
+const FACE_RELATIVE_TOLERANCE: f32 = 0.98;
+const ABSOLUTE_TOLERANCE: f32 = 0.005;
+
+struct FaceQuery {
+    index: usize,
+    separation: f32,
+}
+
+struct EdgeQuery {
+    edge1: usize,
+    edge2: usize,
+    axis: FVec3,
+    separation: f32,
+}
+
+/// # query_face_directions
+/// tests every face normal of `hull1` as a separating axis. The separation is the
+/// distance from the face plane to the deepest point of `hull2`.
+fn query_face_directions(hull1: &ConvexHull<FVec3>, hull2: &ConvexHull<FVec3>) -> FaceQuery {
+    let mut query = FaceQuery { index: 0, separation: f32::MIN };
+    for (i, face) in hull1.faces.iter().enumerate() {
+        let point = hull1.vertices[face.indices[0] as usize];
+        let support = hull2.support(-face.normal);
+        let separation = face.normal.dot(&(support - point));
+        if separation > query.separation {
+            query = FaceQuery { index: i, separation };
+        }
+    }
+    query
+}
+
+/// # is_minkowski_face
+/// whether the arcs `a`-`b` and `c`-`d` cross on the Gauss map, where `a` and `b`
+/// are the normals of the faces next to an edge of one hull and `c` and `d` the
+/// negated normals next to an edge of the other. Only edge pairs that cross
+/// build a face of the Minkowski difference, every other pair can't be the
+/// closest feature and would give a made up contact.
+fn is_minkowski_face(a: FVec3, b: FVec3, c: FVec3, d: FVec3) -> bool {
+    let b_x_a = b.cross(a);
+    let d_x_c = d.cross(c);
+    let cba = c.dot(&b_x_a);
+    let dba = d.dot(&b_x_a);
+    let adc = a.dot(&d_x_c);
+    let bdc = b.dot(&d_x_c);
+    cba * dba < 0.0 && adc * bdc < 0.0 && cba * bdc > 0.0
+}
+
+/// # query_edge_directions
+/// tests the cross product of every edge pair on the Minkowski difference as a
+/// separating axis. The separation is measured from the edge of `hull1` to the
+/// edge of `hull2` along the axis, which always points out of `hull1`.
+fn query_edge_directions(hull1: &ConvexHull<FVec3>, hull2: &ConvexHull<FVec3>) -> EdgeQuery {
+    let mut query = EdgeQuery { edge1: 0, edge2: 0, axis: FVec3::from(0.0), separation: f32::MIN };
+    let centroid1 = hull1.centroid();
+    for (i, (a0, a1)) in hull1.edges.iter().enumerate() {
+        let (u1, v1) = hull1.edge_faces[i];
+        if u1 == usize::MAX || v1 == usize::MAX {
+            continue;
+        }
+        let (u1, v1) = (hull1.faces[u1].normal, hull1.faces[v1].normal);
+        let point1 = hull1.vertices[*a0 as usize];
+        let edge1 = hull1.vertices[*a1 as usize] - point1;
+        for (j, (b0, b1)) in hull2.edges.iter().enumerate() {
+            let (u2, v2) = hull2.edge_faces[j];
+            if u2 == usize::MAX || v2 == usize::MAX {
+                continue;
+            }
+            let (u2, v2) = (hull2.faces[u2].normal, hull2.faces[v2].normal);
+            if !is_minkowski_face(u1, v1, -u2, -v2) {
+                continue;
+            }
+            let point2 = hull2.vertices[*b0 as usize];
+            let edge2 = hull2.vertices[*b1 as usize] - point2;
+            let mut axis = edge1.cross(edge2);
+            let length = axis.length();
+            // parallel edges don't give us a new axis, the face tests already cover them.
+            if length <= 0.0001 * edge1.length() * edge2.length() {
+                continue;
+            }
+            axis = axis / length;
+            if axis.dot(&(point1 - centroid1)) < 0.0 {
+                axis = -axis;
+            }
+
+            let separation = axis.dot(&(point2 - point1));
+            if separation > query.separation {
+                query = EdgeQuery { edge1: i, edge2: j, axis, separation };
+            }
+        }
+    }
+    query
+}
+
+/// # clip_polygon
+/// Sutherland-Hodgman clip, keeps everything on the side of the plane where
+/// `normal.dot(point) <= offset`.
+pub(crate) fn clip_polygon(polygon: &Vec<FVec3>, normal: FVec3, offset: f32) -> Vec<FVec3> {
+    let mut clipped = Vec::<FVec3>::with_capacity(polygon.len() + 1);
+    if polygon.is_empty() {
+        return clipped;
+    }
+    let mut start = polygon[polygon.len() - 1];
+    let mut start_distance = normal.dot(&start) - offset;
+    for end in polygon {
+        let end_distance = normal.dot(end) - offset;
+        if start_distance <= 0.0 && end_distance <= 0.0 {
+            clipped.push(*end);
+        } else if start_distance <= 0.0 && end_distance > 0.0 {
+            let t = start_distance / (start_distance - end_distance);
+            clipped.push(start + (*end - start) * t);
+        } else if start_distance > 0.0 && end_distance <= 0.0 {
+            let t = start_distance / (start_distance - end_distance);
+            clipped.push(start + (*end - start) * t);
+            clipped.push(*end);
+        }
+        start = *end;
+        start_distance = end_distance;
+    }
+    clipped
+}
+
+/// # reduce_contacts
+/// keeps at most 4 contact points, the deepest one and then the ones
+/// that span the largest area since those are the ones that keep a body stable.
+fn reduce_contacts(points: &Vec<(FVec3, f32)>) -> ([FVec3; 4], usize) {
+    let mut contacts = [FVec3::from(0.0); 4];
+    if points.len() <= 4 {
+        for (i, (point, _)) in points.iter().enumerate() {
+            contacts[i] = *point;
+        }
+        return (contacts, points.len());
+    }
+    let mut deepest = 0;
+    for (i, (_, depth)) in points.iter().enumerate() {
+        if *depth > points[deepest].1 {
+            deepest = i;
+        }
+    }
+    let a = points[deepest].0;
+
+    let mut furthest = 0;
+    let mut max_distance = f32::MIN;
+    for (i, (point, _)) in points.iter().enumerate() {
+        let distance = (*point - a).length();
+        if distance > max_distance {
+            max_distance = distance;
+            furthest = i;
+        }
+    }
+    let b = points[furthest].0;
+
+    let mut widest = 0;
+    let mut max_area = f32::MIN;
+    for (i, (point, _)) in points.iter().enumerate() {
+        let area = (*point - a).cross(*point - b).length();
+        if area > max_area {
+            max_area = area;
+            widest = i;
+        }
+    }
+    let c = points[widest].0;
+
+    let center = (a + b + c) / 3.0;
+    let mut last = 0;
+    let mut max_distance = f32::MIN;
+    for (i, (point, _)) in points.iter().enumerate() {
+        if i == deepest || i == furthest || i == widest {
+            continue;
+        }
+        let distance = (*point - center).length();
+        if distance > max_distance {
+            max_distance = distance;
+            last = i;
+        }
+    }
+    contacts[0] = a;
+    contacts[1] = b;
+    contacts[2] = c;
+    contacts[3] = points[last].0;
+    (contacts, 4)
+}
+
+/// # face_contact
+/// builds the manifold by clipping the incident face (the face of `incident` most
+/// opposed to the reference face) against the side planes of the reference face.
+/// The contact points returned are halfway between the two surfaces.
+fn face_contact(reference: &ConvexHull<FVec3>, reference_face: usize, incident: &ConvexHull<FVec3>) -> ([FVec3; 4], usize) {
+    let normal = reference.faces[reference_face].normal;
+    let reference_vertices = reference.face_vertices(reference_face);
+
+    let mut incident_face = 0;
+    let mut min_dot = f32::MAX;
+    for (i, face) in incident.faces.iter().enumerate() {
+        let dot = face.normal.dot(&normal);
+        if dot < min_dot {
+            min_dot = dot;
+            incident_face = i;
+        }
+    }
+    let mut polygon = incident.face_vertices(incident_face);
+
+    let mut center = FVec3::from(0.0);
+    for vertex in &reference_vertices {
+        center += *vertex;
+    }
+    center = center / reference_vertices.len() as f32;
+
+    for i in 0..reference_vertices.len() {
+        let start = reference_vertices[i];
+        let end = reference_vertices[(i + 1) % reference_vertices.len()];
+        let mut side = (end - start).cross(normal);
+        if side.dot(&(center - start)) > 0.0 {
+            side = -side;
+        }
+        polygon = clip_polygon(&polygon, side, side.dot(&start));
+    }
+
+    let plane = normal.dot(&reference_vertices[0]);
+    let mut points = Vec::<(FVec3, f32)>::with_capacity(polygon.len());
+    for point in polygon {
+        let depth = plane - normal.dot(&point);
+        if depth >= 0.0 {
+            points.push((point + normal * (depth * 0.5), depth));
+        }
+    }
+    if points.is_empty() {
+        // numerical trouble, just use the deepest incident vertex.
+        let point = incident.support(-normal);
+        let depth = plane - normal.dot(&point);
+        points.push((point + normal * (depth * 0.5), depth));
+    }
+    reduce_contacts(&points)
+}
+
+/// closest points between the segments p1-q1 and p2-q2.
+fn closest_points_segments(p1: FVec3, q1: FVec3, p2: FVec3, q2: FVec3) -> (FVec3, FVec3) {
+    let d1 = q1 - p1;
+    let d2 = q2 - p2;
+    let r = p1 - p2;
+    let a = d1.dot(&d1);
+    let e = d2.dot(&d2);
+    let f = d2.dot(&r);
+    let c = d1.dot(&r);
+    let b = d1.dot(&d2);
+    let denominator = a * e - b * b;
+
+    let mut s = if denominator > 0.0 { ((b * f - c * e) / denominator).clamp(0.0, 1.0) } else { 0.0 };
+    let mut t = if e > 0.0 { (b * s + f) / e } else { 0.0 };
+    if t < 0.0 {
+        t = 0.0;
+        s = if a > 0.0 { (-c / a).clamp(0.0, 1.0) } else { 0.0 };
+    } else if t > 1.0 {
+        t = 1.0;
+        s = if a > 0.0 { ((b - c) / a).clamp(0.0, 1.0) } else { 0.0 };
+    }
+    (p1 + d1 * s, p2 + d2 * t)
+}
+
+/// # sat
+/// separating axis test between two convex hulls that are already in world space.
+/// The normal of the returned [`CollisionInfo`] points from `hull1` towards `hull2`,
+/// the same direction the GJK collider reports.
+pub fn sat(hull1: &ConvexHull<FVec3>, hull2: &ConvexHull<FVec3>) -> Option<CollisionInfo<FVec3>> {
+    let face_query1 = query_face_directions(hull1, hull2);
+    if face_query1.separation > 0.0 {
+        return None;
+    }
+    let face_query2 = query_face_directions(hull2, hull1);
+    if face_query2.separation > 0.0 {
+        return None;
+    }
+    let edge_query = query_edge_directions(hull1, hull2);
+    if edge_query.separation > 0.0 {
+        return None;
+    }
+
+    let max_face_separation = face_query1.separation.max(face_query2.separation);
+    if EDGE_RELATIVE_TOLERANCE * edge_query.separation > max_face_separation + ABSOLUTE_TOLERANCE {
+        let (a0, a1) = hull1.edges[edge_query.edge1];
+        let (b0, b1) = hull2.edges[edge_query.edge2];
+        let (point1, point2) = closest_points_segments(
+            hull1.vertices[a0 as usize], hull1.vertices[a1 as usize],
+            hull2.vertices[b0 as usize], hull2.vertices[b1 as usize]
+        );
+        let mut contacts = [FVec3::from(0.0); 4];
+        contacts[0] = (point1 + point2) * 0.5;
+        return Some(CollisionInfo {
+            normal: edge_query.axis,
+            depth: -edge_query.separation,
+            contacts,
+            contact_count: 1,
+        });
+    }
+
+    if face_query2.separation > FACE_RELATIVE_TOLERANCE * face_query1.separation + ABSOLUTE_TOLERANCE {
+        let (contacts, contact_count) = face_contact(hull2, face_query2.index, hull1);
+        Some(CollisionInfo {
+            normal: -hull2.faces[face_query2.index].normal,
+            depth: -face_query2.separation,
+            contacts,
+            contact_count,
+        })
+    } else {
+        let (contacts, contact_count) = face_contact(hull1, face_query1.index, hull2);
+        Some(CollisionInfo {
+            normal: hull1.faces[face_query1.index].normal,
+            depth: -face_query1.separation,
+            contacts,
+            contact_count,
+        })
+    }
+}
+
+/// # SATColliderSolid
+/// Convex polyhedron collider using the separating axis theorem. Unlike the
+/// [`super::gjk::GJKColliderSolid`] it needs real hull data (faces and edges),
+/// but in return gives exact normals and a clipped contact manifold, which is
+/// a lot more stable for box heavy scenes. Against colliders that only expose
+/// vertices it falls back to GJK.
+pub struct SATColliderSolid {
+    hull: ConvexHull<FVec3>,
+    /// `hull` moved into world space, along with the transform it was moved by,
+    /// so a body colliding with several others only transforms its hull once a step.
+    world: RefCell<Option<([FVec3; 4], Rc<ConvexHull<FVec3>>)>>,
+}
+impl SATColliderSolid {
+    pub fn new(hull: ConvexHull<FVec3>) -> Self {
+        SATColliderSolid { hull, world: RefCell::new(None) }
+    }
+    pub fn cuboid(half_extents: FVec3) -> Self {
+        Self::new(ConvexHull::cuboid(half_extents))
+    }
+    /// the hull in local space. It can't be changed since the cached world hull
+    /// wouldn't notice, build a new collider instead.
+    pub fn hull(&self) -> &ConvexHull<FVec3> {
+        &self.hull
+    }
+    /// # cached_world_hull
+    /// `hull` moved by `transform`, only transformed again when `transform` changed
+    /// since the last call.
+    fn cached_world_hull(&self, transform: &FMat4) -> Rc<ConvexHull<FVec3>> {
+        // where the origin and the axes end up describe the whole affine transform
+        let key = [
+            transform_point(FVec3::from(0.0), transform),
+            transform_point(FVec3::new(1.0, 0.0, 0.0), transform),
+            transform_point(FVec3::new(0.0, 1.0, 0.0), transform),
+            transform_point(FVec3::new(0.0, 0.0, 1.0), transform),
+        ];
+        if let Some((cached, hull)) = &*self.world.borrow() {
+            if cached.iter().zip(&key).all(|(a, b)| a.x == b.x && a.y == b.y && a.z == b.z) {
+                return hull.clone();
+            }
+        }
+        let hull = Rc::new(self.hull.transformed(transform));
+        *self.world.borrow_mut() = Some((key, hull.clone()));
+        hull
+    }
+    /// builds the hull from a point cloud, e.g. the vertices of a model.
+    /// Returns `None` when the points are flat.
+    pub fn from_points(points: &Vec<FVec3>, max_vertices: Option<usize>) -> Option<Self> {
+        Self::try_from_points(points, max_vertices).ok()
+    }
+    pub fn try_from_points(points: &Vec<FVec3>, max_vertices: Option<usize>) -> Result<Self, MofongoError> {
+        Ok(Self::new(ConvexHull::try_from_points(points, max_vertices)?))
+    }
+}
+
+impl Collider for SATColliderSolid {
+    fn collision(&self, transform1: &TransformQuaternion3D, collider: &dyn Collider<TransformComponent = TransformQuaternion3D, ColliderLayoutVertex = FVec3>, transform2: &TransformQuaternion3D) -> Option<CollisionInfo<FVec3>> {
+        let mut mat1 = FMat4::identity();
+        transform1.apply_matrix4(&mut mat1);
+        let mut mat2 = FMat4::identity();
+        transform2.apply_matrix4(&mut mat2);
+        match collider.layout() {
+            ColliderLayout::Hull(hull) => {
+                let other = collider.world_hull(transform2).unwrap_or_else(|| Rc::new(hull.transformed(&mat2)));
+                sat(&self.cached_world_hull(&mat1), &other)
+            }
+            ColliderLayout::Vertices(vertices) => {
+                gjk(&self.hull.vertices, vertices, &mat1, &mat2)
+            }
+            ColliderLayout::Compound => {
+                collider.collision(transform2, self, transform1).map(flip)
+            }
+            _ => {
+                panic!("Layout not supported for SATCollider")
+            }
+        }
+    }
+    fn layout(&self) -> ColliderLayout<FVec3> {
+        ColliderLayout::Hull(&self.hull)
+    }
+    fn world_hull(&self, transform: &TransformQuaternion3D) -> Option<Rc<ConvexHull<FVec3>>> {
+        let mut mat = FMat4::identity();
+        transform.apply_matrix4(&mut mat);
+        Some(self.cached_world_hull(&mat))
+    }
+    type ColliderLayoutVertex = FVec3;
+    type TransformComponent = TransformQuaternion3D;
+}
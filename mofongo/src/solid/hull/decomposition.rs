@@ -0,0 +1,252 @@
+use std::collections::HashSet;
+
+use drowsed_math::{FVec3, Vector, EuclideanGeometry};
+
+use super::{ConvexHull, quickhull::quickhull};
+
+/// # DecompositionParameters
+/// settings for [`decompose`]. Higher resolutions and more hulls give a closer fit
+/// but take a lot longer, so this should be done once when loading and not every frame.
+#[derive(Debug, Clone, Copy)]
+pub struct DecompositionParameters {
+    /// amount of voxels along the longest side of the mesh.
+    pub resolution: usize,
+    /// maximum amount of convex hulls produced.
+    pub max_hulls: usize,
+    /// a part stops being split once its concavity (the volume its hull adds
+    /// on top of the part, relative to the volume of the whole mesh) is below this.
+    pub max_concavity: f32,
+    /// every hull gets simplified to at most this many vertices.
+    pub max_vertices_per_hull: usize,
+    /// only every n-th voxel plane is tried as a cutting plane.
+    pub plane_downsampling: usize,
+}
+impl Default for DecompositionParameters {
+    fn default() -> Self {
+        Self { resolution: 32, max_hulls: 16, max_concavity: 0.01, max_vertices_per_hull: 32, plane_downsampling: 2 }
+    }
+}
+
+struct VoxelGrid {
+    origin: FVec3,
+    size: f32,
+    dims: [usize; 3],
+}
+impl VoxelGrid {
+    fn index(&self, x: usize, y: usize, z: usize) -> usize {
+        x + self.dims[0] * (y + self.dims[1] * z)
+    }
+    fn coords(&self, index: usize) -> [usize; 3] {
+        [index % self.dims[0], (index / self.dims[0]) % self.dims[1], index / (self.dims[0] * self.dims[1])]
+    }
+    fn voxel_of(&self, point: FVec3) -> usize {
+        let local = (point - self.origin) / self.size;
+        let x = (local.x.max(0.0) as usize).min(self.dims[0] - 1);
+        let y = (local.y.max(0.0) as usize).min(self.dims[1] - 1);
+        let z = (local.z.max(0.0) as usize).min(self.dims[2] - 1);
+        self.index(x, y, z)
+    }
+    fn corner(&self, x: usize, y: usize, z: usize) -> FVec3 {
+        self.origin + FVec3::new(x as f32, y as f32, z as f32) * self.size
+    }
+}
+
+/// # voxelize
+/// marks every voxel the surface passes through and then flood fills the outside,
+/// whatever isn't reached by the flood fill is the solid interior of the mesh.
+/// Returns `None` when every vertex is in the same spot, there is nothing to voxelize.
+fn voxelize(vertices: &Vec<FVec3>, indices: &Vec<u32>, resolution: usize) -> Option<(VoxelGrid, Vec<usize>)> {
+    let mut min = vertices[0];
+    let mut max = vertices[0];
+    for vertex in vertices {
+        min.x = min.x.min(vertex.x); min.y = min.y.min(vertex.y); min.z = min.z.min(vertex.z);
+        max.x = max.x.max(vertex.x); max.y = max.y.max(vertex.y); max.z = max.z.max(vertex.z);
+    }
+    let extent = max - min;
+    let size = extent.x.max(extent.y).max(extent.z) / resolution.max(1) as f32;
+    if !(size > 0.0 && size.is_finite()) {
+        return None;
+    }
+    // one voxel of padding on every side so the flood fill can get around the mesh
+    let dims = [
+        (extent.x / size).ceil() as usize + 3,
+        (extent.y / size).ceil() as usize + 3,
+        (extent.z / size).ceil() as usize + 3,
+    ];
+    let grid = VoxelGrid { origin: min - FVec3::from(size), size, dims };
+
+    let mut surface = vec![false; dims[0] * dims[1] * dims[2]];
+    let mut i = 0;
+    while i + 2 < indices.len() {
+        let a = vertices[indices[i] as usize];
+        let b = vertices[indices[i + 1] as usize];
+        let c = vertices[indices[i + 2] as usize];
+        let longest = (b - a).length().max((c - a).length()).max((c - b).length());
+        let steps = (longest / (size * 0.5)).ceil() as usize + 1;
+        for u in 0..=steps {
+            for v in 0..=(steps - u) {
+                let point = a + (b - a) * (u as f32 / steps as f32) + (c - a) * (v as f32 / steps as f32);
+                surface[grid.voxel_of(point)] = true;
+            }
+        }
+        i += 3;
+    }
+
+    let mut outside = vec![false; surface.len()];
+    let mut stack = vec![0usize];
+    outside[0] = true;
+    while let Some(index) = stack.pop() {
+        let [x, y, z] = grid.coords(index);
+        let mut neighbours = Vec::with_capacity(6);
+        if x > 0 { neighbours.push(grid.index(x - 1, y, z)); }
+        if y > 0 { neighbours.push(grid.index(x, y - 1, z)); }
+        if z > 0 { neighbours.push(grid.index(x, y, z - 1)); }
+        if x + 1 < dims[0] { neighbours.push(grid.index(x + 1, y, z)); }
+        if y + 1 < dims[1] { neighbours.push(grid.index(x, y + 1, z)); }
+        if z + 1 < dims[2] { neighbours.push(grid.index(x, y, z + 1)); }
+        for neighbour in neighbours {
+            if !outside[neighbour] && !surface[neighbour] {
+                outside[neighbour] = true;
+                stack.push(neighbour);
+            }
+        }
+    }
+    let solid = (0..surface.len()).filter(|i| !outside[*i]).collect();
+    Some((grid, solid))
+}
+
+/// hull around the voxels of a part. Only voxels on the boundary of the
+/// part can contribute to the hull so the inner ones are skipped.
+fn part_hull(grid: &VoxelGrid, part: &Vec<usize>, max_vertices: Option<usize>) -> Option<ConvexHull<FVec3>> {
+    let members: HashSet<usize> = part.iter().copied().collect();
+    let mut corners = HashSet::<[usize; 3]>::new();
+    for index in part {
+        let [x, y, z] = grid.coords(*index);
+        let boundary = x == 0 || y == 0 || z == 0
+            || !members.contains(&grid.index(x - 1, y, z))
+            || !members.contains(&grid.index(x, y - 1, z))
+            || !members.contains(&grid.index(x, y, z - 1))
+            || x + 1 >= grid.dims[0] || !members.contains(&grid.index(x + 1, y, z))
+            || y + 1 >= grid.dims[1] || !members.contains(&grid.index(x, y + 1, z))
+            || z + 1 >= grid.dims[2] || !members.contains(&grid.index(x, y, z + 1));
+        if boundary {
+            for corner in 0..8 {
+                corners.insert([x + (corner & 1), y + ((corner >> 1) & 1), z + ((corner >> 2) & 1)]);
+            }
+        }
+    }
+    let points = corners.iter().map(|[x, y, z]| grid.corner(*x, *y, *z)).collect();
+    quickhull(&points, max_vertices)
+}
+
+/// volume the hull adds on top of the voxels, relative to the whole mesh.
+fn concavity(grid: &VoxelGrid, part: &Vec<usize>, total_volume: f32) -> f32 {
+    let part_volume = part.len() as f32 * grid.size * grid.size * grid.size;
+    match part_hull(grid, part, None) {
+        Some(hull) => (hull.volume() - part_volume).max(0.0) / total_volume,
+        None => 0.0,
+    }
+}
+
+/// [`concavity`] estimated from the bounding box of the voxels instead of their hull.
+/// It is never lower than the real one, and cheap enough to try every cutting plane with.
+fn estimated_concavity(grid: &VoxelGrid, part: &Vec<usize>, total_volume: f32) -> f32 {
+    let mut min = [usize::MAX; 3];
+    let mut max = [0usize; 3];
+    for index in part {
+        let coords = grid.coords(*index);
+        for axis in 0..3 {
+            min[axis] = min[axis].min(coords[axis]);
+            max[axis] = max[axis].max(coords[axis]);
+        }
+    }
+    let boxed = (0..3).map(|axis| (max[axis] + 1).saturating_sub(min[axis])).product::<usize>();
+    boxed.saturating_sub(part.len()) as f32 * grid.size * grid.size * grid.size / total_volume
+}
+
+/// # split
+/// tries axis aligned cutting planes through the part and keeps the one where
+/// both halves together have the lowest [`estimated_concavity`]. Only the halves
+/// of that cut get their real [`concavity`], from a hull.
+fn split(grid: &VoxelGrid, part: &Vec<usize>, total_volume: f32, downsampling: usize) -> Option<(Vec<usize>, Vec<usize>, f32, f32)> {
+    let mut min = [usize::MAX; 3];
+    let mut max = [0usize; 3];
+    for index in part {
+        let coords = grid.coords(*index);
+        for axis in 0..3 {
+            min[axis] = min[axis].min(coords[axis]);
+            max[axis] = max[axis].max(coords[axis]);
+        }
+    }
+
+    let mut best: Option<(Vec<usize>, Vec<usize>)> = None;
+    let mut best_cost = f32::MAX;
+    for axis in 0..3 {
+        let mut plane = min[axis] + 1;
+        while plane <= max[axis] {
+            let (left, right): (Vec<usize>, Vec<usize>) = part.iter().copied().partition(|index| grid.coords(*index)[axis] < plane);
+            if !left.is_empty() && !right.is_empty() {
+                let left_concavity = estimated_concavity(grid, &left, total_volume);
+                let right_concavity = estimated_concavity(grid, &right, total_volume);
+                // slightly prefer cuts that produce parts of similar size
+                let balance = (left.len() as f32 - right.len() as f32).abs() / part.len() as f32;
+                let cost = left_concavity + right_concavity + balance * 0.01;
+                if cost < best_cost {
+                    best_cost = cost;
+                    best = Some((left, right));
+                }
+            }
+            plane += downsampling.max(1);
+        }
+    }
+    let (left, right) = best?;
+    let left_concavity = concavity(grid, &left, total_volume);
+    let right_concavity = concavity(grid, &right, total_volume);
+    Some((left, right, left_concavity, right_concavity))
+}
+
+/// # decompose
+/// approximate convex decomposition of a triangle mesh, in the spirit of V-HACD.
+/// The mesh is voxelized and then recursively cut with axis aligned planes,
+/// always splitting the most concave part, until every part is close enough
+/// to convex or `max_hulls` is reached. Each part then gets its own convex hull.
+///
+/// Since the hulls are built from voxels they can stick out of the mesh by up to one voxel.
+pub fn decompose(vertices: &Vec<FVec3>, indices: &Vec<u32>, parameters: &DecompositionParameters) -> Vec<ConvexHull<FVec3>> {
+    if vertices.is_empty() || indices.len() < 3 {
+        return vec![];
+    }
+    let Some((grid, solid)) = voxelize(vertices, indices, parameters.resolution) else {
+        return vec![];
+    };
+    let total_volume = (solid.len() as f32 * grid.size * grid.size * grid.size).max(f32::EPSILON);
+
+    // (voxels, concavity, can still be split)
+    let first_concavity = concavity(&grid, &solid, total_volume);
+    let mut parts = vec![(solid, first_concavity, true)];
+    while parts.len() < parameters.max_hulls.max(1) {
+        let mut worst = None;
+        let mut max_concavity = parameters.max_concavity;
+        for (i, (_, concavity, splittable)) in parts.iter().enumerate() {
+            if *splittable && *concavity > max_concavity {
+                max_concavity = *concavity;
+                worst = Some(i);
+            }
+        }
+        let Some(worst) = worst else {
+            break;
+        };
+        match split(&grid, &parts[worst].0, total_volume, parameters.plane_downsampling) {
+            Some((left, right, left_concavity, right_concavity)) => {
+                parts.swap_remove(worst);
+                parts.push((left, left_concavity, true));
+                parts.push((right, right_concavity, true));
+            }
+            None => parts[worst].2 = false,
+        }
+    }
+
+    parts.iter().filter_map(|(part, _, _)| {
+        part_hull(&grid, part, Some(parameters.max_vertices_per_hull))
+    }).collect()
+}
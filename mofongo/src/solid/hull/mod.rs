@@ -0,0 +1,216 @@
+use std::collections::HashMap;
+
+use drowsed_math::{FVec3, FVec4, FMat4, Vector, EuclideanGeometry};
+
+pub mod quickhull;
+pub mod decomposition;
+
+/// # HullFace
+/// a single polygonal face of a [`ConvexHull`]. The indices wind counter clockwise
+/// when looking at the face from outside of the hull so the normal always points out.
+#[derive(Debug, Clone)]
+pub struct HullFace<V: Vector> {
+    pub indices: Vec<u32>,
+    pub normal: V,
+    /// face on the other side of the edge going from `indices[i]` to `indices[i + 1]`,
+    /// `usize::MAX` if the hull isn't closed along that edge.
+    pub neighbours: Vec<usize>,
+}
+
+/// # ConvexHull
+/// Convex polyhedron stored as vertices, polygonal faces and the unique
+/// edges between those faces. This is what the SAT collider works with since
+/// it needs actual faces and edges to test against instead of just a point cloud.
+#[derive(Debug, Clone)]
+pub struct ConvexHull<V: Vector> {
+    pub vertices: Vec<V>,
+    pub faces: Vec<HullFace<V>>,
+    /// every edge is only stored once, with the lower vertex index first.
+    pub edges: Vec<(u32, u32)>,
+    /// the faces on either side of every edge in `edges`, the one the edge runs
+    /// forwards in first. `usize::MAX` where the hull isn't closed.
+    pub edge_faces: Vec<(usize, usize)>,
+}
+
+impl ConvexHull<FVec3> {
+    /// builds a hull from its vertices and a list of polygons indexing into them.
+    /// normals are calculated here and flipped (along with the winding) if they
+    /// end up pointing into the hull, so the winding of `faces` does not matter.
+    pub fn new(vertices: Vec<FVec3>, faces: Vec<Vec<u32>>) -> Self {
+        let centroid = centroid(&vertices);
+        let mut hull_faces = Vec::with_capacity(faces.len());
+        let mut edges = Vec::<(u32, u32)>::new();
+
+        for mut indices in faces {
+            let mut normal = newell_normal(&vertices, &indices);
+            let center = centroid_indexed(&vertices, &indices);
+            if normal.dot(&(center - centroid)) < 0.0 {
+                normal = -normal;
+                indices.reverse();
+            }
+            for i in 0..indices.len() {
+                let a = indices[i];
+                let b = indices[(i + 1) % indices.len()];
+                let edge = if a < b { (a, b) } else { (b, a) };
+                if !edges.contains(&edge) {
+                    edges.push(edge);
+                }
+            }
+            hull_faces.push(HullFace { indices, normal, neighbours: vec![] });
+        }
+
+        let mut edge_owner = HashMap::<(u32, u32), usize>::new();
+        for (i, face) in hull_faces.iter().enumerate() {
+            for j in 0..face.indices.len() {
+                edge_owner.insert((face.indices[j], face.indices[(j + 1) % face.indices.len()]), i);
+            }
+        }
+        for face in &mut hull_faces {
+            face.neighbours = (0..face.indices.len()).map(|j| {
+                let reverse = (face.indices[(j + 1) % face.indices.len()], face.indices[j]);
+                edge_owner.get(&reverse).copied().unwrap_or(usize::MAX)
+            }).collect();
+        }
+        let edge_faces = edges.iter().map(|(a, b)| {
+            let forwards = edge_owner.get(&(*a, *b)).copied().unwrap_or(usize::MAX);
+            let backwards = edge_owner.get(&(*b, *a)).copied().unwrap_or(usize::MAX);
+            (forwards, backwards)
+        }).collect();
+        Self { vertices, faces: hull_faces, edges, edge_faces }
+    }
+    /// axis aligned box centered on the origin.
+    pub fn cuboid(half_extents: FVec3) -> Self {
+        let (x, y, z) = (half_extents.x, half_extents.y, half_extents.z);
+        let vertices = vec![
+            FVec3::new(-x, -y, -z),
+            FVec3::new( x, -y, -z),
+            FVec3::new( x,  y, -z),
+            FVec3::new(-x,  y, -z),
+            FVec3::new(-x, -y,  z),
+            FVec3::new( x, -y,  z),
+            FVec3::new( x,  y,  z),
+            FVec3::new(-x,  y,  z),
+        ];
+        let faces = vec![
+            vec![0, 3, 2, 1],
+            vec![4, 5, 6, 7],
+            vec![0, 1, 5, 4],
+            vec![2, 3, 7, 6],
+            vec![0, 4, 7, 3],
+            vec![1, 2, 6, 5],
+        ];
+        Self::new(vertices, faces)
+    }
+    pub fn centroid(&self) -> FVec3 {
+        centroid(&self.vertices)
+    }
+    pub fn volume(&self) -> f32 {
+        let centroid = self.centroid();
+        let mut volume = 0.0;
+        for face in &self.faces {
+            let first = self.vertices[face.indices[0] as usize] - centroid;
+            for i in 1..face.indices.len().saturating_sub(1) {
+                let b = self.vertices[face.indices[i] as usize] - centroid;
+                let c = self.vertices[face.indices[i + 1] as usize] - centroid;
+                volume += first.dot(&b.cross(c)).abs() / 6.0;
+            }
+        }
+        volume
+    }
+    /// returns the vertex furthest along `direction`.
+    pub fn support(&self, direction: FVec3) -> FVec3 {
+        let mut max_distance = f32::MIN;
+        let mut max = FVec3::from(0.0);
+        for vertex in &self.vertices {
+            let distance = vertex.dot(&direction);
+            if distance > max_distance {
+                max_distance = distance;
+                max = *vertex;
+            }
+        }
+        max
+    }
+    /// minimum and maximum of the hull projected onto `axis`.
+    pub fn project(&self, axis: FVec3) -> (f32, f32) {
+        let mut min = f32::MAX;
+        let mut max = f32::MIN;
+        for vertex in &self.vertices {
+            let distance = vertex.dot(&axis);
+            min = min.min(distance);
+            max = max.max(distance);
+        }
+        (min, max)
+    }
+    pub fn face_vertices(&self, face: usize) -> Vec<FVec3> {
+        self.faces[face].indices.iter().map(|i| self.vertices[*i as usize]).collect()
+    }
+    /// moves the hull into the space described by `transform`, keeping its faces
+    /// and edges. Normals are recalculated from the transformed vertices so non
+    /// uniform scales stay correct.
+    pub fn transformed(&self, transform: &FMat4) -> Self {
+        let vertices: Vec<FVec3> = self.vertices.iter().map(|v| transform_point(*v, transform)).collect();
+        let origin = transform_point(FVec3::from(0.0), transform);
+        let x = transform_point(FVec3::new(1.0, 0.0, 0.0), transform) - origin;
+        let y = transform_point(FVec3::new(0.0, 1.0, 0.0), transform) - origin;
+        let z = transform_point(FVec3::new(0.0, 0.0, 1.0), transform) - origin;
+        // a mirroring transform turns every face inside out
+        let mirrored = x.dot(&y.cross(z)) < 0.0;
+        let faces = self.faces.iter().map(|face| {
+            let mut indices = face.indices.clone();
+            let mut neighbours = face.neighbours.clone();
+            if mirrored {
+                let count = indices.len();
+                indices.reverse();
+                // the edge from `indices[i]` now is the one that ran into it
+                neighbours = (0..count).map(|i| face.neighbours[(2 * count - 2 - i) % count]).collect();
+            }
+            let normal = newell_normal(&vertices, &indices);
+            HullFace { indices, normal, neighbours }
+        }).collect();
+        let edge_faces = if mirrored {
+            self.edge_faces.iter().map(|(forwards, backwards)| (*backwards, *forwards)).collect()
+        } else {
+            self.edge_faces.clone()
+        };
+        Self { vertices, faces, edges: self.edges.clone(), edge_faces }
+    }
+}
+
+pub(crate) fn transform_point(point: FVec3, transform: &FMat4) -> FVec3 {
+    FVec3::from(FVec4::new(point.x, point.y, point.z, 1.0) * *transform)
+}
+
+fn centroid(vertices: &Vec<FVec3>) -> FVec3 {
+    let mut sum = FVec3::from(0.0);
+    for vertex in vertices {
+        sum += *vertex;
+    }
+    sum / (vertices.len().max(1) as f32)
+}
+
+fn centroid_indexed(vertices: &Vec<FVec3>, indices: &Vec<u32>) -> FVec3 {
+    let mut sum = FVec3::from(0.0);
+    for index in indices {
+        sum += vertices[*index as usize];
+    }
+    sum / (indices.len().max(1) as f32)
+}
+
+/// # newell_normal
+/// normal of a polygon using Newell's method, which still gives a usable
+/// result when the polygon isn't perfectly planar.
+fn newell_normal(vertices: &Vec<FVec3>, indices: &Vec<u32>) -> FVec3 {
+    let mut normal = FVec3::from(0.0);
+    for i in 0..indices.len() {
+        let current = vertices[indices[i] as usize];
+        let next = vertices[indices[(i + 1) % indices.len()] as usize];
+        normal.x += (current.y - next.y) * (current.z + next.z);
+        normal.y += (current.z - next.z) * (current.x + next.x);
+        normal.z += (current.x - next.x) * (current.y + next.y);
+    }
+    if normal.length() == 0.0 {
+        normal
+    } else {
+        normal.normalize()
+    }
+}
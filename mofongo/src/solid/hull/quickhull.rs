@@ -0,0 +1,422 @@
+use std::collections::{HashMap, HashSet};
+
+use drowsed_math::{FVec3, Vector, EuclideanGeometry};
+
+use crate::error::MofongoError;
+
+use super::ConvexHull;
+
+/// two triangles get merged into one polygon if their normals are this close.
+const COPLANAR_TOLERANCE: f32 = 0.001;
+
+struct QuickFace {
+    vertices: [usize; 3],
+    normal: FVec3,
+    offset: f32,
+    /// points that are in front of this face and still need to be processed
+    outside: Vec<usize>,
+    alive: bool,
+}
+
+impl QuickFace {
+    fn new(points: &Vec<FVec3>, a: usize, b: usize, c: usize, interior: FVec3) -> Self {
+        let mut vertices = [a, b, c];
+        let mut normal = (points[b] - points[a]).cross(points[c] - points[a]);
+        if normal.length() > 0.0 {
+            normal = normal.normalize();
+        }
+        let mut offset = normal.dot(&points[a]);
+        if normal.dot(&interior) - offset > 0.0 {
+            normal = -normal;
+            offset = -offset;
+            vertices = [a, c, b];
+        }
+        Self { vertices, normal, offset, outside: vec![], alive: true }
+    }
+    fn distance(&self, point: FVec3) -> f32 {
+        self.normal.dot(&point) - self.offset
+    }
+    fn edges(&self) -> [(usize, usize); 3] {
+        let [a, b, c] = self.vertices;
+        [(a, b), (b, c), (c, a)]
+    }
+}
+
+/// puts `point` in the outside set of whichever face it is furthest in front of.
+/// points that are not in front of any face are inside the hull and get dropped.
+fn assign_point(faces: &mut Vec<QuickFace>, candidates: std::ops::Range<usize>, points: &Vec<FVec3>, point: usize, epsilon: f32) {
+    let mut best = None;
+    let mut max_distance = epsilon;
+    for i in candidates {
+        if !faces[i].alive {
+            continue;
+        }
+        let distance = faces[i].distance(points[point]);
+        if distance > max_distance {
+            max_distance = distance;
+            best = Some(i);
+        }
+    }
+    if let Some(i) = best {
+        faces[i].outside.push(point);
+    }
+}
+
+fn initial_simplex(points: &Vec<FVec3>, epsilon: f32) -> Option<[usize; 4]> {
+    let mut extremes = [0usize; 6];
+    for (i, point) in points.iter().enumerate() {
+        if point.x < points[extremes[0]].x { extremes[0] = i; }
+        if point.x > points[extremes[1]].x { extremes[1] = i; }
+        if point.y < points[extremes[2]].y { extremes[2] = i; }
+        if point.y > points[extremes[3]].y { extremes[3] = i; }
+        if point.z < points[extremes[4]].z { extremes[4] = i; }
+        if point.z > points[extremes[5]].z { extremes[5] = i; }
+    }
+
+    let (mut i0, mut i1) = (0, 0);
+    let mut max_distance = 0.0;
+    for a in extremes {
+        for b in extremes {
+            let distance = (points[b] - points[a]).length();
+            if distance > max_distance {
+                max_distance = distance;
+                (i0, i1) = (a, b);
+            }
+        }
+    }
+    if max_distance <= epsilon {
+        return None;
+    }
+
+    let line = (points[i1] - points[i0]).normalize();
+    let mut i2 = 0;
+    let mut max_distance = 0.0;
+    for (i, point) in points.iter().enumerate() {
+        let distance = (*point - points[i0]).cross(line).length();
+        if distance > max_distance {
+            max_distance = distance;
+            i2 = i;
+        }
+    }
+    if max_distance <= epsilon {
+        return None;
+    }
+
+    let normal = (points[i1] - points[i0]).cross(points[i2] - points[i0]).normalize();
+    let mut i3 = 0;
+    let mut max_distance = 0.0;
+    for (i, point) in points.iter().enumerate() {
+        let distance = normal.dot(&(*point - points[i0])).abs();
+        if distance > max_distance {
+            max_distance = distance;
+            i3 = i;
+        }
+    }
+    if max_distance <= epsilon {
+        return None;
+    }
+    Some([i0, i1, i2, i3])
+}
+
+/// # merge_coplanar
+/// quickhull only ever produces triangles, so a cube would come out with 12 faces
+/// and 18 edges. Neighbouring triangles that lie in the same plane are merged back
+/// into a single polygon so SAT doesn't test the same axis multiple times.
+fn merge_coplanar(triangles: &Vec<([u32; 3], FVec3)>) -> Vec<Vec<u32>> {
+    let mut edge_owner = HashMap::<(u32, u32), usize>::new();
+    for (i, (triangle, _)) in triangles.iter().enumerate() {
+        for j in 0..3 {
+            edge_owner.insert((triangle[j], triangle[(j + 1) % 3]), i);
+        }
+    }
+
+    let mut group: Vec<usize> = (0..triangles.len()).collect();
+    fn root(group: &mut Vec<usize>, mut i: usize) -> usize {
+        while group[i] != i {
+            group[i] = group[group[i]];
+            i = group[i];
+        }
+        i
+    }
+    for (i, (triangle, normal)) in triangles.iter().enumerate() {
+        for j in 0..3 {
+            let reverse = (triangle[(j + 1) % 3], triangle[j]);
+            if let Some(&other) = edge_owner.get(&reverse) {
+                if normal.dot(&triangles[other].1) > 1.0 - COPLANAR_TOLERANCE {
+                    let (a, b) = (root(&mut group, i), root(&mut group, other));
+                    group[a] = b;
+                }
+            }
+        }
+    }
+
+    let mut groups = HashMap::<usize, Vec<usize>>::new();
+    for i in 0..triangles.len() {
+        let r = root(&mut group, i);
+        groups.entry(r).or_default().push(i);
+    }
+
+    let mut polygons = Vec::<Vec<u32>>::with_capacity(groups.len());
+    for (_, members) in groups {
+        if members.len() == 1 {
+            polygons.push(triangles[members[0]].0.to_vec());
+            continue;
+        }
+        let mut edges = HashSet::<(u32, u32)>::new();
+        for member in &members {
+            let triangle = triangles[*member].0;
+            for j in 0..3 {
+                edges.insert((triangle[j], triangle[(j + 1) % 3]));
+            }
+        }
+        // the outline of the polygon is made from the edges that aren't shared inside the group
+        let mut next = HashMap::<u32, u32>::new();
+        for (a, b) in &edges {
+            if !edges.contains(&(*b, *a)) {
+                next.insert(*a, *b);
+            }
+        }
+        let Some(start) = next.keys().next().copied() else {
+            // every edge is shared, nothing to walk, keep the triangles as they are.
+            for member in &members {
+                polygons.push(triangles[*member].0.to_vec());
+            }
+            continue;
+        };
+        let mut polygon = vec![start];
+        let mut current = next[&start];
+        while current != start && polygon.len() <= next.len() {
+            polygon.push(current);
+            current = match next.get(&current) {
+                Some(vertex) => *vertex,
+                None => break,
+            };
+        }
+        if current == start && polygon.len() == next.len() {
+            polygons.push(polygon);
+        } else {
+            // couldn't walk the outline, keep the triangles as they are.
+            for member in &members {
+                polygons.push(triangles[*member].0.to_vec());
+            }
+        }
+    }
+    polygons
+}
+
+/// # quickhull
+/// builds the convex hull of an arbitrary point cloud. Duplicate and interior points
+/// are discarded so the resulting hull only holds the vertices it actually needs.
+///
+/// `max_vertices` simplifies the hull: points are added furthest first until the
+/// budget is reached, then the hull is scaled up around its center until the points
+/// it left out are inside again. A simplified hull is a little bigger than the points,
+/// never smaller, so nothing sinks into a collider built from it.
+/// Returns `None` if the points are flat (all on a line or plane) or there are less than 4.
+pub fn quickhull(points: &Vec<FVec3>, max_vertices: Option<usize>) -> Option<ConvexHull<FVec3>> {
+    if points.len() < 4 {
+        return None;
+    }
+    let mut min = points[0];
+    let mut max = points[0];
+    for point in points {
+        min.x = min.x.min(point.x); min.y = min.y.min(point.y); min.z = min.z.min(point.z);
+        max.x = max.x.max(point.x); max.y = max.y.max(point.y); max.z = max.z.max(point.z);
+    }
+    let extent = max - min;
+    let epsilon = extent.x.max(extent.y).max(extent.z) * 0.00001;
+
+    let simplex = initial_simplex(points, epsilon)?;
+    let interior = (points[simplex[0]] + points[simplex[1]] + points[simplex[2]] + points[simplex[3]]) / 4.0;
+    let [i0, i1, i2, i3] = simplex;
+    let mut faces = vec![
+        QuickFace::new(points, i0, i1, i2, interior),
+        QuickFace::new(points, i0, i3, i1, interior),
+        QuickFace::new(points, i0, i2, i3, interior),
+        QuickFace::new(points, i1, i3, i2, interior),
+    ];
+    for i in 0..points.len() {
+        if !simplex.contains(&i) {
+            assign_point(&mut faces, 0..4, points, i, epsilon);
+        }
+    }
+
+    let mut vertex_count = 4;
+    loop {
+        if let Some(max_vertices) = max_vertices {
+            if vertex_count >= max_vertices {
+                break;
+            }
+        }
+        // the furthest point out of all faces is always the next one to add
+        let mut eye = None;
+        let mut max_distance = epsilon;
+        for face in faces.iter().filter(|face| face.alive) {
+            for point in &face.outside {
+                let distance = face.distance(points[*point]);
+                if distance > max_distance {
+                    max_distance = distance;
+                    eye = Some(*point);
+                }
+            }
+        }
+        let Some(eye) = eye else {
+            break;
+        };
+
+        let visible: Vec<usize> = (0..faces.len()).filter(|i| {
+            faces[*i].alive && faces[*i].distance(points[eye]) > epsilon
+        }).collect();
+
+        let mut visible_edges = HashSet::<(usize, usize)>::new();
+        for i in &visible {
+            for edge in faces[*i].edges() {
+                visible_edges.insert(edge);
+            }
+        }
+        let mut orphans = Vec::<usize>::new();
+        let mut horizon = Vec::<(usize, usize)>::new();
+        for i in &visible {
+            for (a, b) in faces[*i].edges() {
+                if !visible_edges.contains(&(b, a)) {
+                    horizon.push((a, b));
+                }
+            }
+            faces[*i].alive = false;
+            orphans.append(&mut faces[*i].outside);
+        }
+
+        let start = faces.len();
+        for (a, b) in horizon {
+            faces.push(QuickFace::new(points, a, b, eye, interior));
+        }
+        for point in orphans {
+            if point != eye {
+                assign_point(&mut faces, start..faces.len(), points, point, epsilon);
+            }
+        }
+        vertex_count += 1;
+    }
+
+    // points still outside when the budget ran out, the hull has to grow over them
+    let mut scale = 1.0f32;
+    for face in faces.iter().filter(|face| face.alive) {
+        let depth = -face.distance(interior);
+        if depth <= 0.0 {
+            continue;
+        }
+        for outside in faces.iter().filter(|face| face.alive) {
+            for point in &outside.outside {
+                scale = scale.max(1.0 + face.distance(points[*point]) / depth);
+            }
+        }
+    }
+
+    let mut remap = HashMap::<usize, u32>::new();
+    let mut vertices = Vec::<FVec3>::new();
+    let mut triangles = Vec::<([u32; 3], FVec3)>::new();
+    for face in faces.iter().filter(|face| face.alive) {
+        let mut triangle = [0u32; 3];
+        for (j, vertex) in face.vertices.iter().enumerate() {
+            triangle[j] = *remap.entry(*vertex).or_insert_with(|| {
+                vertices.push(interior + (points[*vertex] - interior) * scale);
+                (vertices.len() - 1) as u32
+            });
+        }
+        triangles.push((triangle, face.normal));
+    }
+    Some(ConvexHull::new(vertices, merge_coplanar(&triangles)))
+}
+
+impl ConvexHull<FVec3> {
+    /// # from_points
+    /// minimal convex hull containing every point, see [`quickhull`].
+    pub fn from_points(points: &Vec<FVec3>) -> Option<Self> {
+        quickhull(points, None)
+    }
+    /// # from_points_simplified
+    /// same as [`ConvexHull::from_points`] but the hull never has more than
+    /// `max_vertices` vertices (at least 4 are always used).
+    pub fn from_points_simplified(points: &Vec<FVec3>, max_vertices: usize) -> Option<Self> {
+        quickhull(points, Some(max_vertices))
+    }
+    /// # try_from_points
+    /// [`ConvexHull::from_points`], or [`ConvexHull::from_points_simplified`] with
+    /// `max_vertices`, telling why when there is no hull.
+    pub fn try_from_points(points: &Vec<FVec3>, max_vertices: Option<usize>) -> Result<Self, MofongoError> {
+        if points.len() < 4 {
+            return Err(MofongoError::TooFewPoints(points.len()));
+        }
+        quickhull(points, max_vertices).ok_or(MofongoError::Flat)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cube_corners() -> Vec<FVec3> {
+        let mut corners = vec![];
+        for x in [-1.0, 1.0] {
+            for y in [-1.0, 1.0] {
+                for z in [-1.0, 1.0] {
+                    corners.push(FVec3::new(x, y, z));
+                }
+            }
+        }
+        corners
+    }
+
+    #[test]
+    fn hull_of_a_cube_is_the_cube() {
+        let mut points = cube_corners();
+        // the center, a point on a face and a duplicate corner don't belong to the hull
+        points.push(FVec3::new(0.0, 0.0, 0.0));
+        points.push(FVec3::new(1.0, 0.2, -0.3));
+        points.push(FVec3::new(1.0, 1.0, 1.0));
+        let hull = quickhull(&points, None).unwrap();
+        assert_eq!(hull.vertices.len(), 8);
+        assert_eq!(hull.faces.len(), 6);
+        assert_eq!(hull.edges.len(), 12);
+        assert!(hull.faces.iter().all(|face| face.indices.len() == 4));
+        assert!((hull.volume() - 8.0).abs() < 1e-4);
+        for face in &hull.faces {
+            let outwards = (hull.vertices[face.indices[0] as usize] - hull.centroid()).dot(&face.normal);
+            assert!(outwards > 0.0);
+        }
+    }
+
+    #[test]
+    fn simplified_hull_keeps_to_its_budget() {
+        let hull = quickhull(&cube_corners(), Some(5)).unwrap();
+        assert_eq!(hull.vertices.len(), 5);
+    }
+
+    #[test]
+    fn simplified_hull_contains_every_point() {
+        let mut points = cube_corners();
+        points.push(FVec3::new(0.0, 0.0, 1.5));
+        points.push(FVec3::new(0.3, -1.4, 0.2));
+        let hull = quickhull(&points, Some(6)).unwrap();
+        assert_eq!(hull.vertices.len(), 6);
+        for point in &points {
+            for face in &hull.faces {
+                let distance = face.normal.dot(&(*point - hull.vertices[face.indices[0] as usize]));
+                assert!(distance < 1e-4, "{distance} in front of a face");
+            }
+        }
+    }
+
+    #[test]
+    fn degenerate_points_have_no_hull() {
+        let flat: Vec<FVec3> = cube_corners().into_iter().filter(|point| point.z > 0.0).collect();
+        assert!(quickhull(&flat, None).is_none());
+        assert_eq!(ConvexHull::try_from_points(&flat, None).unwrap_err(), MofongoError::Flat);
+
+        let line: Vec<FVec3> = (0..5).map(|i| FVec3::new(i as f32, 0.0, 0.0)).collect();
+        assert_eq!(ConvexHull::try_from_points(&line, None).unwrap_err(), MofongoError::Flat);
+
+        let triangle = cube_corners()[..3].to_vec();
+        assert_eq!(ConvexHull::try_from_points(&triangle, None).unwrap_err(), MofongoError::TooFewPoints(3));
+    }
+}
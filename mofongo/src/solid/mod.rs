@@ -1,2 +1,4 @@
 pub mod collisions;
-pub mod physics;
\ No newline at end of file
+pub mod physics;
+pub mod hull;
+pub mod transform;
\ No newline at end of file
@@ -0,0 +1,173 @@
+use drowsed_math::{FVec3, Vector, EuclideanGeometry};
+
+use crate::bodies::RigidBody;
+use crate::solid::collisions::sat::clip_polygon;
+use crate::solid::hull::ConvexHull;
+
+/// # FluidBounds
+/// region of space filled by a fluid.
+#[derive(Debug, Clone, Copy)]
+pub enum FluidBounds {
+    /// everything below a surface plane, like an ocean. `normal` is the
+    /// normal of the surface pointing out of the fluid (usually up).
+    Plane { normal: FVec3, height: f32 },
+    /// an axis aligned box, like a pool.
+    Aabb { min: FVec3, max: FVec3 },
+}
+
+impl FluidBounds {
+    /// the planes bounding the fluid, inside is where `normal.dot(point) <= offset`.
+    fn planes(&self) -> Vec<(FVec3, f32)> {
+        match *self {
+            FluidBounds::Plane { normal, height } => vec![(normal, height)],
+            FluidBounds::Aabb { min, max } => vec![
+                (FVec3::new( 1.0, 0.0, 0.0),  max.x),
+                (FVec3::new(-1.0, 0.0, 0.0), -min.x),
+                (FVec3::new(0.0,  1.0, 0.0),  max.y),
+                (FVec3::new(0.0, -1.0, 0.0), -min.y),
+                (FVec3::new(0.0, 0.0,  1.0),  max.z),
+                (FVec3::new(0.0, 0.0, -1.0), -min.z),
+            ],
+        }
+    }
+}
+
+/// # FluidVolume
+/// applies buoyancy and drag to bodies inside of it. The buoyant force pushes
+/// against gravity with the weight of the displaced fluid and acts on the center
+/// of the submerged part of the body, so bodies that are only partly submerged
+/// tilt until they float upright.
+#[derive(Debug, Clone, Copy)]
+pub struct FluidVolume {
+    pub bounds: FluidBounds,
+    pub density: f32,
+    /// drag against linear velocity, scaled by how much of the body is submerged.
+    pub linear_drag: f32,
+    /// drag against angular velocity, scaled by how much of the body is submerged.
+    pub angular_drag: f32,
+}
+
+/// polygons that have been cut by a plane leave a hole, this builds the polygon
+/// closing it up from the points that ended up within `tolerance` of the plane.
+fn cap_polygon(polygons: &Vec<Vec<FVec3>>, normal: FVec3, offset: f32, tolerance: f32) -> Option<Vec<FVec3>> {
+    let mut points = Vec::<FVec3>::new();
+    for polygon in polygons {
+        for point in polygon {
+            if (normal.dot(point) - offset).abs() < tolerance && !points.iter().any(|p| (*p - *point).length() < tolerance) {
+                points.push(*point);
+            }
+        }
+    }
+    if points.len() < 3 {
+        return None;
+    }
+    let mut center = FVec3::from(0.0);
+    for point in &points {
+        center += *point;
+    }
+    center = center / points.len() as f32;
+
+    let reference = if normal.x.abs() < 0.9 { FVec3::new(1.0, 0.0, 0.0) } else { FVec3::new(0.0, 1.0, 0.0) };
+    let u = normal.cross(reference).normalize();
+    let v = normal.cross(u);
+    points.sort_by(|a, b| {
+        let angle_a = (*a - center).dot(&v).atan2((*a - center).dot(&u));
+        let angle_b = (*b - center).dot(&v).atan2((*b - center).dot(&u));
+        angle_a.total_cmp(&angle_b)
+    });
+    Some(points)
+}
+
+/// volume and centroid of a closed convex polyhedron given as polygons.
+fn volume_centroid(polygons: &Vec<Vec<FVec3>>) -> (f32, FVec3) {
+    let mut reference = FVec3::from(0.0);
+    let mut count = 0;
+    for polygon in polygons {
+        for point in polygon {
+            reference += *point;
+            count += 1;
+        }
+    }
+    if count == 0 {
+        return (0.0, reference);
+    }
+    reference = reference / count as f32;
+
+    let mut volume = 0.0;
+    let mut centroid = FVec3::from(0.0);
+    for polygon in polygons {
+        for i in 1..polygon.len().saturating_sub(1) {
+            let (a, b, c) = (polygon[0], polygon[i], polygon[i + 1]);
+            let tetrahedron = (a - reference).dot(&(b - reference).cross(c - reference)).abs() / 6.0;
+            volume += tetrahedron;
+            centroid += (reference + a + b + c) * (tetrahedron / 4.0);
+        }
+    }
+    if volume > 0.0 {
+        centroid = centroid / volume;
+    }
+    (volume, centroid)
+}
+
+impl FluidVolume {
+    pub fn new(bounds: FluidBounds, density: f32) -> Self {
+        Self { bounds, density, linear_drag: 0.5, angular_drag: 0.5 }
+    }
+    /// # submerged
+    /// volume and centroid of the part of `hull` (in world space) inside the fluid.
+    /// Returns `None` if the hull isn't touching the fluid at all.
+    pub fn submerged(&self, hull: &ConvexHull<FVec3>) -> Option<(f32, FVec3)> {
+        let mut polygons: Vec<Vec<FVec3>> = (0..hull.faces.len()).map(|face| hull.face_vertices(face)).collect();
+        let Some(first) = hull.vertices.first() else {
+            return None;
+        };
+        let (mut min, mut max) = (*first, *first);
+        for vertex in &hull.vertices {
+            min.x = min.x.min(vertex.x); min.y = min.y.min(vertex.y); min.z = min.z.min(vertex.z);
+            max.x = max.x.max(vertex.x); max.y = max.y.max(vertex.y); max.z = max.z.max(vertex.z);
+        }
+        // relative to the size of the hull so tiny and huge bodies both get a cap
+        let extent = max - min;
+        let tolerance = extent.x.max(extent.y).max(extent.z) * 0.00001;
+        for (normal, offset) in self.bounds.planes() {
+            polygons = polygons.iter()
+                .map(|polygon| clip_polygon(polygon, normal, offset))
+                .filter(|polygon| polygon.len() >= 3)
+                .collect();
+            if polygons.is_empty() {
+                return None;
+            }
+            if let Some(cap) = cap_polygon(&polygons, normal, offset, tolerance) {
+                polygons.push(cap);
+            }
+        }
+        let (volume, centroid) = volume_centroid(&polygons);
+        if volume > 0.0 {
+            Some((volume, centroid))
+        } else {
+            None
+        }
+    }
+    /// # apply
+    /// applies buoyancy and drag to `body`. `hull` is the shape of the body in world
+    /// space and `center` is the world position forces are relative to (the translation
+    /// of the body). Returns whether the body is in the fluid.
+    pub fn apply<R: RigidBody<SpatialVector = FVec3>>(&self, body: &mut R, hull: &ConvexHull<FVec3>, center: FVec3, gravity: FVec3) -> bool {
+        let Some((volume, centroid)) = self.submerged(hull) else {
+            return false;
+        };
+        let total_volume = hull.volume();
+        let fraction = if total_volume > 0.0 { (volume / total_volume).min(1.0) } else { 1.0 };
+        let offset = centroid - center;
+
+        let buoyancy = gravity * (-self.density * volume);
+        body.apply_force(buoyancy, offset);
+
+        // through the center of mass, drag on its own shouldn't turn the body
+        let drag = body.velocity() * (-self.linear_drag * fraction);
+        body.apply_central_force(drag);
+        let angular_drag = body.angular_velocity() * (-self.angular_drag * fraction);
+        body.apply_torque(angular_drag);
+        true
+    }
+}
@@ -0,0 +1,152 @@
+use drowsed_math::{FVec3, FMat3, Vector, EuclideanGeometry};
+
+use crate::solid::hull::ConvexHull;
+
+/// # MassProperties
+/// mass, center of mass and inertia tensor of a body. The inertia tensor is
+/// stored as rows and is taken around the center of mass, which itself is
+/// relative to the origin of the object.
+#[derive(Debug, Clone, Copy)]
+pub struct MassProperties {
+    pub mass: f32,
+    pub center_of_mass: FVec3,
+    pub inertia: [[f32; 3]; 3],
+}
+
+impl MassProperties {
+    /// solid box of the given size centered on `center`.
+    pub fn cuboid(mass: f32, center: FVec3, size: FVec3) -> Self {
+        let (x, y, z) = (size.x * size.x, size.y * size.y, size.z * size.z);
+        let k = mass / 12.0;
+        Self {
+            mass,
+            center_of_mass: center,
+            inertia: [
+                [k * (y + z), 0.0, 0.0],
+                [0.0, k * (x + z), 0.0],
+                [0.0, 0.0, k * (x + y)],
+            ],
+        }
+    }
+    /// # hull
+    /// solid convex hull of uniform density. Every face is fanned into triangles,
+    /// which together with a point inside make tetrahedra whose volume, centroid
+    /// and covariance are summed up (the divergence theorem, one face at a time).
+    pub fn hull(mass: f32, hull: &ConvexHull<FVec3>) -> Self {
+        let reference = hull.centroid();
+        let mut volume = 0.0;
+        let mut moment = FVec3::from(0.0);
+        // integral of x * x^T over the hull, relative to `reference`
+        let mut covariance = [[0.0f32; 3]; 3];
+        for face in &hull.faces {
+            let a = hull.vertices[face.indices[0] as usize] - reference;
+            for i in 1..face.indices.len().saturating_sub(1) {
+                let b = hull.vertices[face.indices[i] as usize] - reference;
+                let c = hull.vertices[face.indices[i + 1] as usize] - reference;
+                let tetrahedron = a.dot(&b.cross(c)) / 6.0;
+                volume += tetrahedron;
+                moment += (a + b + c) * (tetrahedron / 4.0);
+                let (a, b, c) = ([a.x, a.y, a.z], [b.x, b.y, b.z], [c.x, c.y, c.z]);
+                for j in 0..3 {
+                    for k in 0..3 {
+                        let sum = (a[j] + b[j] + c[j]) * (a[k] + b[k] + c[k]);
+                        covariance[j][k] += tetrahedron / 20.0 * (a[j] * a[k] + b[j] * b[k] + c[j] * c[k] + sum);
+                    }
+                }
+            }
+        }
+        if volume.abs() <= f32::EPSILON {
+            return Self::cuboid(mass, reference, FVec3::from(0.0));
+        }
+        let offset = moment / volume;
+        let density = mass / volume;
+        // move the covariance to the center of mass, then I = tr(C) * E - C
+        let d = [offset.x, offset.y, offset.z];
+        for j in 0..3 {
+            for k in 0..3 {
+                covariance[j][k] -= volume * d[j] * d[k];
+            }
+        }
+        let trace = covariance[0][0] + covariance[1][1] + covariance[2][2];
+        let mut inertia = [[0.0; 3]; 3];
+        for j in 0..3 {
+            for k in 0..3 {
+                let identity = if j == k { trace } else { 0.0 };
+                inertia[j][k] = density * (identity - covariance[j][k]);
+            }
+        }
+        Self { mass, center_of_mass: reference + offset, inertia }
+    }
+    /// rotates the inertia tensor, `axes` are where the x, y and z axis end up.
+    pub fn rotated(&self, axes: [FVec3; 3]) -> Self {
+        let rotation = [
+            [axes[0].x, axes[1].x, axes[2].x],
+            [axes[0].y, axes[1].y, axes[2].y],
+            [axes[0].z, axes[1].z, axes[2].z],
+        ];
+        // R * I * R^T
+        let mut inertia = [[0.0; 3]; 3];
+        for i in 0..3 {
+            for j in 0..3 {
+                for k in 0..3 {
+                    for l in 0..3 {
+                        inertia[i][j] += rotation[i][k] * self.inertia[k][l] * rotation[j][l];
+                    }
+                }
+            }
+        }
+        Self { inertia, ..*self }
+    }
+    /// # combine
+    /// adds several bodies together, moving every inertia tensor to the
+    /// shared center of mass with the parallel axis theorem.
+    pub fn combine(parts: &Vec<MassProperties>) -> Self {
+        let mut mass = 0.0;
+        let mut center_of_mass = FVec3::from(0.0);
+        for part in parts {
+            mass += part.mass;
+            center_of_mass += part.center_of_mass * part.mass;
+        }
+        if mass > 0.0 {
+            center_of_mass = center_of_mass / mass;
+        }
+        let mut inertia = [[0.0; 3]; 3];
+        for part in parts {
+            let d = part.center_of_mass - center_of_mass;
+            let d = [d.x, d.y, d.z];
+            let length = d[0] * d[0] + d[1] * d[1] + d[2] * d[2];
+            for i in 0..3 {
+                for j in 0..3 {
+                    let identity = if i == j { 1.0 } else { 0.0 };
+                    inertia[i][j] += part.inertia[i][j] + part.mass * (length * identity - d[i] * d[j]);
+                }
+            }
+        }
+        Self { mass, center_of_mass, inertia }
+    }
+    /// # inverse_inertia
+    /// [`super::rigidbody::RigidBody3D`] multiplies torque by its `inertia` field,
+    /// so that field is really the inverse of the inertia tensor.
+    pub fn inverse_inertia(&self) -> FMat3 {
+        let m = self.inertia;
+        let determinant = m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0]);
+        if determinant.abs() <= f32::EPSILON {
+            return FMat3::new(0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0);
+        }
+        let d = 1.0 / determinant;
+        // the tensor is symmetric, so it doesn't matter whether FMat3 is row or column major
+        FMat3::new(
+            (m[1][1] * m[2][2] - m[1][2] * m[2][1]) * d,
+            (m[0][2] * m[2][1] - m[0][1] * m[2][2]) * d,
+            (m[0][1] * m[1][2] - m[0][2] * m[1][1]) * d,
+            (m[1][2] * m[2][0] - m[1][0] * m[2][2]) * d,
+            (m[0][0] * m[2][2] - m[0][2] * m[2][0]) * d,
+            (m[0][2] * m[1][0] - m[0][0] * m[1][2]) * d,
+            (m[1][0] * m[2][1] - m[1][1] * m[2][0]) * d,
+            (m[0][1] * m[2][0] - m[0][0] * m[2][1]) * d,
+            (m[0][0] * m[1][1] - m[0][1] * m[1][0]) * d,
+        )
+    }
+}
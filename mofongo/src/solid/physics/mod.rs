@@ -1,2 +1,4 @@
 pub mod rigidbody;
-pub mod rep;
\ No newline at end of file
+pub mod rep;
+pub mod mass;
+pub mod fluid;
\ No newline at end of file
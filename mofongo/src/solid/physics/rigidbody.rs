@@ -2,13 +2,21 @@ use drowsed_math::{Transform, FVec3, FMat3, TransformQuaternion3D, complex::quat
 
 use crate::bodies::RigidBody;
 
+use super::mass::MassProperties;
+
 pub struct RigidBody3D {
     pub velocity: FVec3,
     pub angular_velocity: FVec3,
     pub mass: f32,
     net_force: FVec3,
     pub net_torque: FVec3,
-    pub inertia: FMat3
+    pub inertia: FMat3,
+    /// where the mass is centered relative to the origin of the body, before it
+    /// is rotated and scaled. The body turns around this point.
+    pub center_of_mass: FVec3,
+    /// `center_of_mass` rotated and scaled by the transform of the last step,
+    /// what forces are levered against.
+    center_offset: FVec3,
 }
 
 impl RigidBody for RigidBody3D {
@@ -24,7 +32,10 @@ impl RigidBody for RigidBody3D {
     }
     fn apply_force(&mut self, force: Self::SpatialVector, pos: Self::SpatialVector) {
         self.net_force += force;
-        self.apply_torque(pos.cross(force));
+        self.apply_torque((pos - self.center_offset).cross(force));
+    }
+    fn apply_central_force(&mut self, force: Self::SpatialVector) {
+        self.net_force += force;
     }
     fn apply_torque(&mut self, torque: Self::SpatialVector) {
         self.net_torque += torque;
@@ -53,8 +64,12 @@ impl RigidBody for RigidBody3D {
         self.net_force = FVec3::from(0.0);
         self.net_torque = FVec3::from(0.0);
         let mut transform = *transform;
+        let before = self.world_center(&transform);
         transform.rotation = transform.rotation * rotation;
-        transform.translate(&(self.velocity * deltatime))
+        let after = self.world_center(&transform);
+        self.center_offset = after;
+        // the velocity is the one of the center of mass, which the rotation mustn't move
+        transform.translate(&(self.velocity * deltatime + before - after))
         // transform
     }
 }
@@ -67,7 +82,28 @@ impl RigidBody3D {
             net_force: FVec3::from(0.0), 
             net_torque: FVec3::from(0.0), 
             mass, 
-            inertia: FMat3::identity() 
+            inertia: FMat3::identity(),
+            center_of_mass: FVec3::from(0.0),
+            center_offset: FVec3::from(0.0),
+        }
+    }
+    /// `center_of_mass` relative to the translation of `transform`, in world space.
+    fn world_center(&self, transform: &TransformQuaternion3D) -> FVec3 {
+        let scaled = FVec3::new(
+            self.center_of_mass.x * transform.scale.x,
+            self.center_of_mass.y * transform.scale.y,
+            self.center_of_mass.z * transform.scale.z,
+        );
+        transform.rotation * scaled
+    }
+    /// rigidbody using the mass and inertia of a shape, e.g. from
+    /// [`crate::solid::collisions::compound::CompoundCollider::mass_properties`].
+    pub fn from_mass_properties(properties: &MassProperties) -> Self {
+        Self { 
+            inertia: properties.inverse_inertia(),
+            center_of_mass: properties.center_of_mass,
+            center_offset: properties.center_of_mass,
+            ..Self::new(properties.mass)
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,42 @@
+use drowsed_math::{FVec3, TransformQuaternion3D};
+
+/// # compose
+/// puts `local` inside the space of `parent`, so the result is where
+/// something placed at `local` relative to `parent` ends up in the world.
+pub fn compose(parent: &TransformQuaternion3D, local: &TransformQuaternion3D) -> TransformQuaternion3D {
+    let scaled = FVec3::new(
+        local.translation.x * parent.scale.x,
+        local.translation.y * parent.scale.y,
+        local.translation.z * parent.scale.z
+    );
+    TransformQuaternion3D {
+        translation: parent.translation + parent.rotation * scaled,
+        rotation: parent.rotation * local.rotation,
+        scale: FVec3::new(
+            parent.scale.x * local.scale.x,
+            parent.scale.y * local.scale.y,
+            parent.scale.z * local.scale.z
+        ),
+    }
+}
+
+/// # relative
+/// the inverse of [`compose`], finds the local transform that puts something
+/// at `world` when it's inside the space of `parent`.
+pub fn relative(parent: &TransformQuaternion3D, world: &TransformQuaternion3D) -> TransformQuaternion3D {
+    let inverse = parent.rotation.conjugate();
+    let unrotated = inverse * (world.translation - parent.translation);
+    TransformQuaternion3D {
+        translation: FVec3::new(
+            unrotated.x / parent.scale.x,
+            unrotated.y / parent.scale.y,
+            unrotated.z / parent.scale.z
+        ),
+        rotation: inverse * world.rotation,
+        scale: FVec3::new(
+            world.scale.x / parent.scale.x,
+            world.scale.y / parent.scale.y,
+            world.scale.z / parent.scale.z
+        ),
+    }
+}
@@ -0,0 +1,76 @@
+use std::fmt;
+
+use ash::vk;
+
+use crate::vk_obj::pipelines::ShaderModuleError;
+
+/// # MochaError
+/// everything that can go wrong loading assets or talking to the device.
+#[derive(Debug)]
+pub enum MochaError {
+    Io(std::io::Error),
+    /// the file was read but isn't what it should be, like a broken fbx.
+    Parse(String),
+    Image(image::ImageError),
+    Vulkan(vk::Result),
+    Shader(ShaderModuleError),
+    /// no gpu has a queue that can both draw and present to the window.
+    NoSuitableDevice,
+    /// the device has no memory with the properties a buffer asked for.
+    NoMemoryType(vk::MemoryPropertyFlags),
+    /// writing to a buffer that isn't mapped to host memory.
+    Unmapped,
+    /// writing `len` elements to a buffer that only fits `capacity`.
+    BufferTooSmall { len: usize, capacity: usize },
+}
+
+impl fmt::Display for MochaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MochaError::Io(error) => write!(f, "io error: {error}"),
+            MochaError::Parse(message) => write!(f, "couldn't parse the file: {message}"),
+            MochaError::Image(error) => write!(f, "couldn't decode the image: {error}"),
+            MochaError::Vulkan(result) => write!(f, "vulkan error: {result}"),
+            MochaError::Shader(error) => write!(f, "{error}"),
+            MochaError::NoSuitableDevice => write!(f, "no gpu can draw to the window"),
+            MochaError::NoMemoryType(properties) => write!(f, "no device memory is {properties:?}"),
+            MochaError::Unmapped => write!(f, "the buffer isn't mapped"),
+            MochaError::BufferTooSmall { len, capacity } => write!(f, "{len} elements don't fit a buffer of {capacity}"),
+        }
+    }
+}
+
+impl std::error::Error for MochaError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            MochaError::Io(error) => Some(error),
+            MochaError::Image(error) => Some(error),
+            MochaError::Shader(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for MochaError {
+    fn from(error: std::io::Error) -> Self {
+        MochaError::Io(error)
+    }
+}
+
+impl From<image::ImageError> for MochaError {
+    fn from(error: image::ImageError) -> Self {
+        MochaError::Image(error)
+    }
+}
+
+impl From<vk::Result> for MochaError {
+    fn from(result: vk::Result) -> Self {
+        MochaError::Vulkan(result)
+    }
+}
+
+impl From<ShaderModuleError> for MochaError {
+    fn from(error: ShaderModuleError) -> Self {
+        MochaError::Shader(error)
+    }
+}
@@ -2,4 +2,5 @@ pub mod vk_obj;
 pub mod input;
 pub mod camera;
 pub mod model;
-pub mod debug;
\ No newline at end of file
+pub mod debug;
+pub mod error;
\ No newline at end of file
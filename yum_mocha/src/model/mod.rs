@@ -1,2 +1,3 @@
 pub mod vertex;
-pub mod model_loader;
\ No newline at end of file
+pub mod model_loader;
+pub mod skeleton;
\ No newline at end of file
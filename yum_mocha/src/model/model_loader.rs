@@ -3,9 +3,19 @@
 /// so I need to revise it a bunch.
 use std::{vec, io::Write, collections::HashMap, rc::Rc};
 
-use drowsed_math::{linear::FVec3};
+use drowsed_math::{linear::{FVec3, FVec2}};
 use drowsed_math::linear::Transform3D;
-use fbxcel_dom::{fbxcel::{tree::v7400::NodeHandle}, v7400::Document, any::AnyDocument};
+use fbxcel_dom::{fbxcel::{tree::v7400::NodeHandle, low::v7400::AttributeValue}, v7400::Document, any::AnyDocument};
+
+use crate::error::MochaError;
+
+use super::skeleton::{self, SkinData};
+
+/// the attribute at `index` of `node` read with `get`, an error when it's missing or of another type.
+fn attribute<'a, T>(node: &NodeHandle<'a>, index: usize, get: impl FnOnce(&'a AttributeValue) -> Option<T>) -> Result<T, MochaError> {
+    node.attributes().get(index).and_then(get)
+        .ok_or_else(|| MochaError::Parse(format!("attribute {index} of {} is missing or has the wrong type", node.name())))
+}
 
 pub fn print_format(node: NodeHandle, depth: i32) {
     let new_depth = depth + 1;
@@ -34,6 +44,13 @@ pub enum GeometryNormal {
     None,
     ByPolygonVertex(Vec<FVec3>),
 }
+/// texture coordinates of the first uv set, already looked up through `UVIndex`.
+#[derive(Default, Debug)]
+pub enum GeometryUV {
+    #[default]
+    None,
+    ByPolygonVertex(Vec<FVec2>),
+}
 #[derive(Default, Debug)]
 pub struct Material {
     tag: String,
@@ -46,6 +63,7 @@ pub struct Geometry {
     polygon_indices: Vec<i32>,
     edges: Vec<i32>,
     normal: GeometryNormal,
+    uv: GeometryUV,
 }
 pub struct ModelData {
     tag: String,
@@ -56,23 +74,29 @@ pub struct StandardModelData {
     pub tag: String,
     pub vertices: Vec<FVec3>,
     pub normals: Vec<FVec3>,
+    /// texture coordinates of every vertex, empty when the mesh has none.
+    pub uvs: Vec<FVec2>,
     pub indices: Vec<u32>,
     pub materials: Vec<Rc<Material>>,
     pub transform: Transform3D,
+    /// joints and weights for every vertex, when the mesh is rigged.
+    pub skin: Option<SkinData>,
 }
 
 impl StandardModelData {
+    /// panics when the file can't be loaded, see [`StandardModelData::try_new`].
     pub fn new(filepath: &str) -> Vec<Self> {
-        let file = std::fs::File::open(filepath).expect("Failed to open file");
+        Self::try_new(filepath).unwrap_or_else(|error| panic!("couldn't load {filepath}: {error}"))
+    }
+    pub fn try_new(filepath: &str) -> Result<Vec<Self>, MochaError> {
+        let file = std::fs::File::open(filepath)?;
         let reader = std::io::BufReader::new(file);
-        match AnyDocument::from_seekable_reader(reader).expect("Failed to load document") {
-        AnyDocument::V7400(_fbx_ver, doc) => {
-            return Self::parse(doc);
+        match AnyDocument::from_seekable_reader(reader).map_err(|error| MochaError::Parse(error.to_string()))? {
+            AnyDocument::V7400(_fbx_ver, doc) => Self::parse(doc),
+            _ => Err(MochaError::Parse("unsupported fbx version".to_string())),
         }
-        _ => panic!("Got FBX document of unsupported version"),
     }
-    }
-    pub fn parse(document: Box<Document>) -> Vec<Self> {
+    pub fn parse(document: Box<Document>) -> Result<Vec<Self>, MochaError> {
         let tree = document.tree();
         let root = tree.root();
 
@@ -83,14 +107,18 @@ impl StandardModelData {
         for child in root.children() {
             match child.name() {
                 "Objects" => {
-                    _objects = Self::parse_objects(&child);
+                    _objects = Self::parse_objects(&child)?;
                 }
                 "Connections" => {
-                    _connections = Self::parse_connections(&child);
+                    _connections = Self::parse_connections(&child)?;
                 }
                 _ => {}
             }
         }
+        // Skeletons, skins and animations get read on their own since they need the
+        // properties of connections which the rest of the parsing doesn't care about.
+        let skins = skeleton::parse_skins(&root);
+
         let mut model_sheis:Vec<StandardModelData> = Vec::with_capacity(_objects.1.len());
         let mut model_sheis_idx:Vec<i64> = Vec::with_capacity(_objects.1.len());
 
@@ -113,37 +141,53 @@ impl StandardModelData {
             for i in 0..model_sheis.len() {
                 if model_sheis_idx[i] == connection.1 {
                     // parse geometry data
-                    if _objects.0.contains_key(&connection.0) {
-                        let geometry = _objects.0.get(&connection.0).unwrap();
+                    if let Some(geometry) = _objects.0.get(&connection.0) {
                         model_sheis[i].indices = Self::get_indices(geometry);
 
                         model_sheis[i].vertices = geometry.vertices.clone();
+                        model_sheis[i].skin = skins.get(&connection.0).cloned();
                         
                         match &geometry.normal {
                             GeometryNormal::ByPolygonVertex(v) => {
                                 model_sheis[i].normals = vec![FVec3::from(0.0); model_sheis[i].vertices.len()];
                                 let mut j = 0;
-                                for idx in 0..(model_sheis[i].indices.len()-1) {
+                                for idx in 0..model_sheis[i].indices.len().saturating_sub(1) {
                                     let index = model_sheis[i].indices[idx] as usize;
-                                    if model_sheis[i].normals[index] == 0.0 {
-                                        let val = v[j];
-                                        model_sheis[i].normals[index] = val;
+                                    let (Some(normal), Some(val)) = (model_sheis[i].normals.get_mut(index), v.get(j)) else {
+                                        return Err(MochaError::Parse(format!("index {index} of {} is out of bounds", model_sheis[i].tag)));
+                                    };
+                                    if *normal == 0.0 {
+                                        *normal = *val;
                                     }
                                     j += 1;
                                 }
                             } 
                             GeometryNormal::None => {}
                         }
+                        if let GeometryUV::ByPolygonVertex(uvs) = &geometry.uv {
+                            // a vertex shared by polygons with different uvs keeps the first one
+                            let mut seen = vec![false; model_sheis[i].vertices.len()];
+                            model_sheis[i].uvs = vec![FVec2::new(0.0, 0.0); model_sheis[i].vertices.len()];
+                            for (index, uv) in model_sheis[i].indices.iter().zip(uvs) {
+                                let index = *index as usize;
+                                let (Some(seen), Some(vertex)) = (seen.get_mut(index), model_sheis[i].uvs.get_mut(index)) else {
+                                    return Err(MochaError::Parse(format!("index {index} of {} is out of bounds", model_sheis[i].tag)));
+                                };
+                                if !*seen {
+                                    *seen = true;
+                                    *vertex = *uv;
+                                }
+                            }
+                        }
                     } 
                     // parse material data. Keep in mind there can be multiple material data for 1 model.
-                    else if _objects.2.contains_key(&connection.0) {
-                        let material = _objects.2.get(&connection.0).unwrap();
+                    else if let Some(material) = _objects.2.get(&connection.0) {
                         model_sheis[i].materials.push(material.clone());
                     }
                 }
             }
         }
-        model_sheis
+        Ok(model_sheis)
     }
     fn get_indices(geometry: &Geometry) -> Vec<u32>{
         let indices: Vec<u32> = geometry.polygon_indices.iter().map(|index| {
@@ -155,7 +199,7 @@ impl StandardModelData {
         }).collect();
         indices
     }
-    fn parse_objects(node: &NodeHandle) -> (HashMap::<i64, Geometry>, HashMap::<i64,ModelData>, HashMap::<i64,Rc<Material>>) {
+    fn parse_objects(node: &NodeHandle) -> Result<(HashMap::<i64, Geometry>, HashMap::<i64,ModelData>, HashMap::<i64,Rc<Material>>), MochaError> {
         let mut geometries = HashMap::<i64, Geometry>::new();
         let mut models = HashMap::<i64,ModelData>::new();
         let mut materials = HashMap::<i64,Rc<Material>>::new();
@@ -163,37 +207,37 @@ impl StandardModelData {
         for child in node.children() {
             match child.name() {
                 "Geometry" => {
-                    let (id, geo) = Self::parse_geometry(&child);
+                    let (id, geo) = Self::parse_geometry(&child)?;
                     geometries.insert(id, geo);
                 }
                 "Model" => {
-                    let (id, model) = Self::parse_model(&child);
+                    let (id, model) = Self::parse_model(&child)?;
                     models.insert(id, model);
                 }
                 "Material" => {
-                    let (id, material) = Self::parse_material(&child);
+                    let (id, material) = Self::parse_material(&child)?;
                     materials.insert(id, material);
                 }
                 _ => {}
             }
         }
-        (geometries, models, materials)
+        Ok((geometries, models, materials))
     }
-    fn parse_geometry(node: &NodeHandle) -> (i64, Geometry) {
+    fn parse_geometry(node: &NodeHandle) -> Result<(i64, Geometry), MochaError> {
         let mut geo = Geometry {
             tag: String::new(),
             vertices: vec![],
             polygon_indices: vec![],
             edges: vec![],
             normal: GeometryNormal::None,
+            uv: GeometryUV::None,
         };
-        let collection_id = node.attributes()[0].get_i64().unwrap();
-        geo.tag = node.attributes()[1].get_string().unwrap().into();
+        let collection_id = attribute(node, 0, AttributeValue::get_i64)?;
+        geo.tag = attribute(node, 1, AttributeValue::get_string)?.into();
         for child in node.children() {
             match child.name() {
                 "Vertices" => {
-                    let attribute = child.attributes();
-                    let verticesf64 = attribute[0].get_arr_f64().unwrap();
+                    let verticesf64 = attribute(&child, 0, AttributeValue::get_arr_f64)?;
                     let mut verticesf32 = Vec::<FVec3>::with_capacity(verticesf64.len() / 3);
                     let mut i = 0;
                     while (i < verticesf64.len()) {
@@ -207,13 +251,11 @@ impl StandardModelData {
                     geo.vertices = verticesf32;
                 }
                 "PolygonVertexIndex" => {
-                    let attribute = child.attributes();
-                    let indicesi32 = attribute[0].get_arr_i32().unwrap().to_vec();
+                    let indicesi32 = attribute(&child, 0, AttributeValue::get_arr_i32)?.to_vec();
                     geo.polygon_indices = indicesi32;
                 }
                 "Edges" => {
-                    let attribute = child.attributes();
-                    let edges = attribute[0].get_arr_i32().unwrap().to_vec();
+                    let edges = attribute(&child, 0, AttributeValue::get_arr_i32)?.to_vec();
                     geo.edges = edges;
                 }
                 "LayerElementNormal" => {
@@ -222,10 +264,10 @@ impl StandardModelData {
                     for element in child.children() {
                         match element.name() {
                             "MappingInformationType" => {
-                                mapping = element.attributes()[0].get_string().unwrap().into();
+                                mapping = attribute(&element, 0, AttributeValue::get_string)?.into();
                             }
                             "Normals" => {
-                                normals = element.attributes()[0].get_arr_f64().unwrap().to_vec();
+                                normals = attribute(&element, 0, AttributeValue::get_arr_f64)?.to_vec();
                             }
                             _ => {}
                         }
@@ -244,38 +286,69 @@ impl StandardModelData {
                         geo.normal = GeometryNormal::ByPolygonVertex(normalsf32);
                     }
                 }
+                // only the first uv set is used
+                "LayerElementUV" if matches!(geo.uv, GeometryUV::None) => {
+                    let mut uvs = Vec::<f64>::new();
+                    let mut uv_indices = None;
+                    let mut mapping = String::new();
+                    for element in child.children() {
+                        match element.name() {
+                            "MappingInformationType" => {
+                                mapping = attribute(&element, 0, AttributeValue::get_string)?.into();
+                            }
+                            "UV" => {
+                                uvs = attribute(&element, 0, AttributeValue::get_arr_f64)?.to_vec();
+                            }
+                            "UVIndex" => {
+                                uv_indices = Some(attribute(&element, 0, AttributeValue::get_arr_i32)?.to_vec());
+                            }
+                            _ => {}
+                        }
+                    }
+                    if mapping == "ByPolygonVertex" {
+                        let uvs: Vec<FVec2> = uvs.chunks_exact(2).map(|uv| FVec2::new(uv[0] as f32, uv[1] as f32)).collect();
+                        let uvs = match uv_indices {
+                            Some(indices) => indices.iter().map(|index| {
+                                usize::try_from(*index).ok().and_then(|index| uvs.get(index)).copied()
+                                    .ok_or_else(|| MochaError::Parse(format!("uv index {index} of {} is out of bounds", geo.tag)))
+                            }).collect::<Result<_, _>>()?,
+                            None => uvs,
+                        };
+                        geo.uv = GeometryUV::ByPolygonVertex(uvs);
+                    }
+                }
                 _ => {}
             }
         }
-        (collection_id, geo)
+        Ok((collection_id, geo))
     }
-    fn parse_model(node: &NodeHandle) -> (i64, ModelData) {
+    fn parse_model(node: &NodeHandle) -> Result<(i64, ModelData), MochaError> {
         let mut model = ModelData {
             tag: String::new(),
             transform: Transform3D::default(),
         };
-        let collection_id = node.attributes()[0].get_i64().unwrap();
-        model.tag = node.attributes()[1].get_string().unwrap().into();
+        let collection_id = attribute(node, 0, AttributeValue::get_i64)?;
+        model.tag = attribute(node, 1, AttributeValue::get_string)?.into();
         for child in node.children() {
             match child.name() {
                 "Properties70" => {
                     for property in child.children() {
-                        let attribute_name = property.attributes()[0].get_string().unwrap();
+                        let attribute_name = attribute(&property, 0, AttributeValue::get_string)?;
                         match attribute_name {
                             "Translation" => {
-                                model.transform.translation.x = property.attributes()[4].get_f64().unwrap() as f32;
-                                model.transform.translation.y = property.attributes()[5].get_f64().unwrap() as f32;
-                                model.transform.translation.z = property.attributes()[6].get_f64().unwrap() as f32;
+                                model.transform.translation.x = attribute(&property, 4, AttributeValue::get_f64)? as f32;
+                                model.transform.translation.y = attribute(&property, 5, AttributeValue::get_f64)? as f32;
+                                model.transform.translation.z = attribute(&property, 6, AttributeValue::get_f64)? as f32;
                             }
                             "Rotation" => {
-                                model.transform.rotation.x = property.attributes()[4].get_f64().unwrap() as f32;
-                                model.transform.rotation.y = property.attributes()[5].get_f64().unwrap() as f32;
-                                model.transform.rotation.z = property.attributes()[6].get_f64().unwrap() as f32;
+                                model.transform.rotation.x = attribute(&property, 4, AttributeValue::get_f64)? as f32;
+                                model.transform.rotation.y = attribute(&property, 5, AttributeValue::get_f64)? as f32;
+                                model.transform.rotation.z = attribute(&property, 6, AttributeValue::get_f64)? as f32;
                             }
                             "Scaling" => {
-                                model.transform.scale.x = property.attributes()[4].get_f64().unwrap() as f32;
-                                model.transform.scale.y = property.attributes()[5].get_f64().unwrap() as f32;
-                                model.transform.scale.z = property.attributes()[6].get_f64().unwrap() as f32;
+                                model.transform.scale.x = attribute(&property, 4, AttributeValue::get_f64)? as f32;
+                                model.transform.scale.y = attribute(&property, 5, AttributeValue::get_f64)? as f32;
+                                model.transform.scale.z = attribute(&property, 6, AttributeValue::get_f64)? as f32;
                             }
                             _ => {}
                         }
@@ -284,27 +357,27 @@ impl StandardModelData {
                 _ => {}
             }
         }
-        (collection_id, model)
+        Ok((collection_id, model))
     }
-    fn parse_material(node: &NodeHandle) -> (i64, Rc<Material>) {
+    fn parse_material(node: &NodeHandle) -> Result<(i64, Rc<Material>), MochaError> {
         let mut material = Material {
             tag: String::new(),
             diffuse: FVec3::from(0.0),
         };
 
-        let collection_id = node.attributes()[0].get_i64().unwrap();
-        material.tag = node.attributes()[1].get_string().unwrap().into();
+        let collection_id = attribute(node, 0, AttributeValue::get_i64)?;
+        material.tag = attribute(node, 1, AttributeValue::get_string)?.into();
 
         for child in node.children() {
             match child.name() {
                 "Properties70" => {
                     for property in child.children() {
-                        let attribute_name = property.attributes()[0].get_string().unwrap();
+                        let attribute_name = attribute(&property, 0, AttributeValue::get_string)?;
                         match attribute_name {
                             "DiffuseColor" => {
-                                material.diffuse.x = property.attributes()[4].get_f64().unwrap() as f32;
-                                material.diffuse.y = property.attributes()[5].get_f64().unwrap() as f32;
-                                material.diffuse.z = property.attributes()[6].get_f64().unwrap() as f32;
+                                material.diffuse.x = attribute(&property, 4, AttributeValue::get_f64)? as f32;
+                                material.diffuse.y = attribute(&property, 5, AttributeValue::get_f64)? as f32;
+                                material.diffuse.z = attribute(&property, 6, AttributeValue::get_f64)? as f32;
                             }
                             _ => {}
                         }
@@ -313,7 +386,7 @@ impl StandardModelData {
                 _ => {}
             }
         }
-        (collection_id, Rc::new(material))
+        Ok((collection_id, Rc::new(material)))
     }
     /// # parse_connections
     ///
@@ -325,14 +398,13 @@ impl StandardModelData {
     /// 
     /// ### Note: This could very well be wrong since I just gathered this information
     /// ### from parsing various fbx files and looking at the similarities and making assumptions.
-    fn parse_connections(node: &NodeHandle) -> Vec<(i64, i64)> {
+    fn parse_connections(node: &NodeHandle) -> Result<Vec<(i64, i64)>, MochaError> {
         let mut connections: Vec<(i64, i64)> = vec![];
         for child in node.children() {
-            let attributelist = child.attributes();
-            let lhs = attributelist[1].get_i64().unwrap();
-            let rhs = attributelist[2].get_i64().unwrap();
+            let lhs = attribute(&child, 1, AttributeValue::get_i64)?;
+            let rhs = attribute(&child, 2, AttributeValue::get_i64)?;
             connections.push((lhs, rhs));
         }
-        connections
+        Ok(connections)
     }
 }
\ No newline at end of file
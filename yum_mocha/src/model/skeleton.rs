@@ -0,0 +1,649 @@
+use std::{collections::HashMap, rc::Rc};
+
+use drowsed_math::{linear::{FVec3, FMat4}, SquareMatrix};
+use fbxcel_dom::fbxcel::tree::v7400::NodeHandle;
+
+/// fbx stores key times in ticks, this many to a second.
+const TICKS_PER_SECOND: f64 = 46_186_158_000.0;
+/// how many joints can move a single vertex, matching [`super::vertex::Vertex3DSkinned`].
+pub const MAX_INFLUENCES: usize = 4;
+
+/// # Joint
+/// A bone of a [`Skeleton`]. The rest pose is stored the way fbx stores it,
+/// translation, pre rotation and rotation as euler angles in degrees, then scale.
+#[derive(Clone)]
+pub struct Joint {
+    pub name: String,
+    pub parent: Option<usize>,
+    pub translation: FVec3,
+    pub pre_rotation: FVec3,
+    pub rotation: FVec3,
+    pub scale: FVec3,
+    /// takes points from the space of the file into the space of the joint at bind time.
+    pub inverse_bind: FMat4,
+    /// for joints without a parent joint, where the joint hangs from in the space of the file.
+    pub root_parent: FMat4,
+}
+
+/// # JointPose
+/// the local transform of a joint at some point of an animation.
+#[derive(Debug, Clone, Copy)]
+pub struct JointPose {
+    pub translation: FVec3,
+    pub rotation: FVec3,
+    pub scale: FVec3,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JointProperty {
+    Translation,
+    Rotation,
+    Scaling,
+}
+
+/// # AnimationCurve
+/// keys of a single component of a property, sampled linearly between keys.
+#[derive(Debug, Clone, Default)]
+pub struct AnimationCurve {
+    /// in seconds.
+    pub times: Vec<f32>,
+    pub values: Vec<f32>,
+}
+
+impl AnimationCurve {
+    pub fn sample(&self, time: f32) -> Option<f32> {
+        let last = self.times.len().min(self.values.len()).checked_sub(1)?;
+        let next = self.times[..=last].partition_point(|key| *key <= time);
+        if next == 0 {
+            return Some(self.values[0]);
+        }
+        if next > last {
+            return Some(self.values[last]);
+        }
+        let t = (time - self.times[next - 1]) / (self.times[next] - self.times[next - 1]);
+        Some(self.values[next - 1] + (self.values[next] - self.values[next - 1]) * t)
+    }
+    pub fn duration(&self) -> f32 {
+        self.times.last().copied().unwrap_or(0.0)
+    }
+}
+
+/// # JointChannel
+/// the animated x, y and z of one property of a joint, components
+/// without a curve keep their rest value.
+#[derive(Debug, Clone)]
+pub struct JointChannel {
+    pub joint: usize,
+    pub property: JointProperty,
+    pub curves: [Option<AnimationCurve>; 3],
+}
+
+/// # SkeletalClip
+/// an animation stack of the file.
+#[derive(Debug, Clone)]
+pub struct SkeletalClip {
+    pub name: String,
+    /// in seconds.
+    pub duration: f32,
+    pub channels: Vec<JointChannel>,
+}
+
+/// # Skeleton
+/// every joint of a file, parents always come before their children.
+#[derive(Clone, Default)]
+pub struct Skeleton {
+    pub joints: Vec<Joint>,
+    pub clips: Vec<SkeletalClip>,
+}
+
+impl Skeleton {
+    pub fn find_joint(&self, name: &str) -> Option<usize> {
+        self.joints.iter().position(|joint| joint.name == name)
+    }
+    pub fn find_clip(&self, name: &str) -> Option<&SkeletalClip> {
+        self.clips.iter().find(|clip| clip.name == name)
+    }
+    pub fn rest_pose(&self) -> Vec<JointPose> {
+        self.joints.iter().map(|joint| JointPose { translation: joint.translation, rotation: joint.rotation, scale: joint.scale }).collect()
+    }
+    /// # pose
+    /// the local transform of every joint `time` seconds into `clip`.
+    pub fn pose(&self, clip: &SkeletalClip, time: f32) -> Vec<JointPose> {
+        let mut pose = self.rest_pose();
+        for channel in &clip.channels {
+            let Some(joint) = pose.get_mut(channel.joint) else {
+                continue;
+            };
+            let value = match channel.property {
+                JointProperty::Translation => &mut joint.translation,
+                JointProperty::Rotation => &mut joint.rotation,
+                JointProperty::Scaling => &mut joint.scale,
+            };
+            let sample = |i: usize, rest: f32| channel.curves[i].as_ref().and_then(|curve| curve.sample(time)).unwrap_or(rest);
+            *value = FVec3::new(sample(0, value.x), sample(1, value.y), sample(2, value.z));
+        }
+        pose
+    }
+    /// # global_matrices
+    /// where every joint is in the space of the file for the given pose.
+    pub fn global_matrices(&self, pose: &[JointPose]) -> Vec<FMat4> {
+        let mut globals: Vec<FMat4> = Vec::with_capacity(self.joints.len());
+        for (joint, local) in self.joints.iter().zip(pose) {
+            let parent = joint.parent.map_or(joint.root_parent, |parent| globals[parent]);
+            globals.push(parent * joint.local_matrix(local));
+        }
+        globals
+    }
+    /// # skinning_matrices
+    /// for every joint, the matrix moving a vertex from the bind pose to the pose.
+    pub fn skinning_matrices(&self, pose: &[JointPose]) -> Vec<FMat4> {
+        self.global_matrices(pose).into_iter().zip(&self.joints).map(|(global, joint)| global * joint.inverse_bind).collect()
+    }
+}
+
+impl Joint {
+    pub fn local_matrix(&self, pose: &JointPose) -> FMat4 {
+        translation_matrix(pose.translation) * euler_matrix(self.pre_rotation) * euler_matrix(pose.rotation) * scale_matrix(pose.scale)
+    }
+}
+
+/// # VertexInfluence
+/// the joints moving a vertex and how much, weights add up to 1.
+/// Unused slots have a weight of 0.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct VertexInfluence {
+    pub joints: [u32; MAX_INFLUENCES],
+    pub weights: [f32; MAX_INFLUENCES],
+}
+
+/// # SkinData
+/// how a mesh is bound to a skeleton, one influence per vertex.
+#[derive(Clone)]
+pub struct SkinData {
+    pub skeleton: Rc<Skeleton>,
+    pub influences: Vec<VertexInfluence>,
+    /// where the mesh was in the space of the file at bind time.
+    pub geometry_bind: FMat4,
+}
+
+impl SkinData {
+    /// # skinning_matrices
+    /// like [`Skeleton::skinning_matrices`] but in the local space of the mesh,
+    /// so the rest pose comes out as identity matrices.
+    pub fn skinning_matrices(&self, pose: &[JointPose]) -> Vec<FMat4> {
+        let inverse_geometry = inverse(&self.geometry_bind);
+        self.skeleton.skinning_matrices(pose).into_iter().map(|matrix| inverse_geometry * matrix * self.geometry_bind).collect()
+    }
+    /// # skin
+    /// moves `positions` and `normals` of the bind pose by `matrices`, on the cpu.
+    /// Normals aren't corrected for non uniform scaling.
+    pub fn skin(&self, positions: &[FVec3], normals: &[FVec3], matrices: &[FMat4]) -> (Vec<FVec3>, Vec<FVec3>) {
+        let mut skinned_positions = Vec::with_capacity(positions.len());
+        let mut skinned_normals = Vec::with_capacity(normals.len());
+        for (i, position) in positions.iter().enumerate() {
+            let normal = normals.get(i).copied().unwrap_or(FVec3::from(0.0));
+            let Some(influence) = self.influences.get(i).filter(|influence| influence.weights[0] > 0.0) else {
+                skinned_positions.push(*position);
+                skinned_normals.push(normal);
+                continue;
+            };
+            let mut p = FVec3::from(0.0);
+            let mut n = FVec3::from(0.0);
+            for (joint, weight) in influence.joints.iter().zip(influence.weights) {
+                let Some(matrix) = matrices.get(*joint as usize).filter(|_| weight > 0.0) else {
+                    continue;
+                };
+                p = p + transform_point(matrix, *position) * weight;
+                n = n + transform_vector(matrix, normal) * weight;
+            }
+            skinned_positions.push(p);
+            skinned_normals.push(n);
+        }
+        (skinned_positions, skinned_normals)
+    }
+}
+
+fn translation_matrix(t: FVec3) -> FMat4 {
+    FMat4::new(
+        1.0, 0.0, 0.0, 0.0,
+        0.0, 1.0, 0.0, 0.0,
+        0.0, 0.0, 1.0, 0.0,
+        t.x, t.y, t.z, 1.0,
+    )
+}
+fn scale_matrix(s: FVec3) -> FMat4 {
+    FMat4::new(
+        s.x, 0.0, 0.0, 0.0,
+        0.0, s.y, 0.0, 0.0,
+        0.0, 0.0, s.z, 0.0,
+        0.0, 0.0, 0.0, 1.0,
+    )
+}
+/// rotation of fbx euler angles in degrees, applied x first, then y, then z.
+fn euler_matrix(degrees: FVec3) -> FMat4 {
+    let (sx, cx) = degrees.x.to_radians().sin_cos();
+    let (sy, cy) = degrees.y.to_radians().sin_cos();
+    let (sz, cz) = degrees.z.to_radians().sin_cos();
+    FMat4::new(
+        cy * cz, cy * sz, -sy, 0.0,
+        sx * sy * cz - cx * sz, sx * sy * sz + cx * cz, sx * cy, 0.0,
+        cx * sy * cz + sx * sz, cx * sy * sz - sx * cz, cx * cy, 0.0,
+        0.0, 0.0, 0.0, 1.0,
+    )
+}
+fn columns(m: &FMat4) -> [[f32; 4]; 4] {
+    [
+        [m.x.x, m.x.y, m.x.z, m.x.w],
+        [m.y.x, m.y.y, m.y.z, m.y.w],
+        [m.z.x, m.z.y, m.z.z, m.z.w],
+        [m.w.x, m.w.y, m.w.z, m.w.w],
+    ]
+}
+fn from_columns(c: &[[f32; 4]; 4]) -> FMat4 {
+    FMat4::new(
+        c[0][0], c[0][1], c[0][2], c[0][3],
+        c[1][0], c[1][1], c[1][2], c[1][3],
+        c[2][0], c[2][1], c[2][2], c[2][3],
+        c[3][0], c[3][1], c[3][2], c[3][3],
+    )
+}
+/// fbx matrices are 16 doubles, column after column.
+fn matrix_from_fbx(values: &[f64]) -> Option<FMat4> {
+    if values.len() < 16 {
+        return None;
+    }
+    let mut c = [[0.0f32; 4]; 4];
+    for (i, value) in values[..16].iter().enumerate() {
+        c[i / 4][i % 4] = *value as f32;
+    }
+    Some(from_columns(&c))
+}
+/// # inverse
+/// gauss jordan elimination, a matrix that can't be inverted gives the identity.
+pub fn inverse(m: &FMat4) -> FMat4 {
+    // rows of the augmented matrix [m | identity]
+    let c = columns(m);
+    let mut a = [[0.0f32; 8]; 4];
+    for row in 0..4 {
+        for col in 0..4 {
+            a[row][col] = c[col][row];
+        }
+        a[row][4 + row] = 1.0;
+    }
+    for col in 0..4 {
+        let pivot = (col..4).max_by(|i, j| a[*i][col].abs().total_cmp(&a[*j][col].abs())).unwrap();
+        if a[pivot][col].abs() < 1e-12 {
+            return FMat4::identity();
+        }
+        a.swap(col, pivot);
+        let scale = a[col][col];
+        for value in a[col].iter_mut() {
+            *value /= scale;
+        }
+        for row in 0..4 {
+            if row != col {
+                let factor = a[row][col];
+                for k in 0..8 {
+                    a[row][k] -= factor * a[col][k];
+                }
+            }
+        }
+    }
+    let mut inverse = [[0.0f32; 4]; 4];
+    for row in 0..4 {
+        for col in 0..4 {
+            inverse[col][row] = a[row][4 + col];
+        }
+    }
+    from_columns(&inverse)
+}
+pub fn transform_point(m: &FMat4, p: FVec3) -> FVec3 {
+    FVec3::new(
+        m.x.x * p.x + m.y.x * p.y + m.z.x * p.z + m.w.x,
+        m.x.y * p.x + m.y.y * p.y + m.z.y * p.z + m.w.y,
+        m.x.z * p.x + m.y.z * p.y + m.z.z * p.z + m.w.z,
+    )
+}
+pub fn transform_vector(m: &FMat4, v: FVec3) -> FVec3 {
+    FVec3::new(
+        m.x.x * v.x + m.y.x * v.y + m.z.x * v.z,
+        m.x.y * v.x + m.y.y * v.y + m.z.y * v.z,
+        m.x.z * v.x + m.y.z * v.y + m.z.z * v.z,
+    )
+}
+
+struct Cluster {
+    bone: Option<i64>,
+    indices: Vec<i32>,
+    weights: Vec<f64>,
+    transform: Option<FMat4>,
+    transform_link: Option<FMat4>,
+}
+
+struct Bone {
+    name: String,
+    translation: FVec3,
+    pre_rotation: FVec3,
+    rotation: FVec3,
+    scale: FVec3,
+}
+
+/// connections with the property they connect to, `OP` connections of animations
+/// need it to know which component a curve animates.
+struct Connection {
+    child: i64,
+    parent: i64,
+    property: Option<String>,
+}
+
+/// # parse_skins
+/// Reads the skeleton, skins and animation stacks of a document. Returns the
+/// skin of every geometry that has one, keyed by the id of the geometry.
+///
+/// Deformers connect like this, children first:
+/// `Cluster -> Skin -> Geometry`, `LimbNode -> Cluster`, `LimbNode -> LimbNode`.
+/// Animations like this:
+/// `AnimationCurve -> AnimationCurveNode ("d|X") -> LimbNode ("Lcl Rotation")`
+/// and `AnimationCurveNode -> AnimationLayer -> AnimationStack`.
+pub fn parse_skins(root: &NodeHandle) -> HashMap<i64, SkinData> {
+    let mut bones = HashMap::<i64, Bone>::new();
+    let mut skins = Vec::<i64>::new();
+    let mut clusters = HashMap::<i64, Cluster>::new();
+    let mut stacks = HashMap::<i64, String>::new();
+    let mut layers = Vec::<i64>::new();
+    let mut curve_nodes = Vec::<i64>::new();
+    let mut curves = HashMap::<i64, AnimationCurve>::new();
+    let mut bind_pose = HashMap::<i64, FMat4>::new();
+    let mut vertex_counts = HashMap::<i64, usize>::new();
+    let mut connections = Vec::<Connection>::new();
+
+    for child in root.children() {
+        match child.name() {
+            "Objects" => {
+                for object in child.children() {
+                    let attributes = object.attributes();
+                    let Some(id) = attributes.first().and_then(|id| id.get_i64()) else {
+                        continue;
+                    };
+                    let class = attributes.get(2).and_then(|class| class.get_string()).unwrap_or("");
+                    match (object.name(), class) {
+                        ("Model", "LimbNode") => {
+                            bones.insert(id, parse_bone(&object));
+                        }
+                        ("Geometry", "Mesh") => {
+                            let vertices = object.children().find(|node| node.name() == "Vertices")
+                                .and_then(|node| node.attributes().first().and_then(|vertices| vertices.get_arr_f64().map(|vertices| vertices.len() / 3)));
+                            if let Some(vertices) = vertices {
+                                vertex_counts.insert(id, vertices);
+                            }
+                        }
+                        ("Deformer", "Skin") => skins.push(id),
+                        ("Deformer", "Cluster") => {
+                            clusters.insert(id, parse_cluster(&object));
+                        }
+                        ("AnimationStack", _) => {
+                            let name = attributes.get(1).and_then(|name| name.get_string()).unwrap_or("");
+                            stacks.insert(id, name.split("\u{0}\u{1}").next().unwrap_or("").trim_start_matches("AnimStack::").to_string());
+                        }
+                        ("AnimationLayer", _) => layers.push(id),
+                        ("AnimationCurveNode", _) => curve_nodes.push(id),
+                        ("AnimationCurve", _) => {
+                            curves.insert(id, parse_curve(&object));
+                        }
+                        ("Pose", "BindPose") => {
+                            for pose_node in object.children().filter(|node| node.name() == "PoseNode") {
+                                let mut node = None;
+                                let mut matrix = None;
+                                for value in pose_node.children() {
+                                    match value.name() {
+                                        "Node" => node = value.attributes().first().and_then(|node| node.get_i64()),
+                                        "Matrix" => matrix = value.attributes().first().and_then(|matrix| matrix.get_arr_f64()).and_then(matrix_from_fbx),
+                                        _ => {}
+                                    }
+                                }
+                                if let (Some(node), Some(matrix)) = (node, matrix) {
+                                    bind_pose.insert(node, matrix);
+                                }
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            "Connections" => {
+                for connection in child.children() {
+                    let attributes = connection.attributes();
+                    let (Some(child), Some(parent)) = (attributes.get(1).and_then(|id| id.get_i64()), attributes.get(2).and_then(|id| id.get_i64())) else {
+                        continue;
+                    };
+                    let property = attributes.get(3).and_then(|property| property.get_string()).map(String::from);
+                    connections.push(Connection { child, parent, property });
+                }
+            }
+            _ => {}
+        }
+    }
+    if bones.is_empty() {
+        return HashMap::new();
+    }
+
+    // joints ordered parents first, by walking down from the bones without a parent bone
+    let parent_of: HashMap<i64, i64> = connections.iter()
+        .filter(|connection| bones.contains_key(&connection.child) && bones.contains_key(&connection.parent))
+        .map(|connection| (connection.child, connection.parent))
+        .collect();
+    let mut order: Vec<i64> = bones.keys().copied().filter(|id| !parent_of.contains_key(id)).collect();
+    order.sort();
+    let mut next = 0;
+    while next < order.len() {
+        let parent = order[next];
+        let mut children: Vec<i64> = parent_of.iter().filter(|(_, p)| **p == parent).map(|(child, _)| *child).collect();
+        children.sort();
+        order.extend(children);
+        next += 1;
+    }
+    let index_of: HashMap<i64, usize> = order.iter().enumerate().map(|(i, id)| (*id, i)).collect();
+
+    // the bone bind matrices come from the clusters, then the bind pose, then the parent
+    for connection in &connections {
+        if let (true, Some(cluster)) = (bones.contains_key(&connection.child), clusters.get_mut(&connection.parent)) {
+            cluster.bone = Some(connection.child);
+        }
+    }
+    let mut bind_globals: HashMap<i64, FMat4> = clusters.values()
+        .filter_map(|cluster| Some((cluster.bone?, cluster.transform_link?)))
+        .collect();
+    let mut joints = Vec::<Joint>::with_capacity(order.len());
+    for id in &order {
+        let bone = &bones[id];
+        let parent = parent_of.get(id).map(|parent| index_of[parent]);
+        let rest = JointPose { translation: bone.translation, rotation: bone.rotation, scale: bone.scale };
+        let mut joint = Joint {
+            name: bone.name.clone(),
+            parent,
+            translation: bone.translation,
+            pre_rotation: bone.pre_rotation,
+            rotation: bone.rotation,
+            scale: bone.scale,
+            inverse_bind: FMat4::identity(),
+            root_parent: FMat4::identity(),
+        };
+        let local = joint.local_matrix(&rest);
+        let bind_global = match bind_globals.get(id).or(bind_pose.get(id)) {
+            Some(global) => *global,
+            None => match parent_of.get(id).and_then(|parent| bind_globals.get(parent)) {
+                Some(parent_global) => *parent_global * local,
+                None => local,
+            },
+        };
+        bind_globals.insert(*id, bind_global);
+        joint.inverse_bind = inverse(&bind_global);
+        if parent.is_none() {
+            joint.root_parent = bind_global * inverse(&local);
+        }
+        joints.push(joint);
+    }
+
+    let clips = parse_clips(&connections, &stacks, &layers, &curve_nodes, &mut curves, &index_of);
+    let skeleton = Rc::new(Skeleton { joints, clips });
+
+    let mut result = HashMap::new();
+    for skin in skins {
+        let Some(geometry) = connections.iter().find(|connection| connection.child == skin).map(|connection| connection.parent) else {
+            continue;
+        };
+        // indices past the vertices of the geometry can't be skinned, without a count
+        // every index that isn't negative is taken.
+        let vertex_count = vertex_counts.get(&geometry).copied();
+        let mut per_vertex = Vec::<Vec<(u32, f32)>>::new();
+        per_vertex.resize_with(vertex_count.unwrap_or(0), Vec::new);
+        let mut skipped = 0;
+        let mut geometry_bind = None;
+        for cluster in connections.iter().filter(|connection| connection.parent == skin).filter_map(|connection| clusters.get(&connection.child)) {
+            let Some(joint) = cluster.bone.and_then(|bone| index_of.get(&bone)) else {
+                continue;
+            };
+            geometry_bind = geometry_bind.or(cluster.transform);
+            for (index, weight) in cluster.indices.iter().zip(&cluster.weights) {
+                let Ok(index) = usize::try_from(*index) else {
+                    skipped += 1;
+                    continue;
+                };
+                if vertex_count.is_some_and(|count| index >= count) {
+                    skipped += 1;
+                    continue;
+                }
+                if per_vertex.len() <= index {
+                    per_vertex.resize_with(index + 1, Vec::new);
+                }
+                per_vertex[index].push((*joint as u32, *weight as f32));
+            }
+        }
+        if skipped > 0 {
+            log::warn!("skin {skin} has {skipped} weights for vertices its geometry doesn't have, they were skipped");
+        }
+        let influences = per_vertex.into_iter().map(|mut weights| {
+            weights.sort_by(|a, b| b.1.total_cmp(&a.1));
+            weights.truncate(MAX_INFLUENCES);
+            let total: f32 = weights.iter().map(|(_, weight)| weight).sum();
+            let mut influence = VertexInfluence::default();
+            for (i, (joint, weight)) in weights.into_iter().enumerate() {
+                influence.joints[i] = joint;
+                influence.weights[i] = if total > 0.0 { weight / total } else { 0.0 };
+            }
+            influence
+        }).collect();
+        result.insert(geometry, SkinData { skeleton: skeleton.clone(), influences, geometry_bind: geometry_bind.unwrap_or(FMat4::identity()) });
+    }
+    result
+}
+
+fn parse_bone(node: &NodeHandle) -> Bone {
+    let name = node.attributes().get(1).and_then(|name| name.get_string()).unwrap_or("");
+    let mut bone = Bone {
+        name: name.split("\u{0}\u{1}").next().unwrap_or("").trim_start_matches("Model::").to_string(),
+        translation: FVec3::from(0.0),
+        pre_rotation: FVec3::from(0.0),
+        rotation: FVec3::from(0.0),
+        scale: FVec3::from(1.0),
+    };
+    for property in node.children().filter(|child| child.name() == "Properties70").flat_map(|child| child.children()) {
+        let attributes = property.attributes();
+        let value = || FVec3::new(
+            attributes[4].get_f64().unwrap_or(0.0) as f32,
+            attributes[5].get_f64().unwrap_or(0.0) as f32,
+            attributes[6].get_f64().unwrap_or(0.0) as f32,
+        );
+        if attributes.len() < 7 {
+            continue;
+        }
+        match attributes[0].get_string().unwrap_or("") {
+            "Lcl Translation" => bone.translation = value(),
+            "PreRotation" => bone.pre_rotation = value(),
+            "Lcl Rotation" => bone.rotation = value(),
+            "Lcl Scaling" => bone.scale = value(),
+            _ => {}
+        }
+    }
+    bone
+}
+
+fn parse_cluster(node: &NodeHandle) -> Cluster {
+    let mut cluster = Cluster { bone: None, indices: vec![], weights: vec![], transform: None, transform_link: None };
+    for child in node.children() {
+        let Some(attribute) = child.attributes().first() else {
+            continue;
+        };
+        match child.name() {
+            "Indexes" => cluster.indices = attribute.get_arr_i32().map(|indices| indices.to_vec()).unwrap_or_default(),
+            "Weights" => cluster.weights = attribute.get_arr_f64().map(|weights| weights.to_vec()).unwrap_or_default(),
+            "Transform" => cluster.transform = attribute.get_arr_f64().and_then(matrix_from_fbx),
+            "TransformLink" => cluster.transform_link = attribute.get_arr_f64().and_then(matrix_from_fbx),
+            _ => {}
+        }
+    }
+    cluster
+}
+
+fn parse_curve(node: &NodeHandle) -> AnimationCurve {
+    let mut curve = AnimationCurve::default();
+    for child in node.children() {
+        let Some(attribute) = child.attributes().first() else {
+            continue;
+        };
+        match child.name() {
+            "KeyTime" => {
+                curve.times = attribute.get_arr_i64().map_or(vec![], |times| times.iter().map(|time| (*time as f64 / TICKS_PER_SECOND) as f32).collect());
+            }
+            "KeyValueFloat" => {
+                curve.values = attribute.get_arr_f32().map(|values| values.to_vec()).unwrap_or_default();
+            }
+            _ => {}
+        }
+    }
+    curve
+}
+
+fn parse_clips(connections: &[Connection], stacks: &HashMap<i64, String>, layers: &[i64], curve_nodes: &[i64], curves: &mut HashMap<i64, AnimationCurve>, index_of: &HashMap<i64, usize>) -> Vec<SkeletalClip> {
+    let parent = |child: i64, candidates: &dyn Fn(i64) -> bool| connections.iter()
+        .find(|connection| connection.child == child && candidates(connection.parent))
+        .map(|connection| connection.parent);
+
+    let mut clips: HashMap<i64, SkeletalClip> = HashMap::new();
+    for node in curve_nodes {
+        let Some(layer) = parent(*node, &|id| layers.contains(&id)) else {
+            continue;
+        };
+        let Some(stack) = parent(layer, &|id| stacks.contains_key(&id)) else {
+            continue;
+        };
+        let Some((joint, property)) = connections.iter()
+            .filter(|connection| connection.child == *node)
+            .find_map(|connection| Some((*index_of.get(&connection.parent)?, connection.property.as_deref()?)))
+        else {
+            continue;
+        };
+        let property = match property {
+            "Lcl Translation" => JointProperty::Translation,
+            "Lcl Rotation" => JointProperty::Rotation,
+            "Lcl Scaling" => JointProperty::Scaling,
+            _ => continue,
+        };
+        let mut channel = JointChannel { joint, property, curves: [None, None, None] };
+        for connection in connections.iter().filter(|connection| connection.parent == *node) {
+            let component = match connection.property.as_deref() {
+                Some("d|X") => 0,
+                Some("d|Y") => 1,
+                Some("d|Z") => 2,
+                _ => continue,
+            };
+            channel.curves[component] = curves.remove(&connection.child);
+        }
+        let clip = clips.entry(stack).or_insert_with(|| SkeletalClip { name: stacks[&stack].clone(), duration: 0.0, channels: vec![] });
+        clip.duration = channel.curves.iter().flatten().map(AnimationCurve::duration).fold(clip.duration, f32::max);
+        clip.channels.push(channel);
+    }
+    let mut clips: Vec<(i64, SkeletalClip)> = clips.into_iter().collect();
+    clips.sort_by_key(|(id, _)| *id);
+    clips.into_iter().map(|(_, clip)| clip).collect()
+}
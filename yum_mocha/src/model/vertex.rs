@@ -159,6 +159,60 @@ impl Vertex for Vertex3DNormalUV {
         }
     }
 }
+/// vertex of a rigged mesh, moved by up to four joints.
+/// Weights add up to 1, unused joints have a weight of 0.
+#[repr(C, align(16))]
+#[derive(Default, Clone, Copy, Debug)]
+pub struct Vertex3DSkinned {
+    pub pos: FVec3,
+    pub normal: FVec3,
+    pub uv: FVec2,
+    pub joints: [u32; 4],
+    pub weights: [f32; 4],
+}
+impl Vertex for Vertex3DSkinned {
+    fn attribute_description() -> Vec<vk::VertexInputAttributeDescription> {
+        let attr = vk::VertexInputAttributeDescription {
+            location: 0,
+            binding: 0,
+            format: vk::Format::R32G32B32_SFLOAT,
+            offset: offset_of!(Self, pos) as u32,
+        };
+        let attr2 = vk::VertexInputAttributeDescription {
+            location: 1,
+            binding: 0,
+            format: vk::Format::R32G32B32_SFLOAT,
+            offset: offset_of!(Self, normal) as u32,
+        };
+        let attr3 = vk::VertexInputAttributeDescription {
+            location: 2,
+            binding: 0,
+            format: vk::Format::R32G32_SFLOAT,
+            offset: offset_of!(Self, uv) as u32,
+        };
+        let attr4 = vk::VertexInputAttributeDescription {
+            location: 3,
+            binding: 0,
+            format: vk::Format::R32G32B32A32_UINT,
+            offset: offset_of!(Self, joints) as u32,
+        };
+        let attr5 = vk::VertexInputAttributeDescription {
+            location: 4,
+            binding: 0,
+            format: vk::Format::R32G32B32A32_SFLOAT,
+            offset: offset_of!(Self, weights) as u32,
+        };
+        let attributes = vec![attr, attr2, attr3, attr4, attr5];
+        attributes
+    }
+    fn binding_description() -> vk::VertexInputBindingDescription {
+        vk::VertexInputBindingDescription {
+            binding: 0,
+            stride: std::mem::size_of::<Self>() as u32,
+            input_rate: vk::VertexInputRate::VERTEX,
+        }
+    }
+}
 ///
 /// Globaal Vertex Type im using for every type so that I dont need to change every
 /// single value that uses a vertex, only this value.
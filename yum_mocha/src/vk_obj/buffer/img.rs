@@ -1,28 +1,54 @@
 #![allow(unused)]
+use std::sync::PoisonError;
+
 use ash::vk;
-use crate::vk_obj::device;
+use crate::{error::MochaError, vk_obj::device};
 use image;
 use super::raw::Buffer;
-#[derive(Default)]
+/// # DecodedImage
+/// the rgba8 pixels of an image file, decoded without touching the device
+/// so it can happen on another thread than the upload.
+pub struct DecodedImage {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+impl DecodedImage {
+    pub fn open(filepath: &str) -> Result<Self, image::ImageError> {
+        let rgba8 = image::open(filepath)?.into_rgba8();
+        let (width, height) = rgba8.dimensions();
+        Ok(Self { width, height, pixels: rgba8.into_vec() })
+    }
+}
 pub struct ImageTexture {
+    device: std::sync::Arc<device::Device>,
     image: vk::Image,
     view: vk::ImageView,
     sampler: vk::Sampler,
     memory: vk::DeviceMemory,
 }
 impl ImageTexture {
+    /// panics when the image can't be loaded, see [`ImageTexture::try_new`].
     pub fn new(device: std::sync::Arc<device::Device>, filepath: &str) -> Self {
-        let image = image::open(filepath).unwrap();
-        let rgba8 = image.into_rgba8();
-        let vector = rgba8.clone().into_vec();
-        let size =( rgba8.dimensions().0 * rgba8.dimensions().1 * 4) as usize;
+        Self::try_new(device, filepath).unwrap_or_else(|error| panic!("couldn't load {filepath}: {error}"))
+    }
+    pub fn try_new(device: std::sync::Arc<device::Device>, filepath: &str) -> Result<Self, MochaError> {
+        Self::try_from_decoded(device, &DecodedImage::open(filepath)?)
+    }
+    pub fn from_decoded(device: std::sync::Arc<device::Device>, decoded: &DecodedImage) -> Self {
+        Self::try_from_decoded(device, decoded).unwrap()
+    }
+    /// uploads the pixels to a new sampled image.
+    pub fn try_from_decoded(device: std::sync::Arc<device::Device>, decoded: &DecodedImage) -> Result<Self, MochaError> {
+        let vector = decoded.pixels.clone();
+        let size = (decoded.width * decoded.height * 4) as usize;
 
-        let mut temp = Buffer::new(
+        let mut temp = Buffer::try_new(
             device.clone(), size, 
             vk::BufferUsageFlags::TRANSFER_SRC, 
             vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT
-        );
-        temp.mapping(device.clone(), size, 0);
+        )?;
+        temp.try_mapping(device.clone(), size, 0)?;
         temp.append(&vector);
         temp.unmapping(device.clone());
 
@@ -31,8 +57,8 @@ impl ImageTexture {
         let info = vk::ImageCreateInfo {
             image_type: vk::ImageType::TYPE_2D,
             extent: vk::Extent3D {
-                width: rgba8.width(),
-                height: rgba8.height(),
+                width: decoded.width,
+                height: decoded.height,
                 depth: 1
             },
             mip_levels: 1,
@@ -45,9 +71,9 @@ impl ImageTexture {
             samples: vk::SampleCountFlags::TYPE_1,
             ..Default::default()
         };
-        let (image, memory) = device.create_image(&info);
+        let (image, memory) = device.try_create_image(&info)?;
         Self::transition(device.clone(), &image, format, vk::ImageLayout::UNDEFINED, vk::ImageLayout::TRANSFER_DST_OPTIMAL);
-        temp.to_image(device.clone(), &image, rgba8.width(), rgba8.height());
+        temp.to_image(device.clone(), &image, decoded.width, decoded.height);
         Self::transition(device.clone(), &image, format, vk::ImageLayout::TRANSFER_DST_OPTIMAL, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL);
         
         let view_info = vk::ImageViewCreateInfo {
@@ -63,9 +89,28 @@ impl ImageTexture {
             },
             ..Default::default()
         };
-        let view = unsafe { device.device.create_image_view(&view_info, None).unwrap() };
-        let sampler = Self::create_texture_sampler(device.clone());
-        Self { image, view, sampler, memory }
+        let view = match unsafe { device.device.create_image_view(&view_info, None) } {
+            Ok(view) => view,
+            Err(result) => {
+                unsafe {
+                    device.device.destroy_image(image, None);
+                    device.device.free_memory(memory, None);
+                }
+                return Err(result.into());
+            }
+        };
+        let sampler = match Self::create_texture_sampler(device.clone()) {
+            Ok(sampler) => sampler,
+            Err(result) => {
+                unsafe {
+                    device.device.destroy_image_view(view, None);
+                    device.device.destroy_image(image, None);
+                    device.device.free_memory(memory, None);
+                }
+                return Err(result.into());
+            }
+        };
+        Ok(Self { device, image, view, sampler, memory })
     }
     fn transition(device: std::sync::Arc<device::Device>, image: &vk::Image, format: vk::Format, old_layout: vk::ImageLayout, new_layout: vk::ImageLayout) {
         let cmd_buffer = device.single_time_commands();
@@ -112,7 +157,7 @@ impl ImageTexture {
                     &[barrier]) };
         device.end_single_time_commands_graphics(cmd_buffer);
     }
-    fn create_texture_sampler(device: std::sync::Arc<device::Device>) -> vk::Sampler {
+    fn create_texture_sampler(device: std::sync::Arc<device::Device>) -> Result<vk::Sampler, vk::Result> {
         // let properties = unsafe { device.instance.instance.get_physical_device_properties(self.physical_device) };
 
         let info = vk::SamplerCreateInfo {
@@ -136,7 +181,7 @@ impl ImageTexture {
             ..Default::default()
         };
         
-        unsafe { device.device.create_sampler(&info, None).unwrap() }
+        unsafe { device.device.create_sampler(&info, None) }
     }
     pub fn get_info(&self, layout: vk::ImageLayout) -> vk::DescriptorImageInfo {
         vk::DescriptorImageInfo {
@@ -145,4 +190,13 @@ impl ImageTexture {
             sampler: self.sampler
         }
     }
+}
+
+/// the image might still be sampled by a frame in flight, so it waits in the
+/// deletion queue of the device until those frames are done.
+impl Drop for ImageTexture {
+    fn drop(&mut self) {
+        let deletion = device::Deletion::Image { image: self.image, view: self.view, sampler: self.sampler, memory: self.memory };
+        self.device.deletion_queue.lock().unwrap_or_else(PoisonError::into_inner).push(deletion);
+    }
 }
\ No newline at end of file
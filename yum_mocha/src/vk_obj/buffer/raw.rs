@@ -1,7 +1,7 @@
 use ash::vk::{self, Extent3D, Offset3D, ImageSubresourceLayers};
 use std::{sync::Arc, collections::btree_set::Iter};
 
-use crate::vk_obj::device::Device;
+use crate::{error::MochaError, vk_obj::device::Device};
 
 pub struct Buffer<T> {
     device: Arc<Device>,
@@ -27,9 +27,24 @@ impl<T> Buffer<T> {
     /// }
     /// ```
     pub fn new(device: Arc<Device>, size: usize, usage: vk::BufferUsageFlags, properties: vk::MemoryPropertyFlags) -> Self {
-        let buffer = device.allocate_buffer(size, usage, properties);
+        Self::try_new(device, size, usage, properties).unwrap()
+    }
+    /// [`Buffer::new`] returning an error when the buffer or its memory can't be made.
+    pub fn try_new(device: Arc<Device>, size: usize, usage: vk::BufferUsageFlags, properties: vk::MemoryPropertyFlags) -> Result<Self, MochaError> {
+        let (buffer, memory) = Self::allocate(&device, size, usage, properties)?;
+        Ok(Self { buffer, memory, capacity: size as u64, length: 0, mapped: std::ptr::null_mut(), device })
+    }
+    /// a buffer of `size` bytes bound to new memory, nothing is left behind on an error.
+    fn allocate(device: &Arc<Device>, size: usize, usage: vk::BufferUsageFlags, properties: vk::MemoryPropertyFlags) -> Result<(vk::Buffer, vk::DeviceMemory), MochaError> {
+        let buffer = device.try_allocate_buffer(size, usage, properties)?;
         let requirements = unsafe { device.device.get_buffer_memory_requirements(buffer) };
-        let memory_index = Self::get_memory_type_index(device.clone(), properties, requirements);
+        let memory_index = match Self::get_memory_type_index(device.clone(), properties, requirements) {
+            Some(memory_index) => memory_index,
+            None => {
+                unsafe { device.device.destroy_buffer(buffer, None) };
+                return Err(MochaError::NoMemoryType(properties));
+            }
+        };
 
         let alloc_info = vk::MemoryAllocateInfo {
             allocation_size: requirements.size,
@@ -37,26 +52,40 @@ impl<T> Buffer<T> {
             ..Default::default()
         };
         
-        let memory = unsafe { device.device.allocate_memory(&alloc_info, None).unwrap() };
-        unsafe { device.device.bind_buffer_memory(buffer, memory, 0).unwrap() };
-        
-        Self { buffer, memory, capacity: size as u64, length: 0, mapped: [].as_mut_ptr() as *mut T, device: device.clone() }
+        let memory = match unsafe { device.device.allocate_memory(&alloc_info, None) } {
+            Ok(memory) => memory,
+            Err(result) => {
+                unsafe { device.device.destroy_buffer(buffer, None) };
+                return Err(result.into());
+            }
+        };
+        if let Err(result) = unsafe { device.device.bind_buffer_memory(buffer, memory, 0) } {
+            unsafe {
+                device.device.destroy_buffer(buffer, None);
+                device.device.free_memory(memory, None);
+            }
+            return Err(result.into());
+        }
+        Ok((buffer, memory))
     }
-    fn get_memory_type_index(device: Arc<Device>, properties: vk::MemoryPropertyFlags, requirements: vk::MemoryRequirements) -> u32 {
+    fn get_memory_type_index(device: Arc<Device>, properties: vk::MemoryPropertyFlags, requirements: vk::MemoryRequirements) -> Option<u32> {
         let memory_properties = unsafe { device.instance.instance.get_physical_device_memory_properties(device.physical_device) };
-        let i = (0..memory_properties.memory_type_count).find_map(|i| {
+        (0..memory_properties.memory_type_count).find_map(|i| {
             if requirements.memory_type_bits & (1 << i) == (1 << i) &&
 				memory_properties.memory_types[i as usize].property_flags & properties == properties {
 				Some(i)
 			} else {
                 None
             }
-        }).unwrap();
-
-        i
+        })
     }
     pub fn mapping(&mut self, device: Arc<Device>, size: usize, offset: vk::DeviceSize) {
-        self.mapped = unsafe { device.device.map_memory(self.memory, offset, size as u64, vk::MemoryMapFlags::empty()).unwrap() } as *mut T;
+        self.try_mapping(device, size, offset).unwrap()
+    }
+    /// [`Buffer::mapping`] returning an error when the memory can't be mapped.
+    pub fn try_mapping(&mut self, device: Arc<Device>, size: usize, offset: vk::DeviceSize) -> Result<(), MochaError> {
+        self.mapped = unsafe { device.device.map_memory(self.memory, offset, size as u64, vk::MemoryMapFlags::empty())? } as *mut T;
+        Ok(())
     }
     pub fn append(&mut self, data: &Vec<T>) {
         let size = data.len() * std::mem::size_of::<T>();
@@ -76,6 +105,19 @@ impl<T> Buffer<T> {
             }
         }
     }
+    /// overwrites the buffer from the start with `data`, the buffer has to be
+    /// mapped and `data` can't be larger than the capacity, nothing is written otherwise.
+    pub fn write(&mut self, data: &[T]) -> Result<(), MochaError> {
+        if self.mapped.is_null() {
+            return Err(MochaError::Unmapped);
+        }
+        if data.len() > self.capacity() {
+            return Err(MochaError::BufferTooSmall { len: data.len(), capacity: self.capacity() });
+        }
+        unsafe { std::ptr::copy_nonoverlapping(data.as_ptr(), self.mapped, data.len()) };
+        self.length = data.len();
+        Ok(())
+    }
     pub fn unmapping(&self, device: std::sync::Arc<Device>) {
         if !self.mapped.is_null() {
             unsafe { device.device.unmap_memory(self.memory) };
@@ -83,19 +125,8 @@ impl<T> Buffer<T> {
     }
     pub fn from_iter(device: Arc<Device>, usage: vk::BufferUsageFlags, properties: vk::MemoryPropertyFlags, iter: Iter<T>) {
         let size = iter.len() * std::mem::size_of::<T>();
-        let buffer = device.allocate_buffer(size, usage, properties);
-        let requirements = unsafe { device.device.get_buffer_memory_requirements(buffer) };
-        let memory_index = Self::get_memory_type_index(device.clone(), properties, requirements);
-
-        let alloc_info = vk::MemoryAllocateInfo {
-            allocation_size: requirements.size,
-            memory_type_index: memory_index,
-            ..Default::default()
-        };
-        
-        let memory = unsafe { device.device.allocate_memory(&alloc_info, None).unwrap() };
-        unsafe { device.device.bind_buffer_memory(buffer, memory, 0).unwrap() };
-        let mut this = Self { buffer, memory, capacity: size as u64, length: iter.len(), mapped: [].as_mut_ptr() as *mut T, device: device.clone() };
+        let (buffer, memory) = Self::allocate(&device, size, usage, properties).unwrap();
+        let mut this = Self { buffer, memory, capacity: size as u64, length: iter.len(), mapped: std::ptr::null_mut(), device: device.clone() };
         this.mapping(device.clone(), size, 0);
         let mut mapped = this.mapped;
         for val in iter {
@@ -105,27 +136,17 @@ impl<T> Buffer<T> {
         }
     }
     pub fn from_vec(device: Arc<Device>, usage: vk::BufferUsageFlags, properties: vk::MemoryPropertyFlags, vec: &Vec<T>) -> Self {
+        Self::try_from_vec(device, usage, properties, vec).unwrap()
+    }
+    /// [`Buffer::from_vec`] returning an error when the buffer can't be made or mapped.
+    pub fn try_from_vec(device: Arc<Device>, usage: vk::BufferUsageFlags, properties: vk::MemoryPropertyFlags, vec: &Vec<T>) -> Result<Self, MochaError> {
         let size = vec.len() * std::mem::size_of::<T>();
-        let buffer = device.allocate_buffer(size, usage, properties);
-        let requirements = unsafe { device.device.get_buffer_memory_requirements(buffer) };
-        
-        let i = Self::get_memory_type_index(device.clone(), properties, requirements);
-
-        let alloc_info = vk::MemoryAllocateInfo {
-            allocation_size: requirements.size,
-            memory_type_index: i as u32,
-            ..Default::default()
-        };
-        
-        let memory = unsafe { device.device.allocate_memory(&alloc_info, None).unwrap() };
-        unsafe { device.device.bind_buffer_memory(buffer, memory, 0).unwrap() };
-        
-        let mut ret = Self { buffer, memory, capacity: size as u64, length: 0, mapped: [].as_mut_ptr() as *mut T, device: device.clone() };
-        ret.mapping(device.clone(), size, 0);
+        let mut ret = Self::try_new(device.clone(), size, usage, properties)?;
+        ret.try_mapping(device, size, 0)?;
 
         ret.append(vec);
 
-        ret
+        Ok(ret)
     }
     pub fn to_image(&self, device: Arc<Device>, image: &vk::Image, width: u32, height: u32) {
         let command_buffer = device.single_time_commands();
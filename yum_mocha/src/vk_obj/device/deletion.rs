@@ -0,0 +1,71 @@
+use ash::vk;
+
+/// # Deletion
+/// a resource dropped on the cpu that the gpu might still be reading.
+pub enum Deletion {
+    Image { image: vk::Image, view: vk::ImageView, sampler: vk::Sampler, memory: vk::DeviceMemory },
+}
+
+impl Deletion {
+    unsafe fn destroy(self, device: &ash::Device) {
+        match self {
+            Deletion::Image { image, view, sampler, memory } => {
+                device.destroy_sampler(sampler, None);
+                device.destroy_image_view(view, None);
+                device.destroy_image(image, None);
+                device.free_memory(memory, None);
+            }
+        }
+    }
+}
+
+/// # DeletionQueue
+/// Keeps dropped resources alive until every frame that could have used them
+/// finished. Resources are stamped with the frame being recorded when they get
+/// dropped, and [`DeletionQueue::begin_frame`] destroys the ones whose frame's
+/// fence was waited on.
+pub struct DeletionQueue {
+    frame: u64,
+    pending: Vec<(u64, Deletion)>,
+}
+
+impl DeletionQueue {
+    pub fn new() -> Self {
+        Self { frame: 0, pending: vec![] }
+    }
+    pub fn push(&mut self, deletion: Deletion) {
+        self.pending.push((self.frame, deletion));
+    }
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+    /// # begin_frame
+    /// moves on to the next frame, has to be called once the fence of the frame
+    /// about to be recorded was waited on. With `frames_in_flight` frames that
+    /// means every frame up to `frames_in_flight` ago is done.
+    pub fn begin_frame(&mut self, device: &ash::Device, frames_in_flight: u64) {
+        self.frame += 1;
+        let frame = self.frame;
+        let (done, pending): (Vec<_>, Vec<_>) = std::mem::take(&mut self.pending).into_iter()
+            .partition(|(dropped, _)| dropped + frames_in_flight <= frame);
+        self.pending = pending;
+        for (_, deletion) in done {
+            unsafe { deletion.destroy(device) };
+        }
+    }
+    /// destroys everything right away, the device has to be idle.
+    pub fn flush(&mut self, device: &ash::Device) {
+        for (_, deletion) in self.pending.drain(..) {
+            unsafe { deletion.destroy(device) };
+        }
+    }
+}
+
+impl Default for DeletionQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
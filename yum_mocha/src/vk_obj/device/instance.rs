@@ -1,6 +1,6 @@
 // This file will replace the instance code just not yet
 
-use ash::{vk::{InstanceCreateInfo, ApplicationInfo}, Entry, extensions::ext::DebugUtils};
+use ash::{vk::{self, InstanceCreateInfo, ApplicationInfo}, Entry, extensions::ext::DebugUtils};
 use ash_window;
 use raw_window_handle::{self};
 
@@ -19,21 +19,22 @@ pub struct VulkanInstanceBuilder {
     api_version: u32,
     extensions: Vec<*const i8>,
     layers: Vec<*const i8>,
+    /// the first thing that went wrong while building, returned by [`VulkanInstanceBuilder::try_build`].
+    error: Option<vk::Result>,
 }
 impl VulkanInstanceBuilder {
     pub fn new() -> Self {
-        Self { entry: Entry::linked(), api_version: 0, extensions: vec![], layers: vec![] }
+        Self { entry: Entry::linked(), api_version: 0, extensions: vec![], layers: vec![], error: None }
     }
     pub fn set_version(mut self, version: ApiVersion) -> Self {
         self.api_version = version as u32;
         self
     }
     pub fn enable_window_extensions(mut self, display: raw_window_handle::RawDisplayHandle) -> Self {
-        let mut window_required_extensions = ash_window::enumerate_required_extensions(
-            display
-        )
-        .unwrap().to_vec();
-        self.extensions.append(&mut window_required_extensions);
+        match ash_window::enumerate_required_extensions(display) {
+            Ok(window_required_extensions) => self.extensions.extend_from_slice(window_required_extensions),
+            Err(result) => { self.error.get_or_insert(result); }
+        }
         self
     }
     pub fn enable_debugging(mut self) -> Self {
@@ -48,8 +49,14 @@ impl VulkanInstanceBuilder {
         self.layers.push(indexing_layers.as_ptr() as *const i8);
         self
     }
-    pub fn build(mut self) -> VulkanInstance {
-        
+    pub fn build(self) -> VulkanInstance {
+        self.try_build().unwrap()
+    }
+    /// [`VulkanInstanceBuilder::build`] returning what went wrong instead of panicking.
+    pub fn try_build(self) -> Result<VulkanInstance, vk::Result> {
+        if let Some(result) = self.error {
+            return Err(result);
+        }
         let app_info = ApplicationInfo {
             api_version: self.api_version,
             ..Default::default()
@@ -63,9 +70,9 @@ impl VulkanInstanceBuilder {
             p_application_info: &app_info,
             ..Default::default()
         };
-        let instance = unsafe { self.entry.create_instance(&create_info, None).unwrap() }; 
+        let instance = unsafe { self.entry.create_instance(&create_info, None)? };
 
-        VulkanInstance { instance: instance }
+        Ok(VulkanInstance { instance: instance })
     }
 }
 
@@ -3,9 +3,13 @@ use ash::{vk::{self, Extent2D}, Entry};
 // mod instance;
 mod replacedevice;
 mod instance;
+mod deletion;
+pub use deletion::*;
 use ash_window;
 use raw_window_handle::{ HasRawDisplayHandle, HasRawWindowHandle};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use crate::error::MochaError;
 
 #[derive(Clone)]
 pub enum WindowOption {
@@ -46,15 +50,24 @@ pub struct Device {
     pub graphics_queue: Option<vk::Queue>,
     pub command_pool: vk::CommandPool,
     // this field is used so that we can drop the surface
-    pub surface_funcs: ash::extensions::khr::Surface
+    pub surface_funcs: ash::extensions::khr::Surface,
+    /// resources dropped while frames might still use them, emptied by the renderer.
+    pub deletion_queue: Mutex<DeletionQueue>,
 }
 impl Device {
+    /// panics when there is no gpu to draw with, see [`Device::try_new`].
     pub fn new(entry: &Entry, window: Arc<winit::window::Window>) -> Arc<Self> {
-        let instance = instance::VulkanInstance::builder().set_version(instance::ApiVersion::Type1_0).enable_debugging().enable_window_extensions(window.raw_display_handle()).build();
-        let surface = Self::create_surface_winit(&entry, &instance.instance, &window);
-        let (physical_device, surface_funcs) = Self::choose_device(&entry, &instance, &surface);
-        let queue_indices = unsafe { Self::queue_family_indices(&physical_device, &instance.instance, &surface_funcs, &surface) };
-        let device = Self::create_device(&instance.instance, &physical_device, &queue_indices );
+        Self::try_new(entry, window).unwrap()
+    }
+    pub fn try_new(entry: &Entry, window: Arc<winit::window::Window>) -> Result<Arc<Self>, MochaError> {
+        let instance = instance::VulkanInstance::builder().set_version(instance::ApiVersion::Type1_0).enable_debugging().enable_window_extensions(window.raw_display_handle()).try_build()?;
+        let surface = Self::create_surface_winit(&entry, &instance.instance, &window)?;
+        let (physical_device, surface_funcs) = Self::choose_device(&entry, &instance, &surface)?;
+        let queue_indices = unsafe { Self::queue_family_indices(&physical_device, &instance.instance, &surface_funcs, &surface)? };
+        let Some(graphics) = queue_indices.graphics else {
+            return Err(MochaError::NoSuitableDevice);
+        };
+        let device = Self::create_device(&instance.instance, &physical_device, graphics)?;
         let present_queue = if let Some(i) = queue_indices.surface {
             let queue = unsafe { device.get_device_queue(i as u32, 0) };
             Some(queue)
@@ -69,58 +82,45 @@ impl Device {
             None
         };
         
-        let command_pool = Self::create_commandpool(&device, queue_indices.graphics.unwrap() as u32);
+        let command_pool = Self::create_commandpool(&device, graphics)?;
 
-        Arc::new(Self { instance, surface, physical_device, queue_indices, device, surface_funcs, present_queue, graphics_queue, command_pool })
+        Ok(Arc::new(Self { instance, surface, physical_device, queue_indices, device, surface_funcs, present_queue, graphics_queue, command_pool, deletion_queue: Mutex::new(DeletionQueue::new()) }))
     }
-    fn create_surface_winit(entry: &Entry, instance: &ash::Instance, window: &winit::window::Window) -> vk::SurfaceKHR {
+    fn create_surface_winit(entry: &Entry, instance: &ash::Instance, window: &winit::window::Window) -> Result<vk::SurfaceKHR, vk::Result> {
         let display = window.raw_display_handle();
         let window_hwnd = window.raw_window_handle();
-        unsafe { ash_window::create_surface(entry, instance, display, window_hwnd, None).unwrap() }
+        unsafe { ash_window::create_surface(entry, instance, display, window_hwnd, None) }
     }
-    fn query_swapchain_support(surface_funcs: &ash::extensions::khr::Surface, physical_device: &vk::PhysicalDevice, surface: &vk::SurfaceKHR) -> SwapchainSupport {
+    fn query_swapchain_support(surface_funcs: &ash::extensions::khr::Surface, physical_device: &vk::PhysicalDevice, surface: &vk::SurfaceKHR) -> Result<SwapchainSupport, vk::Result> {
         let support = unsafe { 
             SwapchainSupport {
-                    capabilities: surface_funcs.get_physical_device_surface_capabilities(*physical_device, *surface).unwrap(),
-                    formats: surface_funcs.get_physical_device_surface_formats(*physical_device, *surface).unwrap(),
-                    present_modes: surface_funcs.get_physical_device_surface_present_modes(*physical_device, *surface).unwrap(),
+                    capabilities: surface_funcs.get_physical_device_surface_capabilities(*physical_device, *surface)?,
+                    formats: surface_funcs.get_physical_device_surface_formats(*physical_device, *surface)?,
+                    present_modes: surface_funcs.get_physical_device_surface_present_modes(*physical_device, *surface)?,
             } 
         };
-        support
+        Ok(support)
     }
-    pub fn swapchain_support(&self) -> SwapchainSupport {
+    pub fn swapchain_support(&self) -> Result<SwapchainSupport, vk::Result> {
         Self::query_swapchain_support(&self.surface_funcs, &self.physical_device, &self.surface)
     }
-    fn choose_device(entry: &Entry, instance: &instance::VulkanInstance, surface: &vk::SurfaceKHR) -> (vk::PhysicalDevice, ash::extensions::khr::Surface) {
-        let devices = unsafe { instance.instance.enumerate_physical_devices().unwrap() };
+    /// the first gpu with a queue family that can draw and present to `surface`.
+    fn choose_device(entry: &Entry, instance: &instance::VulkanInstance, surface: &vk::SurfaceKHR) -> Result<(vk::PhysicalDevice, ash::extensions::khr::Surface), MochaError> {
+        let devices = unsafe { instance.instance.enumerate_physical_devices()? };
         let surface_funcs = ash::extensions::khr::Surface::new(entry, &instance.instance);
-        let (physical_device) = devices.iter().find_map(|device: &vk::PhysicalDevice| {
-            unsafe { 
-                instance.instance
-                    .get_physical_device_queue_family_properties(*device)
-                    .iter().enumerate()
-                    .find_map(|(index, info)| {
-                    let supports_graphic_and_surface =
-                        info.queue_flags.contains(vk::QueueFlags::GRAPHICS)
-                            && surface_funcs
-                                .get_physical_device_surface_support(
-                                    *device,
-                                    index as u32,
-                                    *surface,
-                                )
-                                .unwrap();
-                    if supports_graphic_and_surface {
-                        Some((*device))
-                    } else {
-                        None
-                    }
-                }) 
+        for device in devices {
+            let families = unsafe { instance.instance.get_physical_device_queue_family_properties(device) };
+            for (index, info) in families.iter().enumerate() {
+                if info.queue_flags.contains(vk::QueueFlags::GRAPHICS)
+                    && unsafe { surface_funcs.get_physical_device_surface_support(device, index as u32, *surface)? } {
+                    return Ok((device, surface_funcs));
+                }
             }
-        }).unwrap();
-        (physical_device, surface_funcs)
+        }
+        Err(MochaError::NoSuitableDevice)
     }
 
-    fn create_device(instance: &ash::Instance, physical_device: &vk::PhysicalDevice, queue_indices: &QueueFamilyIndices) -> ash::Device {
+    fn create_device(instance: &ash::Instance, physical_device: &vk::PhysicalDevice, graphics: u32) -> Result<ash::Device, vk::Result> {
         // Extensions
         let index_extension = b"VK_EXT_descriptor_indexing\0";
 
@@ -144,7 +144,7 @@ impl Device {
         // Creation
 		let mut queue_create_infos: Vec<vk::DeviceQueueCreateInfo> = vec![];
 		let mut unique_queue_families = std::collections::HashSet::new();
-        unique_queue_families.insert(queue_indices.graphics.unwrap());
+        unique_queue_families.insert(graphics);
         for queue_index in unique_queue_families {
             let queue_create_info = vk::DeviceQueueCreateInfo {
                 queue_family_index: queue_index,
@@ -163,22 +163,25 @@ impl Device {
 
             ..Default::default()
         };
-        let device = unsafe { instance.create_device(*physical_device, &create_info, None).unwrap() };
-        device
+        unsafe { instance.create_device(*physical_device, &create_info, None) }
     }
-    fn create_commandpool(device: &ash::Device, queue_index: u32) -> vk::CommandPool {
+    fn create_commandpool(device: &ash::Device, queue_index: u32) -> Result<vk::CommandPool, vk::Result> {
         let create_info = vk::CommandPoolCreateInfo {
             queue_family_index: queue_index,
             flags: vk::CommandPoolCreateFlags::TRANSIENT | vk::CommandPoolCreateFlags::RESET_COMMAND_BUFFER,
             ..Default::default()
         };
-        unsafe { device.create_command_pool(&create_info, None).unwrap() }
+        unsafe { device.create_command_pool(&create_info, None) }
     }
     pub fn create_image(
         &self,
         info: &vk::ImageCreateInfo
     ) -> (vk::Image, vk::DeviceMemory) {
-        let image = unsafe { self.device.create_image(info, None).unwrap() };
+        self.try_create_image(info).unwrap()
+    }
+    /// [`Device::create_image`] returning the error instead of panicking, nothing is left behind on one.
+    pub fn try_create_image(&self, info: &vk::ImageCreateInfo) -> Result<(vk::Image, vk::DeviceMemory), vk::Result> {
+        let image = unsafe { self.device.create_image(info, None)? };
         // Allocate Memory
         let requirements = unsafe { self.device.get_image_memory_requirements(image) };
         let memory_properties = unsafe { self.instance.instance.get_physical_device_memory_properties(self.physical_device) };
@@ -196,10 +199,21 @@ impl Device {
             memory_type_index: memory_type_index,
             ..Default::default()
         };
-        let memory = unsafe { self.device.allocate_memory(&alloc_info, None).unwrap() };
-       
-       unsafe { self.device.bind_image_memory(image, memory, 0).unwrap() };
-        (image, memory)
+        let memory = match unsafe { self.device.allocate_memory(&alloc_info, None) } {
+            Ok(memory) => memory,
+            Err(result) => {
+                unsafe { self.device.destroy_image(image, None) };
+                return Err(result);
+            }
+        };
+        if let Err(result) = unsafe { self.device.bind_image_memory(image, memory, 0) } {
+            unsafe {
+                self.device.destroy_image(image, None);
+                self.device.free_memory(memory, None);
+            }
+            return Err(result);
+        }
+        Ok((image, memory))
     }
     pub unsafe fn find_supported_format(
         &self, candidates: &Vec<vk::Format> ,  tiling: vk::ImageTiling,  features: vk::FormatFeatureFlags) -> vk::Format {
@@ -215,18 +229,18 @@ impl Device {
       }
       panic!("failed to find supported format!");
     }
-    pub unsafe fn queue_family_indices(physical_device: &vk::PhysicalDevice, instance: &ash::Instance, surface_: &ash::extensions::khr::Surface, surface: &ash::vk::SurfaceKHR) -> QueueFamilyIndices {
+    pub unsafe fn queue_family_indices(physical_device: &vk::PhysicalDevice, instance: &ash::Instance, surface_: &ash::extensions::khr::Surface, surface: &ash::vk::SurfaceKHR) -> Result<QueueFamilyIndices, vk::Result> {
         let mut indices = QueueFamilyIndices::default();
 
         let properties = instance.get_physical_device_queue_family_properties(*physical_device);
-        println!("{:#?}", properties);
+        log::debug!("queue families {:#?}", properties);
         let mut i = 0;
         for family in properties {
             if family.queue_count > 0 && (family.queue_flags & vk::QueueFlags::GRAPHICS) == vk::QueueFlags::GRAPHICS {
                 indices.graphics = Some(i);
               }
               // instance.
-              let present_support = ash::extensions::khr::Surface::get_physical_device_surface_support(&surface_, *physical_device, i, *surface).unwrap();
+              let present_support = ash::extensions::khr::Surface::get_physical_device_surface_support(&surface_, *physical_device, i, *surface)?;
               if family.queue_count > 0 && present_support {
                 indices.surface = Some(i);
                 // indices.presentFamilyHasValue = true;
@@ -237,17 +251,19 @@ impl Device {
           
               i += 1;
         }
-        indices
+        Ok(indices)
     }
     pub fn allocate_buffer(&self, size: usize, usage: vk::BufferUsageFlags, properties: vk::MemoryPropertyFlags) -> vk::Buffer {
+        self.try_allocate_buffer(size, usage, properties).unwrap()
+    }
+    pub fn try_allocate_buffer(&self, size: usize, usage: vk::BufferUsageFlags, properties: vk::MemoryPropertyFlags) -> Result<vk::Buffer, vk::Result> {
         let create_info = vk::BufferCreateInfo {
             size: size as u64,
             usage: usage,
             sharing_mode: vk::SharingMode::EXCLUSIVE,
             ..Default::default()
         };
-        let buffer = unsafe { self.device.create_buffer(&create_info, None).unwrap() };
-        buffer
+        unsafe { self.device.create_buffer(&create_info, None) }
     }
     pub fn create_command_buffers(&self, level: vk::CommandBufferLevel, count: u32) -> Vec<vk::CommandBuffer> {
         let info = vk::CommandBufferAllocateInfo {
@@ -303,6 +319,8 @@ impl Device {
 impl Drop for Device {
     fn drop(&mut self) {
         unsafe { 
+            let _ = self.device.device_wait_idle();
+            self.deletion_queue.get_mut().unwrap_or_else(PoisonError::into_inner).flush(&self.device);
             self.surface_funcs.destroy_surface(self.surface, None);
             self.device.destroy_command_pool(self.command_pool, None);
             self.device.destroy_device(None);
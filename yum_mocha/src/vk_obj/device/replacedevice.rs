@@ -20,7 +20,8 @@ pub struct LogicalDeviceBuilder {
     queue_counts: Vec<u32>,
     queue_flags: Vec<Vec<vk::QueueFlags>>,
     exclude_queue_flags: Vec<(Vec<vk::QueueFlags>, u32)>,
-    extensions: Vec<*const i8>
+    extensions: Vec<*const i8>,
+    validation: bool,
 }
 pub struct LogicalDevice {
     pub instance: instance::VulkanInstance,
@@ -45,6 +46,7 @@ impl LogicalDeviceBuilder {
             queue_counts: vec![],
             queue_flags: vec![],
             extensions: vec![],
+            validation: true,
         }
     }
     /// by using this function you are telling Vulkan
@@ -53,6 +55,11 @@ impl LogicalDeviceBuilder {
         self.window = Some(window);
         self
     }
+    /// turns the validation layers on or off, they are on by default.
+    pub fn enable_validation(mut self, validation: bool) -> Self {
+        self.validation = validation;
+        self
+    }
     pub fn add_swapchain_extension(mut self) -> Self {
         self.extensions.push(ash::extensions::khr::Swapchain::name().as_ptr());
         self
@@ -89,8 +96,10 @@ impl LogicalDeviceBuilder {
 
         let mut surface_extensions = false;
         let mut instancebuilder = instance::VulkanInstance::builder()
-            .set_version(instance::ApiVersion::Type1_0)
-            .enable_debugging();
+            .set_version(instance::ApiVersion::Type1_0);
+        if self.validation {
+            instancebuilder = instancebuilder.enable_debugging();
+        }
         
         let (instance, surface) = if let Some(window) = self.window {
             instancebuilder = instancebuilder.enable_window_extensions((*window).raw_display_handle());
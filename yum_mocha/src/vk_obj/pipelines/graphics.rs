@@ -11,14 +11,14 @@ use std::marker::PhantomData;
 use std::ops::Index;
 use std::sync::Arc;
 
-use super::create_shader_module;
+use super::{try_create_shader_module, ShaderModuleError};
 
 pub struct GraphicsPipelines {
     pub pipelines: Vec<vk::Pipeline>,
     mods: Vec<vk::ShaderModule>,
     device: std::sync::Arc<device::Device>,
 }
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct GraphicsPipelineInfo
 {
     pub vertex_filepath: String,
@@ -29,6 +29,8 @@ pub struct GraphicsPipelineInfo
     pub layout: vk::PipelineLayout,
     pub renderpass: vk::RenderPass,
     pub subpass: u32,
+    /// samples of the render pass attachments, empty is one sample.
+    pub samples: vk::SampleCountFlags,
 }
 #[derive(Default, Clone)]
 struct GraphicsPipelineData {
@@ -42,6 +44,7 @@ struct GraphicsPipelineData {
     layout: Vec<vk::PipelineLayout>,
     render_pass: Vec<vk::RenderPass>,
     subpass: Vec<u32>,
+    samples: Vec<vk::SampleCountFlags>,
     input_assembly_state: Vec<vk::PipelineInputAssemblyStateCreateInfo>,
     multisample_state: Vec<vk::PipelineMultisampleStateCreateInfo>,
     depth_stencil: Vec<vk::PipelineDepthStencilStateCreateInfo>,
@@ -61,6 +64,8 @@ impl GraphicsPipelineBuilder {
         GraphicsPipelineBuilder::default()
     }
     pub fn subpass(mut self, subpass: u32) -> Self {self.data.subpass.push(subpass); self }
+    /// has to match the samples of the render pass, see [`GraphicsPipelineInfo::samples`].
+    pub fn multisampling(mut self, samples: vk::SampleCountFlags) -> Self {self.data.samples.push(samples); self }
     pub fn rasterization(mut self, polygon_mode: vk::PolygonMode, culling: vk::CullModeFlags) -> Self {
         self.data.rasterization_state.push(vk::PipelineRasterizationStateCreateInfo {
             depth_clamp_enable: 0,  // VK_FALSE
@@ -116,6 +121,10 @@ impl GraphicsPipelineBuilder {
         self
     }
     pub fn push_info(mut self) -> Self {
+        let samples = match self.data.samples.last() {
+            Some(samples) if !samples.is_empty() => *samples,
+            _ => vk::SampleCountFlags::TYPE_1,
+        };
         self.data.input_assembly_state.push(vk::PipelineInputAssemblyStateCreateInfo {
             topology: vk::PrimitiveTopology::TRIANGLE_LIST,
             primitive_restart_enable: 0, // VK_FALSE
@@ -129,7 +138,7 @@ impl GraphicsPipelineBuilder {
         });
         self.data.multisample_state.push(vk::PipelineMultisampleStateCreateInfo {
             sample_shading_enable: 0, // VK_FALSE
-            rasterization_samples: vk::SampleCountFlags::TYPE_1,
+            rasterization_samples: samples,
             min_sample_shading: 1.0,
             alpha_to_coverage_enable: 0, // VK_FALSE
             alpha_to_one_enable: 0, // VK_FALSE
@@ -190,17 +199,46 @@ impl GraphicsPipelineBuilder {
         self.create_infos.push(create_info);
         self
     }
-    pub fn build(self, device: std::sync::Arc<Device>, cache: vk::PipelineCache) -> GraphicsPipelines{
-        let pipelines = unsafe { device.device.create_graphics_pipelines(cache, &self.create_infos, None).unwrap() };
-        GraphicsPipelines { pipelines, device, mods: self.unique_modules }
+    /// # build
+    /// creates the pipelines of every pushed info. When that fails the shader
+    /// modules given to the builder and any pipeline that did get created are destroyed.
+    pub fn build(self, device: std::sync::Arc<Device>, cache: vk::PipelineCache) -> Result<GraphicsPipelines, vk::Result> {
+        match unsafe { device.device.create_graphics_pipelines(cache, &self.create_infos, None) } {
+            Ok(pipelines) => Ok(GraphicsPipelines { pipelines, device, mods: self.unique_modules }),
+            Err((pipelines, result)) => {
+                unsafe {
+                    for pipeline in pipelines.into_iter().filter(|pipeline| *pipeline != vk::Pipeline::null()) {
+                        device.device.destroy_pipeline(pipeline, None);
+                    }
+                    for shader in self.unique_modules {
+                        device.device.destroy_shader_module(shader, None);
+                    }
+                }
+                Err(result)
+            }
+        }
     }
 }
 
 impl GraphicsPipelines {
     pub fn new<T>(device: Arc<device::Device>, info: &GraphicsPipelineInfo) -> Self
     where T: Vertex {
-        let vertex = create_shader_module(&device.device, &info.vertex_filepath);
-        let fragment = create_shader_module(&device.device, &info.fragment_filepath);
+        Self::try_new::<T>(device, info).unwrap()
+    }
+    /// # try_new
+    /// [`GraphicsPipelines::new`] returning an error when the shaders can't be
+    /// loaded or the pipelines can't be created instead of panicking, for
+    /// rebuilding them while the engine runs.
+    pub fn try_new<T>(device: Arc<device::Device>, info: &GraphicsPipelineInfo) -> Result<Self, ShaderModuleError>
+    where T: Vertex {
+        let vertex = try_create_shader_module(&device.device, &info.vertex_filepath)?;
+        let fragment = match try_create_shader_module(&device.device, &info.fragment_filepath) {
+            Ok(fragment) => fragment,
+            Err(error) => {
+                unsafe { device.device.destroy_shader_module(vertex, None) };
+                return Err(error);
+            }
+        };
         let stages = vec![
             vk::PipelineShaderStageCreateInfo {
             module: vertex,
@@ -215,7 +253,7 @@ impl GraphicsPipelines {
             ..Default::default()
             }
         ];
-        {
+        let pipelines = {
             let binding = T::binding_description();
             let attribute = T::attribute_description();
             GraphicsPipelineBuilder::new()
@@ -226,13 +264,16 @@ impl GraphicsPipelines {
             .pipeline_layout(info.layout)
             .render_pass(info.renderpass)
             .subpass(info.subpass)
+            .multisampling(info.samples)
             .rasterization(vk::PolygonMode::FILL, vk::CullModeFlags::NONE)
             .vertex_input_state::<T>(&binding, &attribute)
             .push_info()
             // .rasterization(vk::PolygonMode::LINE, vk::CullModeFlags::NONE)
             // .push_info()
             .build(device.clone(), vk::PipelineCache::null())
-        }
+            .map_err(ShaderModuleError::Vulkan)?
+        };
+        Ok(pipelines)
     }
 
     pub fn builder() -> GraphicsPipelineBuilder {
@@ -1,20 +1,55 @@
-use std::io::Read;
+use std::{fmt, io::Read};
 pub mod graphics;
 pub mod compute;
 use ash::vk;
 
+#[derive(Debug)]
+pub enum ShaderModuleError {
+    Io(std::io::Error),
+    /// the file isn't SPIR-V, its length has to be a multiple of 4 starting with the magic number.
+    NotSpirv,
+    Vulkan(vk::Result),
+}
+
+impl fmt::Display for ShaderModuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShaderModuleError::Io(error) => write!(f, "couldn't read the shader: {error}"),
+            ShaderModuleError::NotSpirv => write!(f, "the shader isn't SPIR-V"),
+            ShaderModuleError::Vulkan(result) => write!(f, "couldn't create the shader module: {result}"),
+        }
+    }
+}
+
+impl std::error::Error for ShaderModuleError {}
+
+impl From<std::io::Error> for ShaderModuleError {
+    fn from(error: std::io::Error) -> Self {
+        ShaderModuleError::Io(error)
+    }
+}
+
 pub fn create_shader_module(device: &ash::Device, path: &str) -> vk::ShaderModule {
-    let file = std::fs::File::options().read(true).open(path).unwrap();
+    try_create_shader_module(device, path).unwrap()
+}
+
+/// [`create_shader_module`] for shaders that might not be there or be broken, like while they're being edited.
+pub fn try_create_shader_module(device: &ash::Device, path: &str) -> Result<vk::ShaderModule, ShaderModuleError> {
+    let file = std::fs::File::options().read(true).open(path)?;
     let mut reader = std::io::BufReader::new(file);
     let mut buf = Vec::new();
 
-    reader.read_to_end(&mut buf).unwrap();
+    reader.read_to_end(&mut buf)?;
+    if buf.len() % 4 != 0 || buf.get(..4) != Some(&[0x03, 0x02, 0x23, 0x07]) {
+        return Err(ShaderModuleError::NotSpirv);
+    }
+    // the bytes of the file aren't necessarily aligned for u32
+    let code: Vec<u32> = buf.chunks_exact(4).map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]])).collect();
 
     let create_info = vk::ShaderModuleCreateInfo {
         code_size: buf.len(),
-        p_code: buf.as_ptr() as *const u32,
+        p_code: code.as_ptr(),
         ..Default::default()
     };
-    let shader_module = unsafe { device.create_shader_module(&create_info, None).unwrap() };
-    shader_module
+    unsafe { device.create_shader_module(&create_info, None) }.map_err(ShaderModuleError::Vulkan)
 }
\ No newline at end of file
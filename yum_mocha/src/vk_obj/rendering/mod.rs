@@ -4,9 +4,30 @@ mod swapchain;
 pub mod mesh;
 pub mod batcher;
 pub mod render_queue;
-use crate::{vk_obj::device };
+pub mod timestamps;
+use std::sync::PoisonError;
+
+use crate::{error::MochaError, vk_obj::device };
+
+use self::timestamps::{GpuSpan, GpuTimestamps};
 
 use super::device::WindowOption;
+
+/// # RenderOptions
+/// how the swapchain of a [`Renderer`] gets made, kept for when it is recreated.
+#[derive(Debug, Clone)]
+pub struct RenderOptions {
+    /// present modes in order of preference, FIFO is used when none are supported.
+    pub present_modes: Vec<vk::PresentModeKHR>,
+    /// samples per pixel, lowered to what the device supports. `TYPE_1` turns msaa off.
+    pub samples: vk::SampleCountFlags,
+}
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self { present_modes: vec![vk::PresentModeKHR::MAILBOX], samples: vk::SampleCountFlags::TYPE_1 }
+    }
+}
+
 pub struct Renderer {
     pub swapchain: swapchain::Swapchain,
     pub command_buffers: Vec<vk::CommandBuffer>,
@@ -14,26 +35,52 @@ pub struct Renderer {
     device: std::sync::Arc<device::Device>,
     pub window: WindowOption,
     pub clear_value: vk::ClearColorValue,
+    options: RenderOptions,
+    /// `None` until [`Renderer::enable_timestamps`], times the render pass of every frame.
+    pub timestamps: Option<GpuTimestamps>,
+    render_pass_span: Option<usize>,
+    gpu_spans: Vec<GpuSpan>,
 }
 impl Renderer {
     pub fn new(device: std::sync::Arc<device::Device>, window: WindowOption) -> Self {
+        Self::with_options(device, window, RenderOptions::default())
+    }
+    pub fn with_options(device: std::sync::Arc<device::Device>, window: WindowOption, options: RenderOptions) -> Self {
+        Self::try_with_options(device, window, options).unwrap()
+    }
+    /// [`Renderer::with_options`] returning an error when the swapchain can't be made.
+    pub fn try_with_options(device: std::sync::Arc<device::Device>, window: WindowOption, options: RenderOptions) -> Result<Self, MochaError> {
         let extent = window.get_extent2d();
 
-        let swapchain = swapchain::Swapchain::new(device.clone(), extent, SwapchainKHR::null());// , SwapchainKHR::null());
+        let swapchain = swapchain::Swapchain::try_new(device.clone(), extent, SwapchainKHR::null(), &options)?;
         
-        let command_buffers = Self::create_command_buffers(device.clone());
+        let command_buffers = Self::create_command_buffers(device.clone())?;
         
-        Self { swapchain, command_buffers, image_index: 0, device: device.clone(), window, clear_value: vk::ClearColorValue {float32: [0.0, 0.0, 0.0, 1.0] } }
+        Ok(Self { swapchain, command_buffers, image_index: 0, device: device.clone(), window, clear_value: vk::ClearColorValue {float32: [0.0, 0.0, 0.0, 1.0] }, options, timestamps: None, render_pass_span: None, gpu_spans: vec![] })
+    }
+    pub fn options(&self) -> &RenderOptions {
+        &self.options
+    }
+    /// # enable_timestamps
+    /// starts timing the render pass on the gpu, see [`Renderer::take_gpu_spans`].
+    pub fn enable_timestamps(&mut self) -> Result<(), MochaError> {
+        if self.timestamps.is_none() {
+            self.timestamps = Some(GpuTimestamps::new(self.device.clone(), swapchain::MAX_FRAMES, 8)?);
+        }
+        Ok(())
     }
-    fn create_command_buffers(device: std::sync::Arc<device::Device>) -> Vec<vk::CommandBuffer> {
+    /// the gpu timings of the frames that finished since the last call.
+    pub fn take_gpu_spans(&mut self) -> Vec<GpuSpan> {
+        std::mem::take(&mut self.gpu_spans)
+    }
+    fn create_command_buffers(device: std::sync::Arc<device::Device>) -> Result<Vec<vk::CommandBuffer>, vk::Result> {
         let alloc_info = vk::CommandBufferAllocateInfo {
             level: vk::CommandBufferLevel::PRIMARY,
             command_pool: device.command_pool,
             command_buffer_count: 2, // Max Frames
             ..Default::default()
         };
-        let command_buffers = unsafe { device.device.allocate_command_buffers(&alloc_info).unwrap() };
-        command_buffers
+        unsafe { device.device.allocate_command_buffers(&alloc_info) }
     }
     pub fn recreate_swapchain(&mut self) {
         let mut window_extent = self.window.get_extent2d();
@@ -43,10 +90,10 @@ impl Renderer {
         unsafe { self.device.device.device_wait_idle().unwrap() };
 
         if (self.swapchain.swapchain ) == SwapchainKHR::null() {
-            self.swapchain = swapchain::Swapchain::new(self.device.clone(), window_extent, SwapchainKHR::null());
+            self.swapchain = swapchain::Swapchain::new(self.device.clone(), window_extent, SwapchainKHR::null(), &self.options);
         } else {
             // Add other things here later
-            self.swapchain  = swapchain::Swapchain::new(self.device.clone(), window_extent, self.swapchain.swapchain);
+            self.swapchain  = swapchain::Swapchain::new(self.device.clone(), window_extent, self.swapchain.swapchain, &self.options);
         }
     }
     pub fn begin_command_buffer(&mut self) -> Result<vk::CommandBuffer, vk::Result> {
@@ -55,8 +102,14 @@ impl Renderer {
             Ok((o, _)) => {
                 self.image_index = o;
                 let command_buffer = self.command_buffers[self.swapchain.current_frame];
+                // the fence of this frame was waited on, what older frames dropped can go now
+                self.device.deletion_queue.lock().unwrap_or_else(PoisonError::into_inner).begin_frame(&self.device.device, swapchain::MAX_FRAMES as u64);
                 let begin_info = vk::CommandBufferBeginInfo::default();
                 unsafe { self.device.device.begin_command_buffer(command_buffer, &begin_info).unwrap() };
+                if let Some(timestamps) = &mut self.timestamps {
+                    let finished = timestamps.begin_frame(command_buffer, self.swapchain.current_frame);
+                    self.gpu_spans.extend(finished);
+                }
                 return Ok(command_buffer);
             }
             Err(ash::vk::Result::ERROR_OUT_OF_DATE_KHR) => {
@@ -68,7 +121,10 @@ impl Renderer {
             }
         }
     }
-    pub fn begin_render_pass(&self, command_buffer: vk::CommandBuffer) {
+    pub fn begin_render_pass(&mut self, command_buffer: vk::CommandBuffer) {
+        if let Some(timestamps) = &mut self.timestamps {
+            self.render_pass_span = timestamps.begin(command_buffer, self.swapchain.current_frame, "render pass");
+        }
         let clear_value = [
             vk::ClearValue {
                 color: self.clear_value,
@@ -114,8 +170,11 @@ impl Renderer {
         self.image_index = image_index;
         suboptimal
     }
-    pub fn end(&self, command_buffer: vk::CommandBuffer) {
+    pub fn end(&mut self, command_buffer: vk::CommandBuffer) {
         unsafe { self.device.device.cmd_end_render_pass(command_buffer) };
+        if let (Some(timestamps), Some(span)) = (&mut self.timestamps, self.render_pass_span.take()) {
+            timestamps.end(command_buffer, self.swapchain.current_frame, span);
+        }
         unsafe { self.device.device.end_command_buffer(command_buffer).unwrap() };
     }
     pub fn get_aspect_ratio(&self) -> f32 {
@@ -2,7 +2,9 @@
 // use insomniac::linear::fvec2::FVec2;
 use ash::{vk::{self, SurfaceFormatKHR, PresentModeKHR, Extent2D, SharingMode, CompositeAlphaFlagsKHR, SwapchainKHR, ImageSubresourceRange, ImageViewType, SampleCountFlags, AttachmentLoadOp, AccessFlags, Extent3D, FenceCreateFlags}, extensions::khr, Instance};
 
-use crate::vk_obj::device;
+use crate::{error::MochaError, vk_obj::device};
+
+use super::RenderOptions;
 #[derive(Default, Clone, Copy)]
 pub struct ImageResource {
     pub image: vk::Image,
@@ -21,7 +23,12 @@ pub struct Swapchain {
     pub image_views: Vec<vk::ImageView>,
     pub renderpass: vk::RenderPass,
     pub depth_format: vk::Format,
+    /// samples per pixel of the color and depth attachments, what was asked
+    /// for in the [`RenderOptions`] or the most the device supports below that.
+    pub samples: SampleCountFlags,
     depth_resources: Vec<ImageResource>,
+    /// the multisampled images resolved into the swapchain images, empty without msaa.
+    color_resources: Vec<ImageResource>,
     pub frambuffers: Vec<vk::Framebuffer>,
     image_available: Vec<vk::Semaphore>, 
     rendering_done: Vec<vk::Semaphore>, 
@@ -32,17 +39,26 @@ pub struct Swapchain {
 pub const MAX_FRAMES: usize = 2;
 
 impl Swapchain {
-    pub fn new(device: std::sync::Arc<device::Device>, extent: Extent2D, old: SwapchainKHR) -> Self {
+    pub fn new(device: std::sync::Arc<device::Device>, extent: Extent2D, old: SwapchainKHR, options: &RenderOptions) -> Self {
+        Self::try_new(device, extent, old, options).unwrap()
+    }
+    pub fn try_new(device: std::sync::Arc<device::Device>, extent: Extent2D, old: SwapchainKHR, options: &RenderOptions) -> Result<Self, MochaError> {
         let swapchain_funcs: khr::Swapchain = khr::Swapchain::new(&device.instance.instance, &device.device);
-        let (swapchain, format, extent, image_count) = Self::create_swapchain(&swapchain_funcs, &device, extent, old);
-        let images = unsafe { swapchain_funcs.get_swapchain_images(swapchain).unwrap() };
-        let image_views = Self::create_image_views(image_count, &images, format.format, &device);
-        let (renderpass, depth_format) = Self::create_renderpass(&device, format.format);
-        let depth_resources = Self::create_depth_resources(&device, image_count, extent, depth_format);
-        let frambuffers = Self::create_framebuffers(&device, image_count, extent, &image_views, &depth_resources, &renderpass);
-        let (image_available, rendering_done, in_flight_fence, in_flight_images) = Self::create_sync_resources(&device, image_count);
+        let (swapchain, format, extent, image_count) = Self::create_swapchain(&swapchain_funcs, &device, extent, old, &options.present_modes)?;
+        let images = unsafe { swapchain_funcs.get_swapchain_images(swapchain)? };
+        let image_views = Self::create_image_views(image_count, &images, format.format, &device)?;
+        let samples = Self::choose_samples(&device, options.samples);
+        let (renderpass, depth_format) = Self::create_renderpass(&device, format.format, samples)?;
+        let depth_resources = Self::create_depth_resources(&device, image_count, extent, depth_format, samples)?;
+        let color_resources = if samples == SampleCountFlags::TYPE_1 {
+            vec![]
+        } else {
+            Self::create_color_resources(&device, image_count, extent, format.format, samples)?
+        };
+        let frambuffers = Self::create_framebuffers(&device, image_count, extent, &image_views, &depth_resources, &color_resources, &renderpass)?;
+        let (image_available, rendering_done, in_flight_fence, in_flight_images) = Self::create_sync_resources(&device, image_count)?;
 
-        Self { 
+        Ok(Self { 
             device, 
             swapchain_funcs, 
             swapchain, 
@@ -54,20 +70,25 @@ impl Swapchain {
             image_views, 
             renderpass, 
             depth_format, 
+            samples,
             depth_resources, 
+            color_resources,
             frambuffers, 
             image_available, 
             rendering_done, 
             in_flight_fence, 
             in_flight_images,
             current_frame: 0,
-        }
+        })
     }
-    fn create_swapchain(swapchain_funcs: &ash::extensions::khr::Swapchain, device: &std::sync::Arc<device::Device>, extent: Extent2D, old: vk::SwapchainKHR) -> (vk::SwapchainKHR, SurfaceFormatKHR, Extent2D, u32) {
-        let details = device.swapchain_support();
+    fn create_swapchain(swapchain_funcs: &ash::extensions::khr::Swapchain, device: &std::sync::Arc<device::Device>, extent: Extent2D, old: vk::SwapchainKHR, preferred: &[PresentModeKHR]) -> Result<(vk::SwapchainKHR, SurfaceFormatKHR, Extent2D, u32), MochaError> {
+        let details = device.swapchain_support()?;
+        if details.formats.is_empty() {
+            return Err(MochaError::NoSuitableDevice);
+        }
 
         let format = Self::choose_format(&details.formats);
-        let present_mode = Self::choose_present_mode(&details.present_modes);
+        let present_mode = Self::choose_present_mode(&details.present_modes, preferred);
         let window_extent = Self::choose_extent(&details.capabilities, extent);
 
         let mut image_count = details.capabilities.min_image_count + 1;
@@ -76,15 +97,9 @@ impl Swapchain {
             image_count = details.capabilities.max_image_count;
 		}
         
-        let (sharing, index_count, indices) = if device.queue_indices.graphics.is_some() && device.queue_indices.surface.is_some() {
-            if device.queue_indices.graphics.unwrap() != device.queue_indices.surface.unwrap() {
-                (vk::SharingMode::CONCURRENT, 2, vec![device.queue_indices.graphics.unwrap(), device.queue_indices.surface.unwrap()])
-            } else {
-                (vk::SharingMode::EXCLUSIVE, 0, vec![])
-            }
-            
-        } else {
-            (vk::SharingMode::EXCLUSIVE, 0, vec![])
+        let (sharing, index_count, indices) = match (device.queue_indices.graphics, device.queue_indices.surface) {
+            (Some(graphics), Some(surface)) if graphics != surface => (vk::SharingMode::CONCURRENT, 2, vec![graphics, surface]),
+            _ => (vk::SharingMode::EXCLUSIVE, 0, vec![]),
         };
 
         let create_info = vk::SwapchainCreateInfoKHR {
@@ -109,11 +124,11 @@ impl Swapchain {
             ..Default::default()
         };
         
-        let swapchain = unsafe { swapchain_funcs.create_swapchain(&create_info, None).unwrap() };
-        (swapchain, format, window_extent, image_count)
+        let swapchain = unsafe { swapchain_funcs.create_swapchain(&create_info, None)? };
+        Ok((swapchain, format, window_extent, image_count))
     }
-    fn create_image_views(image_count: u32, images: &Vec<vk::Image>, format: vk::Format, device: &std::sync::Arc<device::Device>) -> Vec<vk::ImageView> {
-        let views: Vec<vk::ImageView> = images.iter().map(|image| {
+    fn create_image_views(image_count: u32, images: &Vec<vk::Image>, format: vk::Format, device: &std::sync::Arc<device::Device>) -> Result<Vec<vk::ImageView>, vk::Result> {
+        images.iter().map(|image| {
             let create_info = vk::ImageViewCreateInfo {
                 image: *image,
                 format: format,
@@ -128,12 +143,13 @@ impl Swapchain {
                 ..Default::default()
             };
 
-            let view = unsafe { device.device.create_image_view(&create_info, None).unwrap() };
-            view
-        }).collect();
-        views
+            unsafe { device.device.create_image_view(&create_info, None) }
+        }).collect()
     }
-    fn create_renderpass(device: &std::sync::Arc<device::Device>, format: vk::Format) -> (vk::RenderPass, vk::Format) {
+    /// without msaa the color attachment is the swapchain image, with it the
+    /// multisampled color attachment gets resolved into the swapchain image.
+    fn create_renderpass(device: &std::sync::Arc<device::Device>, format: vk::Format, samples: SampleCountFlags) -> Result<(vk::RenderPass, vk::Format), vk::Result> {
+        let multisampled = samples != SampleCountFlags::TYPE_1;
         // FIND DEPTH FORMAT
         let depth_format = Self::find_depth_format(device.clone());
 
@@ -141,7 +157,7 @@ impl Swapchain {
         // * THIS IS GOOD STOP FRIGGIN CHECKING
         let depth_attachment = vk::AttachmentDescription {
             format: depth_format,
-            samples: SampleCountFlags::TYPE_1,
+            samples,
             load_op: vk::AttachmentLoadOp::CLEAR,
             store_op: vk::AttachmentStoreOp::DONT_CARE,
             stencil_load_op: vk::AttachmentLoadOp::DONT_CARE,
@@ -162,8 +178,20 @@ impl Swapchain {
         // * THIS IS GOOD STOP FRIGGIN CHECKING
         let color_attachment = vk::AttachmentDescription {
             format: format,
-            samples: SampleCountFlags::TYPE_1,
+            samples,
             load_op: vk::AttachmentLoadOp::CLEAR,
+            // the samples only live until they are resolved
+            store_op: if multisampled { vk::AttachmentStoreOp::DONT_CARE } else { vk::AttachmentStoreOp::STORE },
+            stencil_store_op: vk::AttachmentStoreOp::DONT_CARE,
+            stencil_load_op: vk::AttachmentLoadOp::DONT_CARE,
+            initial_layout: vk::ImageLayout::UNDEFINED,
+            final_layout: if multisampled { vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL } else { vk::ImageLayout::PRESENT_SRC_KHR },
+            ..Default::default()
+        };
+        let resolve_attachment = vk::AttachmentDescription {
+            format: format,
+            samples: SampleCountFlags::TYPE_1,
+            load_op: vk::AttachmentLoadOp::DONT_CARE,
             store_op: vk::AttachmentStoreOp::STORE,
             stencil_store_op: vk::AttachmentStoreOp::DONT_CARE,
             stencil_load_op: vk::AttachmentLoadOp::DONT_CARE,
@@ -171,6 +199,11 @@ impl Swapchain {
             final_layout: vk::ImageLayout::PRESENT_SRC_KHR,
             ..Default::default()
         };
+        let resolve_ref = vk::AttachmentReference {
+            attachment: 2,
+            layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+            ..Default::default()
+        };
         
         // * THIS IS GOOD STOP FRIGGIN CHECKING
         let color_ref = vk::AttachmentReference {
@@ -185,6 +218,7 @@ impl Swapchain {
             color_attachment_count: 1,
             p_color_attachments: &color_ref,
             p_depth_stencil_attachment: &depth_ref,
+            p_resolve_attachments: if multisampled { &resolve_ref } else { std::ptr::null() },
             ..Default::default()
         };
 
@@ -199,7 +233,8 @@ impl Swapchain {
             ..Default::default()
         };
         // * THIS IS GOOD STOP FRIGGIN CHECKING
-        let attachments = [color_attachment, depth_attachment];
+        let attachments = [color_attachment, depth_attachment, resolve_attachment];
+        let attachments = if multisampled { &attachments[..] } else { &attachments[..2] };
         let create_info = vk::RenderPassCreateInfo {
             attachment_count: attachments.len() as u32,
             p_attachments: attachments.as_ptr(),
@@ -209,10 +244,10 @@ impl Swapchain {
             p_dependencies: &dependency,
             ..Default::default()
         };
-        let renderpass = unsafe { device.device.create_render_pass(&create_info, None).unwrap() };
-        (renderpass, depth_format)
+        let renderpass = unsafe { device.device.create_render_pass(&create_info, None)? };
+        Ok((renderpass, depth_format))
     }
-    fn create_depth_resources(device: &std::sync::Arc<device::Device>,image_count: u32, extent: Extent2D, depth_format: vk::Format) -> Vec<ImageResource> {
+    fn create_depth_resources(device: &std::sync::Arc<device::Device>,image_count: u32, extent: Extent2D, depth_format: vk::Format, samples: SampleCountFlags) -> Result<Vec<ImageResource>, vk::Result> {
         let mut resources: Vec<ImageResource> = vec![ImageResource::default(); image_count as usize];
         for i in 0..resources.len() {
             // Create Image
@@ -228,12 +263,12 @@ impl Swapchain {
                 image_type: vk::ImageType::TYPE_2D,
                 usage: vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT,
                 array_layers: 1,
-                samples: SampleCountFlags::TYPE_1,
+                samples,
                 sharing_mode: vk::SharingMode::EXCLUSIVE,
                 initial_layout: vk::ImageLayout::UNDEFINED,
                 ..Default::default()
             };
-            (resources[i].image, resources[i].memory) = device.create_image(&image_info);
+            (resources[i].image, resources[i].memory) = device.try_create_image(&image_info)?;
         
             // Create Image View
             let view_info = vk::ImageViewCreateInfo {
@@ -250,16 +285,58 @@ impl Swapchain {
                 },
                 ..Default::default()
             };
-            resources[i].view = unsafe { device.device.create_image_view(&view_info, None).unwrap() };
+            resources[i].view = unsafe { device.device.create_image_view(&view_info, None)? };
+        }
+        Ok(resources)
+    }
+    /// multisampled color images for every swapchain image, see [`Swapchain::create_renderpass`].
+    fn create_color_resources(device: &std::sync::Arc<device::Device>, image_count: u32, extent: Extent2D, format: vk::Format, samples: SampleCountFlags) -> Result<Vec<ImageResource>, vk::Result> {
+        let mut resources: Vec<ImageResource> = vec![ImageResource::default(); image_count as usize];
+        for resource in &mut resources {
+            let image_info = vk::ImageCreateInfo {
+                extent: Extent3D {
+                    width: extent.width,
+                    height: extent.height,
+                    depth: 1,
+                },
+                mip_levels: 1,
+                format,
+                tiling: vk::ImageTiling::OPTIMAL,
+                image_type: vk::ImageType::TYPE_2D,
+                usage: vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::TRANSIENT_ATTACHMENT,
+                array_layers: 1,
+                samples,
+                sharing_mode: vk::SharingMode::EXCLUSIVE,
+                initial_layout: vk::ImageLayout::UNDEFINED,
+                ..Default::default()
+            };
+            (resource.image, resource.memory) = device.try_create_image(&image_info)?;
+            let view_info = vk::ImageViewCreateInfo {
+                image: resource.image,
+                view_type: vk::ImageViewType::TYPE_2D,
+                format,
+                subresource_range: ImageSubresourceRange {
+                    aspect_mask: vk::ImageAspectFlags::COLOR,
+                    base_mip_level: 0,
+                    level_count: 1,
+                    base_array_layer: 0,
+                    layer_count: 1,
+                },
+                ..Default::default()
+            };
+            resource.view = unsafe { device.device.create_image_view(&view_info, None)? };
         }
-        resources
+        Ok(resources)
     }
-    fn create_framebuffers(device: &std::sync::Arc<device::Device>, image_count: u32, extent: Extent2D, color: &Vec<vk::ImageView>, depth: &Vec<ImageResource>, renderpass: &vk::RenderPass) -> Vec<vk::Framebuffer> {
+    fn create_framebuffers(device: &std::sync::Arc<device::Device>, image_count: u32, extent: Extent2D, color: &Vec<vk::ImageView>, depth: &Vec<ImageResource>, multisampled: &Vec<ImageResource>, renderpass: &vk::RenderPass) -> Result<Vec<vk::Framebuffer>, vk::Result> {
         let mut frambuffers = vec![vk::Framebuffer::default(); image_count as usize];
 
         // * THIS IS GOOD STOP FRIGGIN CHECKING
         for i in 0..image_count as usize {
-            let attachments = [color[i], depth[i].view];
+            let attachments = match multisampled.get(i) {
+                Some(multisampled) => vec![multisampled.view, depth[i].view, color[i]],
+                None => vec![color[i], depth[i].view],
+            };
             let create_info = vk::FramebufferCreateInfo {
                 render_pass: *renderpass,
                 attachment_count: attachments.len() as u32,
@@ -269,11 +346,11 @@ impl Swapchain {
                 layers: 1,
                 ..Default::default()
             };
-            frambuffers[i] = unsafe { device.device.create_framebuffer(&create_info, None).unwrap() };
+            frambuffers[i] = unsafe { device.device.create_framebuffer(&create_info, None)? };
         }
-        frambuffers
+        Ok(frambuffers)
     }
-    fn create_sync_resources(device: &std::sync::Arc<device::Device>,image_count: u32) -> (Vec<vk::Semaphore>, Vec<vk::Semaphore>, Vec<vk::Fence>, Vec<Option<vk::Fence>>) {
+    fn create_sync_resources(device: &std::sync::Arc<device::Device>,image_count: u32) -> Result<(Vec<vk::Semaphore>, Vec<vk::Semaphore>, Vec<vk::Fence>, Vec<Option<vk::Fence>>), vk::Result> {
         let mut image_available = vec![vk::Semaphore::default(); MAX_FRAMES];
         let mut rendering_done = vec![vk::Semaphore::default(); MAX_FRAMES];
         let mut in_flight_fence = vec![vk::Fence::default(); MAX_FRAMES];
@@ -287,11 +364,11 @@ impl Swapchain {
             ..Default::default()
         };
         for i in 0..MAX_FRAMES {
-            image_available[i] = unsafe { device.device.create_semaphore(&semaphore_info, None).unwrap() };
-            rendering_done[i] = unsafe { device.device.create_semaphore(&semaphore_info, None).unwrap() };
-            in_flight_fence[i] = unsafe { device.device.create_fence(&fence_info, None).unwrap() };
+            image_available[i] = unsafe { device.device.create_semaphore(&semaphore_info, None)? };
+            rendering_done[i] = unsafe { device.device.create_semaphore(&semaphore_info, None)? };
+            in_flight_fence[i] = unsafe { device.device.create_fence(&fence_info, None)? };
         }
-        (image_available, rendering_done, in_flight_fence, in_flight_image)
+        Ok((image_available, rendering_done, in_flight_fence, in_flight_image))
     }
     fn choose_format(formats: &Vec<SurfaceFormatKHR>) -> SurfaceFormatKHR {
         
@@ -304,14 +381,25 @@ impl Swapchain {
         }
         formats[0]
     }
-    fn choose_present_mode(present_modes: &Vec<PresentModeKHR>) -> PresentModeKHR {
-        for mode in present_modes {
-            if *mode == PresentModeKHR::MAILBOX {
+    /// the first of `preferred` the surface supports, FIFO is always supported.
+    fn choose_present_mode(present_modes: &Vec<PresentModeKHR>, preferred: &[PresentModeKHR]) -> PresentModeKHR {
+        for mode in preferred {
+            if present_modes.contains(mode) {
                 return *mode;
             }
         }
         return PresentModeKHR::FIFO;
     }
+    /// the most samples up to `requested` that both color and depth attachments support.
+    fn choose_samples(device: &std::sync::Arc<device::Device>, requested: SampleCountFlags) -> SampleCountFlags {
+        let properties = unsafe { device.instance.instance.get_physical_device_properties(device.physical_device) };
+        let supported = properties.limits.framebuffer_color_sample_counts & properties.limits.framebuffer_depth_sample_counts;
+        let mut samples = requested.as_raw().max(1);
+        while samples > 1 && !supported.contains(SampleCountFlags::from_raw(samples)) {
+            samples /= 2;
+        }
+        SampleCountFlags::from_raw(samples)
+    }
     fn choose_extent(capabilities: &vk::SurfaceCapabilitiesKHR, extent: Extent2D) -> Extent2D {
         if capabilities.current_extent.width != std::u32::MAX {
             return capabilities.current_extent;
@@ -391,6 +479,11 @@ impl Drop for Swapchain {
                 self.device.device.destroy_image_view(self.depth_resources[i].view, None);
                 self.device.device.free_memory(self.depth_resources[i].memory, None);
             }
+            for resource in &self.color_resources {
+                self.device.device.destroy_image_view(resource.view, None);
+                self.device.device.destroy_image(resource.image, None);
+                self.device.device.free_memory(resource.memory, None);
+            }
             for i in 0..MAX_FRAMES {
                 self.device.device.destroy_semaphore(self.rendering_done[i], None);
                 self.device.device.destroy_semaphore(self.image_available[i], None);
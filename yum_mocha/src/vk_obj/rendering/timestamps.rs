@@ -0,0 +1,118 @@
+use std::{sync::Arc, time::{Duration, Instant}};
+
+use ash::vk;
+
+use crate::{error::MochaError, vk_obj::device};
+
+/// # GpuSpan
+/// how long something took on the gpu. The gpu has its own clock, so `start` is
+/// only lined up with the cpu at the start of the frame the span was recorded in.
+#[derive(Debug, Clone)]
+pub struct GpuSpan {
+    pub name: String,
+    pub start: Instant,
+    pub duration: Duration,
+}
+
+/// a span written into a command buffer, its end is the query after `query`.
+struct Recorded {
+    name: String,
+    query: u32,
+    ended: bool,
+}
+
+/// # GpuTimestamps
+/// Times parts of command buffers with timestamp queries. Every frame in flight
+/// has its own range of queries, which are read back the next time that frame
+/// begins, once its fence was waited on and the results are there.
+pub struct GpuTimestamps {
+    device: Arc<device::Device>,
+    pool: vk::QueryPool,
+    /// queries of every frame, two per span.
+    per_frame: u32,
+    /// nanoseconds per tick of a timestamp.
+    period: f64,
+    spans: Vec<Vec<Recorded>>,
+    /// when each frame began recording on the cpu.
+    began: Vec<Instant>,
+}
+
+impl GpuTimestamps {
+    /// room for `spans` spans in each of `frames` frames, fails when the
+    /// graphics queue can't write timestamps.
+    pub fn new(device: Arc<device::Device>, frames: usize, spans: u32) -> Result<Self, MochaError> {
+        let properties = unsafe { device.instance.instance.get_physical_device_properties(device.physical_device) };
+        if properties.limits.timestamp_compute_and_graphics == 0 || properties.limits.timestamp_period == 0.0 {
+            return Err(MochaError::Vulkan(vk::Result::ERROR_FEATURE_NOT_PRESENT));
+        }
+        let per_frame = spans * 2;
+        let info = vk::QueryPoolCreateInfo {
+            query_type: vk::QueryType::TIMESTAMP,
+            query_count: per_frame * frames as u32,
+            ..Default::default()
+        };
+        let pool = unsafe { device.device.create_query_pool(&info, None)? };
+        Ok(Self {
+            device,
+            pool,
+            per_frame,
+            period: properties.limits.timestamp_period as f64,
+            spans: (0..frames).map(|_| vec![]).collect(),
+            began: vec![Instant::now(); frames],
+        })
+    }
+    /// # begin_frame
+    /// has to be recorded at the start of the command buffer of `frame`, outside
+    /// of any render pass. Gives back the spans from the last time `frame` ran.
+    pub fn begin_frame(&mut self, command_buffer: vk::CommandBuffer, frame: usize) -> Vec<GpuSpan> {
+        let finished = self.read(frame);
+        unsafe { self.device.device.cmd_reset_query_pool(command_buffer, self.pool, frame as u32 * self.per_frame, self.per_frame) };
+        self.spans[frame].clear();
+        self.began[frame] = Instant::now();
+        finished
+    }
+    /// starts timing `name`, `None` when the frame is out of queries.
+    pub fn begin(&mut self, command_buffer: vk::CommandBuffer, frame: usize, name: &str) -> Option<usize> {
+        let spans = &mut self.spans[frame];
+        if spans.len() as u32 * 2 >= self.per_frame {
+            return None;
+        }
+        let query = frame as u32 * self.per_frame + spans.len() as u32 * 2;
+        unsafe { self.device.device.cmd_write_timestamp(command_buffer, vk::PipelineStageFlags::TOP_OF_PIPE, self.pool, query) };
+        spans.push(Recorded { name: name.to_string(), query, ended: false });
+        Some(spans.len() - 1)
+    }
+    pub fn end(&mut self, command_buffer: vk::CommandBuffer, frame: usize, span: usize) {
+        let Some(recorded) = self.spans[frame].get_mut(span) else {
+            return;
+        };
+        unsafe { self.device.device.cmd_write_timestamp(command_buffer, vk::PipelineStageFlags::BOTTOM_OF_PIPE, self.pool, recorded.query + 1) };
+        recorded.ended = true;
+    }
+    fn read(&self, frame: usize) -> Vec<GpuSpan> {
+        let mut ticks = vec![];
+        for recorded in self.spans[frame].iter().filter(|recorded| recorded.ended) {
+            let mut data = [0u64; 2];
+            let result = unsafe { self.device.device.get_query_pool_results(self.pool, recorded.query, 2, &mut data, vk::QueryResultFlags::TYPE_64) };
+            if result.is_ok() {
+                ticks.push((recorded.name.clone(), data[0], data[1]));
+            }
+        }
+        // everything is placed relative to the first span of the frame
+        let Some(first) = ticks.iter().map(|(_, start, _)| *start).min() else {
+            return vec![];
+        };
+        let nanos = |ticks: u64| Duration::from_nanos((ticks as f64 * self.period) as u64);
+        ticks.into_iter().map(|(name, start, end)| GpuSpan {
+            name,
+            start: self.began[frame] + nanos(start - first),
+            duration: nanos(end.saturating_sub(start)),
+        }).collect()
+    }
+}
+
+impl Drop for GpuTimestamps {
+    fn drop(&mut self) {
+        unsafe { self.device.device.destroy_query_pool(self.pool, None) };
+    }
+}